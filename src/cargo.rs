@@ -0,0 +1,265 @@
+//! Cargo packets carrying collected resources to the storage hubs
+//!
+//! Collected resources leave their star as one packet per resource type and
+//! hop along connections, always toward a neighbour closer to a hub, until a
+//! hub stores them. Every connection carries a limited amount of cargo at once,
+//! so packets queue at busy junctions, and longer connections take longer to
+//! cross. Packets at a star with no route to a hub wait there until one appears.
+//! Packets belong to the empire that collected them and only travel between its
+//! own stars. Cargo waiting at a star that gets captured is seized by the new
+//! owner, and nothing leaves or enters a blockaded star.
+
+use crate::combat::{blockaded_stars, Fleet};
+use crate::connection::Connection;
+use crate::empire::{EmpireId, RivalEmpires};
+use crate::events::CargoDelivered;
+use crate::graph::RouteDistance;
+use crate::resources::{PlayerResources, ResourceType};
+use crate::star::Star;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+// World units a packet travels per second
+pub const CARGO_SPEED: f32 = 60.0;
+
+// Shortest time to cross a connection, also used for stars without a position
+pub const MIN_LEG_TIME: f32 = 0.5;
+
+// Cargo one connection carries at once, a packet bigger than this only leaves
+// over an empty connection
+pub const CONNECTION_CAPACITY: f32 = 20.0;
+
+// A packet crossing one connection
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CargoLeg {
+    pub connection: Entity,
+    pub to: Entity,
+    pub elapsed: f32,  // Seconds since leaving
+    pub duration: f32, // Seconds to reach `to`
+}
+
+impl CargoLeg {
+    // Share of the connection already crossed, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        (self.elapsed / self.duration).min(1.0)
+    }
+}
+
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct CargoPacket {
+    pub resource_type: ResourceType,
+    pub amount: f32,
+    pub at: Entity,            // Star the packet waits at, or the one it last left
+    pub leg: Option<CargoLeg>, // None while waiting at `at`
+    pub empire: EmpireId,      // Stockpile the cargo is stored in
+}
+
+impl CargoPacket {
+    pub fn new(resource_type: ResourceType, amount: f32, at: Entity, empire: EmpireId) -> Self {
+        Self {
+            resource_type,
+            amount,
+            at,
+            leg: None,
+            empire,
+        }
+    }
+}
+
+// Cargo per resource type that has left `empire`'s stars but not reached a hub yet
+pub fn cargo_in_transit<'a>(
+    packets: impl IntoIterator<Item = &'a CargoPacket>,
+    empire: EmpireId,
+) -> HashMap<ResourceType, f32> {
+    let mut in_transit = HashMap::new();
+    for packet in packets.into_iter().filter(|packet| packet.empire == empire) {
+        *in_transit.entry(packet.resource_type).or_insert(0.0) += packet.amount;
+    }
+    in_transit
+}
+
+// Time to cross the connection between two stars
+pub fn leg_duration(from: Option<&Transform>, to: Option<&Transform>) -> f32 {
+    match (from, to) {
+        (Some(from), Some(to)) => {
+            let length = from
+                .translation
+                .truncate()
+                .distance(to.translation.truncate());
+            (length / CARGO_SPEED).max(MIN_LEG_TIME)
+        }
+        _ => MIN_LEG_TIME,
+    }
+}
+
+// Move packets along their connections, send waiting ones down the least
+// loaded connection toward a hub and store the ones that reached a hub
+pub fn move_cargo(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut packet_query: Query<(Entity, &mut CargoPacket)>,
+    star_query: Query<(&Star, Option<&RouteDistance>, Option<&Transform>)>,
+    connection_query: Query<(Entity, &Connection)>,
+    fleet_query: Query<&Fleet>,
+    mut player_resources: ResMut<PlayerResources>,
+    mut rivals: ResMut<RivalEmpires>,
+    mut delivered_events: EventWriter<CargoDelivered>,
+) {
+    let dt = time.delta_seconds();
+
+    // Connections walked both ways, like supply routes
+    let mut routes: HashMap<Entity, Vec<(Entity, Entity)>> = HashMap::new();
+    for (entity, connection) in &connection_query {
+        routes
+            .entry(connection.from)
+            .or_default()
+            .push((entity, connection.to));
+        routes
+            .entry(connection.to)
+            .or_default()
+            .push((entity, connection.from));
+    }
+
+    let mut load: HashMap<Entity, f32> = HashMap::new();
+    for (_, packet) in &packet_query {
+        if let Some(leg) = packet.leg {
+            *load.entry(leg.connection).or_insert(0.0) += packet.amount;
+        }
+    }
+
+    let distance = |star: Entity| {
+        star_query
+            .get(star)
+            .ok()
+            .and_then(|(_, route, _)| route.and_then(|route| route.0))
+    };
+    let owner = |star: Entity| {
+        star_query
+            .get(star)
+            .ok()
+            .filter(|(star, _, _)| star.is_colonized)
+            .map(|(star, _, _)| star.empire)
+    };
+    let blockaded = blockaded_stars(&fleet_query);
+
+    for (entity, mut packet) in &mut packet_query {
+        if star_query.get(packet.at).is_err() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        if let Some(mut leg) = packet.leg {
+            if connection_query.get(leg.connection).is_err() {
+                // The connection was removed under the packet, it goes back
+                // to the star it left
+                packet.leg = None;
+            } else {
+                leg.elapsed += dt;
+                if leg.elapsed < leg.duration {
+                    packet.leg = Some(leg);
+                    continue;
+                }
+                *load.entry(leg.connection).or_insert(0.0) -= packet.amount;
+                packet.at = leg.to;
+                packet.leg = None;
+            }
+        }
+
+        if let Some(owner) = owner(packet.at).filter(|owner| *owner != packet.empire) {
+            packet.empire = owner;
+        }
+        if blockaded.contains(&packet.at) {
+            continue;
+        }
+        let Some(hops) = distance(packet.at) else {
+            continue;
+        };
+        if hops == 0 {
+            let Some(stockpile) = rivals.stockpile(&mut player_resources, packet.empire) else {
+                commands.entity(entity).despawn();
+                continue;
+            };
+            let stored = stockpile.store(packet.resource_type, packet.amount);
+            if stored > 0.0 && packet.empire.is_player() {
+                delivered_events.send(CargoDelivered {
+                    hub: packet.at,
+                    resource_type: packet.resource_type,
+                    amount: stored,
+                });
+            }
+            packet.amount -= stored;
+            if packet.amount <= 0.0 {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        }
+
+        let next = routes
+            .get(&packet.at)
+            .into_iter()
+            .flatten()
+            .filter(|(_, neighbour)| {
+                distance(*neighbour) == Some(hops - 1)
+                    && owner(*neighbour) == Some(packet.empire)
+                    && !blockaded.contains(neighbour)
+            })
+            .map(|&(connection, neighbour)| {
+                (
+                    connection,
+                    neighbour,
+                    load.get(&connection).copied().unwrap_or(0.0),
+                )
+            })
+            .filter(|(_, _, used)| *used <= 0.0 || used + packet.amount <= CONNECTION_CAPACITY)
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+        if let Some((connection, neighbour, _)) = next {
+            let from_transform = star_query.get(packet.at).ok().and_then(|(_, _, t)| t);
+            let to_transform = star_query.get(neighbour).ok().and_then(|(_, _, t)| t);
+            *load.entry(connection).or_insert(0.0) += packet.amount;
+            packet.leg = Some(CargoLeg {
+                connection,
+                to: neighbour,
+                elapsed: 0.0,
+                duration: leg_duration(from_transform, to_transform),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leg_duration_grows_with_length() {
+        let from = Transform::from_xyz(0.0, 0.0, 0.0);
+        let near = Transform::from_xyz(CARGO_SPEED * 2.0, 0.0, 0.0);
+        let far = Transform::from_xyz(0.0, CARGO_SPEED * 5.0, 0.0);
+        assert_eq!(leg_duration(Some(&from), Some(&near)), 2.0);
+        assert_eq!(leg_duration(Some(&from), Some(&far)), 5.0);
+    }
+
+    #[test]
+    fn short_or_unplaced_legs_take_the_minimum_time() {
+        let from = Transform::from_xyz(0.0, 0.0, 0.0);
+        let close = Transform::from_xyz(1.0, 0.0, 0.0);
+        assert_eq!(leg_duration(Some(&from), Some(&close)), MIN_LEG_TIME);
+        assert_eq!(leg_duration(Some(&from), None), MIN_LEG_TIME);
+    }
+
+    #[test]
+    fn in_transit_sums_an_empire_packets_per_resource() {
+        let star = Entity::from_raw(1);
+        let player = EmpireId::PLAYER;
+        let packets = [
+            CargoPacket::new(ResourceType::Iron, 2.0, star, player),
+            CargoPacket::new(ResourceType::Iron, 3.0, star, player),
+            CargoPacket::new(ResourceType::Water, 1.5, star, player),
+            CargoPacket::new(ResourceType::Food, 4.0, star, EmpireId(1)),
+        ];
+        let in_transit = cargo_in_transit(&packets, player);
+        assert_eq!(in_transit[&ResourceType::Iron], 5.0);
+        assert_eq!(in_transit[&ResourceType::Water], 1.5);
+        assert!(!in_transit.contains_key(&ResourceType::Food));
+    }
+}
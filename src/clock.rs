@@ -0,0 +1,101 @@
+//! Simulation speed: pause, fast-forward and single steps
+//!
+//! Timed game rules run in `FixedUpdate`, so their results do not depend on the
+//! frame rate. The clock only scales the virtual time that feeds the fixed
+//! timestep, presentation code keeps running at full speed while paused.
+
+use bevy::prelude::*;
+use std::time::Duration;
+
+// Speed multipliers the clock cycles through
+pub const SPEEDS: [f32; 4] = [1.0, 2.0, 4.0, 8.0];
+
+// Simulated time advanced by one single step while paused
+pub const STEP_SECONDS: f32 = 0.25;
+
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Default)]
+pub struct SimulationClock {
+    pub paused: bool,
+    speed_index: usize, // Index into SPEEDS
+    pending_steps: u32, // Single steps requested since the last frame
+}
+
+impl SimulationClock {
+    pub fn speed(&self) -> f32 {
+        SPEEDS[self.speed_index]
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    // Next speed up, staying at the fastest one
+    pub fn faster(&mut self) {
+        self.speed_index = (self.speed_index + 1).min(SPEEDS.len() - 1);
+    }
+
+    // Next speed down, staying at 1x
+    pub fn slower(&mut self) {
+        self.speed_index = self.speed_index.saturating_sub(1);
+    }
+
+    // Advance a paused simulation by STEP_SECONDS, ignored while running
+    pub fn step(&mut self) {
+        if self.paused {
+            self.pending_steps += 1;
+        }
+    }
+}
+
+// Apply pause and speed to the virtual clock, from the next frame on
+// Runs right after `TimeSystem`, so a single step can still push this frame's
+// paused virtual clock forward before the fixed timestep reads it
+pub fn apply_simulation_clock(
+    mut clock: ResMut<SimulationClock>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    if clock.paused != virtual_time.is_paused() {
+        if clock.paused {
+            virtual_time.pause();
+        } else {
+            virtual_time.unpause();
+        }
+    }
+    if virtual_time.relative_speed() != clock.speed() {
+        virtual_time.set_relative_speed(clock.speed());
+    }
+
+    if clock.pending_steps > 0 {
+        let steps = std::mem::take(&mut clock.pending_steps);
+        virtual_time.advance_by(Duration::from_secs_f32(STEP_SECONDS * steps as f32));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_stays_within_the_available_speeds() {
+        let mut clock = SimulationClock::default();
+        clock.slower();
+        assert_eq!(clock.speed(), 1.0);
+        for _ in 0..10 {
+            clock.faster();
+        }
+        assert_eq!(clock.speed(), 8.0);
+        clock.slower();
+        assert_eq!(clock.speed(), 4.0);
+    }
+
+    #[test]
+    fn steps_only_queue_while_paused() {
+        let mut clock = SimulationClock::default();
+        clock.step();
+        assert_eq!(clock.pending_steps, 0);
+        clock.toggle_pause();
+        clock.step();
+        clock.step();
+        assert_eq!(clock.pending_steps, 2);
+    }
+}
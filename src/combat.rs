@@ -0,0 +1,331 @@
+//! Warships, fleets and battles over contested stars
+//!
+//! The warships stationed at a star are its garrison and defend it. They can be
+//! sent as a fleet along a connection to a star of another empire, either to
+//! attack it or to blockade it. Battles are resolved with the square law, the
+//! garrison fighting DEFENDER_ADVANTAGE times as hard, so the same fleets always
+//! give the same result. A winning attack captures the star: it changes owner
+//! along with its outbound connections and the survivors become its garrison.
+//! A captured home star stops being one: its captor treats it as any other
+//! colony, and losing it is recorded in the session stats.
+//! A blockaded star neither collects nor ships cargo until the blockading fleet
+//! is gone.
+
+use crate::cargo::leg_duration;
+use crate::connection::Connection;
+use crate::constellation::{ConstellationDissolved, ConstellationTracker, DissolveReason};
+use crate::empire::EmpireId;
+use crate::events::{BattleFought, StarCaptured};
+use crate::star::{Star, UnitType};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+// How much harder a garrison fights than the fleet attacking it
+pub const DEFENDER_ADVANTAGE: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mission {
+    Attack,   // Fight the garrison and capture the star
+    Blockade, // Stay at the star, cutting it off from its empire's network
+}
+
+impl Mission {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mission::Attack => "attack",
+            Mission::Blockade => "blockade",
+        }
+    }
+}
+
+// Warships on their way to a hostile star, or blockading it once arrived
+#[derive(Component, Debug, Clone, PartialEq)]
+pub struct Fleet {
+    pub empire: EmpireId,
+    pub ships: u32,
+    pub mission: Mission,
+    pub from: Entity, // Star the fleet left
+    pub target: Entity,
+    pub connection: Entity, // Connection the fleet travels along
+    pub elapsed: f32,       // Seconds since leaving
+    pub duration: f32,      // Seconds to reach `target`
+}
+
+impl Fleet {
+    pub fn has_arrived(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    // Share of the connection already crossed, from 0 to 1
+    pub fn progress(&self) -> f32 {
+        (self.elapsed / self.duration).min(1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FleetError {
+    NoWarships,
+    NotConnected,
+    NotHostile, // The target is free or already belongs to the same empire
+}
+
+impl fmt::Display for FleetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FleetError::NoWarships => write!(f, "No warships are stationed there"),
+            FleetError::NotConnected => write!(f, "Fleets can only travel along a connection"),
+            FleetError::NotHostile => write!(f, "Fleets can only be sent to another empire's star"),
+        }
+    }
+}
+
+// Warships left on each side once a battle is over, one side is always wiped out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BattleOutcome {
+    pub attackers: u32,
+    pub defenders: u32,
+}
+
+impl BattleOutcome {
+    pub fn attackers_won(&self) -> bool {
+        self.defenders == 0 && self.attackers > 0
+    }
+}
+
+// Lanchester's square law: the stronger side wins and keeps the square root of
+// the difference of the squared strengths, a tie goes to the defenders
+pub fn resolve_battle(attackers: u32, defenders: u32) -> BattleOutcome {
+    let attack = attackers as f32;
+    let defence = defenders as f32 * DEFENDER_ADVANTAGE;
+    if attack > defence {
+        let left = (attack * attack - defence * defence).sqrt().floor() as u32;
+        BattleOutcome {
+            attackers: left.clamp(1, attackers),
+            defenders: 0,
+        }
+    } else {
+        let left = ((defence * defence - attack * attack).sqrt() / DEFENDER_ADVANTAGE).floor();
+        BattleOutcome {
+            attackers: 0,
+            defenders: (left as u32).min(defenders),
+        }
+    }
+}
+
+// Connection between two stars, in either direction
+pub fn connection_between<'a>(
+    connections: impl IntoIterator<Item = (Entity, &'a Connection)>,
+    a: Entity,
+    b: Entity,
+) -> Option<Entity> {
+    connections
+        .into_iter()
+        .find(|(_, connection)| {
+            (connection.from == a && connection.to == b)
+                || (connection.from == b && connection.to == a)
+        })
+        .map(|(entity, _)| entity)
+}
+
+// Stars held under blockade by a fleet that has arrived
+pub fn blockaded_stars<'a>(fleets: impl IntoIterator<Item = &'a Fleet>) -> HashSet<Entity> {
+    fleets
+        .into_iter()
+        .filter(|fleet| fleet.mission == Mission::Blockade && fleet.has_arrived())
+        .map(|fleet| fleet.target)
+        .collect()
+}
+
+// Send every warship stationed at `from` to `target` along `connection`
+// The garrison leaves `from` at once, the caller spawns the returned fleet
+pub fn launch_fleet(
+    from: (Entity, &mut Star, Option<&Transform>),
+    target: (Entity, &Star, Option<&Transform>),
+    connection: Option<Entity>,
+    mission: Mission,
+) -> Result<Fleet, FleetError> {
+    let (from, from_star, from_transform) = from;
+    let (target, target_star, target_transform) = target;
+    if !target_star.is_colonized || target_star.empire == from_star.empire {
+        return Err(FleetError::NotHostile);
+    }
+    let connection = connection.ok_or(FleetError::NotConnected)?;
+    let ships = from_star.units.remove(&UnitType::Warship).unwrap_or(0);
+    if ships == 0 {
+        return Err(FleetError::NoWarships);
+    }
+    Ok(Fleet {
+        empire: from_star.empire,
+        ships,
+        mission,
+        from,
+        target,
+        connection,
+        elapsed: 0.0,
+        // Fleets cross a connection as fast as cargo does
+        duration: leg_duration(from_transform, target_transform),
+    })
+}
+
+fn set_garrison(star: &mut Star, ships: u32) {
+    if ships > 0 {
+        star.units.insert(UnitType::Warship, ships);
+    } else {
+        star.units.remove(&UnitType::Warship);
+    }
+}
+
+// Move fleets along their connections and fight once they arrive
+// Fleets whose mission can no longer be carried out, because their connection
+// was removed or the target star was abandoned, go back to their origin if
+// their empire still owns it, and are lost otherwise. Fleets reaching a star
+// their own empire took in the meantime join its garrison.
+pub fn move_fleets(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut fleet_query: Query<(Entity, &mut Fleet)>,
+    mut star_query: Query<&mut Star>,
+    connection_query: Query<&Connection>,
+    mut constellation_tracker: ResMut<ConstellationTracker>,
+    mut battle_events: EventWriter<BattleFought>,
+    mut captured_events: EventWriter<StarCaptured>,
+    mut dissolved_events: EventWriter<ConstellationDissolved>,
+) {
+    let dt = time.delta_seconds();
+
+    for (entity, mut fleet) in &mut fleet_query {
+        if !fleet.has_arrived() {
+            fleet.elapsed += dt;
+        }
+
+        let owner = star_query
+            .get(fleet.target)
+            .ok()
+            .filter(|star| star.is_colonized)
+            .map(|star| star.empire);
+        if owner.is_none() || connection_query.get(fleet.connection).is_err() {
+            if let Ok(mut origin) = star_query.get_mut(fleet.from) {
+                if origin.is_owned_by(fleet.empire) {
+                    origin.add_units(UnitType::Warship, fleet.ships);
+                }
+            }
+            commands.entity(entity).despawn();
+            continue;
+        }
+        if !fleet.has_arrived() {
+            continue;
+        }
+        if owner == Some(fleet.empire) {
+            // The star was taken while the fleet was on its way
+            if let Ok(mut star) = star_query.get_mut(fleet.target) {
+                star.add_units(UnitType::Warship, fleet.ships);
+            }
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let Ok(mut star) = star_query.get_mut(fleet.target) else {
+            continue;
+        };
+        let garrison = star.unit_count(UnitType::Warship);
+        // A blockade only fights when the star has a garrison to fight
+        if fleet.mission == Mission::Blockade && garrison == 0 {
+            continue;
+        }
+
+        let defender = star.empire;
+        let outcome = resolve_battle(fleet.ships, garrison);
+        set_garrison(&mut star, outcome.defenders);
+        if fleet.empire.is_player() || defender.is_player() {
+            battle_events.send(BattleFought {
+                star: fleet.target,
+                attacker: fleet.empire,
+                defender,
+                attacker_losses: fleet.ships - outcome.attackers,
+                defender_losses: garrison - outcome.defenders,
+            });
+        }
+
+        if !outcome.attackers_won() {
+            commands.entity(entity).despawn();
+        } else if fleet.mission == Mission::Blockade {
+            fleet.ships = outcome.attackers;
+        } else {
+            // A captured capital is an ordinary colony for its captor
+            let capital = star.is_home_star;
+            star.empire = fleet.empire;
+            star.is_home_star = false;
+            set_garrison(&mut star, outcome.attackers);
+            info!("{} captured {}", fleet.empire.name(), star.name);
+            if fleet.empire.is_player() || defender.is_player() {
+                captured_events.send(StarCaptured {
+                    star: fleet.target,
+                    from: defender,
+                    to: fleet.empire,
+                    capital,
+                });
+            }
+            // Constellations need every member under one owner
+            let target = fleet.target;
+            constellation_tracker
+                .constellations
+                .retain(|constellation| {
+                    let kept = !constellation.stars.contains(&target);
+                    if !kept {
+                        dissolved_events.send(ConstellationDissolved {
+                            id: constellation.id,
+                            stars: constellation.stars.clone(),
+                            reason: DissolveReason::StarCaptured,
+                        });
+                    }
+                    kept
+                });
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undefended_star_falls_without_losses() {
+        assert_eq!(
+            resolve_battle(3, 0),
+            BattleOutcome {
+                attackers: 3,
+                defenders: 0
+            }
+        );
+    }
+
+    #[test]
+    fn stronger_side_wins_with_square_law_losses() {
+        // 10 attackers against 4 defenders fighting as 6: sqrt(100 - 36) = 8
+        let outcome = resolve_battle(10, 4);
+        assert!(outcome.attackers_won());
+        assert_eq!(outcome.attackers, 8);
+
+        // 3 attackers against 4 defenders fighting as 6: sqrt(36 - 9) / 1.5 = 3.46
+        let outcome = resolve_battle(3, 4);
+        assert!(!outcome.attackers_won());
+        assert_eq!(outcome.defenders, 3);
+    }
+
+    #[test]
+    fn ties_go_to_the_defenders() {
+        let outcome = resolve_battle(6, 4);
+        assert!(!outcome.attackers_won());
+        assert_eq!(
+            outcome,
+            BattleOutcome {
+                attackers: 0,
+                defenders: 0
+            }
+        );
+        assert!(!resolve_battle(0, 0).attackers_won());
+    }
+}
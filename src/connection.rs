@@ -0,0 +1,69 @@
+//! Connections between stars
+
+use crate::events::ConnectionRemoved;
+use crate::star::Star;
+use bevy::prelude::*;
+
+// Seconds between two resource collections along a connection
+pub const COLLECTION_INTERVAL: f32 = 2.0;
+
+#[derive(Component)]
+pub struct Connection {
+    pub from: Entity,
+    pub to: Entity,
+    pub collection_timer: Timer,
+    pub is_collecting: bool,
+    pub creation_time: f32, // Time in seconds since creation
+}
+
+impl Connection {
+    pub fn new(from: Entity, to: Entity) -> Self {
+        Self {
+            from,
+            to,
+            collection_timer: Timer::from_seconds(COLLECTION_INTERVAL, TimerMode::Repeating),
+            is_collecting: true,
+            creation_time: 0.0,
+        }
+    }
+}
+
+// Keep track of how long every connection has existed
+pub fn age_connections(mut connection_query: Query<&mut Connection>, time: Res<Time>) {
+    for mut connection in &mut connection_query {
+        connection.creation_time += time.delta_seconds();
+    }
+}
+
+// Remove every connection touching `star`, along with the neighbour entries of
+// both ends, and report the ones that belonged to the player
+pub fn sever_connections<'a>(
+    star: Entity,
+    connections: impl IntoIterator<Item = (Entity, &'a Connection)>,
+    star_query: &mut Query<(Entity, &mut Star)>,
+    commands: &mut Commands,
+    removed_events: &mut EventWriter<ConnectionRemoved>,
+) {
+    for (entity, connection) in connections {
+        if connection.from != star && connection.to != star {
+            continue;
+        }
+        let owned_by_player = star_query
+            .get(connection.from)
+            .is_ok_and(|(_, from)| from.empire.is_player());
+        for end in [connection.from, connection.to] {
+            if let Ok((_, mut end)) = star_query.get_mut(end) {
+                end.connections_from
+                    .retain(|&other| other != connection.from);
+                end.connections_to.retain(|&other| other != connection.to);
+            }
+        }
+        commands.entity(entity).despawn();
+        if owned_by_player {
+            removed_events.send(ConnectionRemoved {
+                from: connection.from,
+                to: connection.to,
+            });
+        }
+    }
+}
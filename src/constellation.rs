@@ -0,0 +1,442 @@
+//! Constellations formed by closed cycles of connected stars
+
+use crate::connection::Connection;
+use crate::empire::EmpireId;
+use crate::events::ConstellationFormed;
+use crate::graph::shortest_cycle_through;
+use crate::resources::ResourceType;
+use crate::star::{Specialization, Star};
+use crate::zodiac::ZodiacSign;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// What a constellation gives to its member stars
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstellationBonus {
+    Production(f32),  // Multiplier on resource extraction
+    BuildSpeed(f32),  // Multiplier on construction and upgrade speed
+    Storage(f32),     // Extra empire capacity per resource type, per member star
+    Connections(u32), // Extra outbound connections per member star
+}
+
+impl ConstellationBonus {
+    pub fn describe(&self) -> String {
+        match self {
+            ConstellationBonus::Production(multiplier) => {
+                format!("{:.1}x Production", multiplier)
+            }
+            ConstellationBonus::BuildSpeed(multiplier) => {
+                format!("{:.1}x Build Speed", multiplier)
+            }
+            ConstellationBonus::Storage(amount) => {
+                format!("+{:.0} Storage per star", amount)
+            }
+            ConstellationBonus::Connections(extra) => {
+                format!("+{} Connections", extra)
+            }
+        }
+    }
+}
+
+// Constellation catalog, recognised from the shape and makeup of the cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ConstellationKind {
+    Triangle,      // 3 stars
+    Quadrilateral, // 4 stars
+    Pentagram,     // 5 stars
+    GreatCircle,   // 6 or more stars
+    Monolith,      // Every star richest in the same resource
+    Assembly,      // Every star with a different specialization
+}
+
+impl ConstellationKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConstellationKind::Triangle => "Triangle",
+            ConstellationKind::Quadrilateral => "Quadrilateral",
+            ConstellationKind::Pentagram => "Pentagram",
+            ConstellationKind::GreatCircle => "Great Circle",
+            ConstellationKind::Monolith => "Monolith",
+            ConstellationKind::Assembly => "Assembly",
+        }
+    }
+
+    pub fn bonus(&self) -> ConstellationBonus {
+        match self {
+            ConstellationKind::Triangle => ConstellationBonus::Production(2.0),
+            ConstellationKind::Quadrilateral => ConstellationBonus::BuildSpeed(2.0),
+            ConstellationKind::Pentagram => ConstellationBonus::Connections(2),
+            ConstellationKind::GreatCircle => ConstellationBonus::Storage(100.0),
+            ConstellationKind::Monolith => ConstellationBonus::Production(3.0),
+            ConstellationKind::Assembly => ConstellationBonus::BuildSpeed(2.5),
+        }
+    }
+
+    // Makeup is checked before shape, since those constellations are harder to build
+    pub fn classify(stars: &[&Star]) -> Self {
+        let mut dominant = stars.iter().map(|star| dominant_resource(star));
+        let first = dominant.next().flatten();
+        if first.is_some() && dominant.all(|resource| resource == first) {
+            return ConstellationKind::Monolith;
+        }
+
+        let specializations: HashSet<Specialization> =
+            stars.iter().map(|star| star.specialization).collect();
+        if !specializations.contains(&Specialization::None) && specializations.len() == stars.len()
+        {
+            return ConstellationKind::Assembly;
+        }
+
+        match stars.len() {
+            0..=3 => ConstellationKind::Triangle,
+            4 => ConstellationKind::Quadrilateral,
+            5 => ConstellationKind::Pentagram,
+            _ => ConstellationKind::GreatCircle,
+        }
+    }
+}
+
+// Resource a star holds the most of when full
+fn dominant_resource(star: &Star) -> Option<ResourceType> {
+    star.max_resources
+        .iter()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(resource_type, _)| *resource_type)
+}
+
+// Constellation data structure
+pub struct Constellation {
+    pub id: u32,
+    pub stars: Vec<Entity>,
+    pub color: Color,
+    pub kind: ConstellationKind,
+    pub zodiac: Option<ZodiacSign>, // Zodiac sign traced by the stars, if any
+    pub empire: EmpireId,           // Owner of the member stars
+}
+
+#[derive(Resource, Default)]
+pub struct ConstellationTracker {
+    pub next_id: u32,
+    pub constellations: Vec<Constellation>,
+}
+
+// Why a constellation broke up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DissolveReason {
+    ConnectionRemoved, // Its cycle lost a connection or a star
+    StarCaptured,      // One of its stars changed owner
+}
+
+impl DissolveReason {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            DissolveReason::ConnectionRemoved => "a connection was removed",
+            DissolveReason::StarCaptured => "one of its stars was captured",
+        }
+    }
+}
+
+// Sent when a constellation breaks up
+#[derive(Event, Debug, Clone)]
+pub struct ConstellationDissolved {
+    pub id: u32,
+    pub stars: Vec<Entity>,
+    pub reason: DissolveReason,
+}
+
+impl ConstellationTracker {
+    pub fn contains_star(&self, star_entity: Entity) -> bool {
+        self.constellation_of(star_entity).is_some()
+    }
+
+    pub fn constellation_of(&self, star_entity: Entity) -> Option<&Constellation> {
+        self.constellations
+            .iter()
+            .find(|constellation| constellation.stars.contains(&star_entity))
+    }
+
+    fn bonus_of(&self, star_entity: Entity) -> Option<ConstellationBonus> {
+        self.constellation_of(star_entity)
+            .map(|constellation| constellation.kind.bonus())
+    }
+
+    pub fn build_speed_bonus(&self, star_entity: Entity) -> f32 {
+        match self.bonus_of(star_entity) {
+            Some(ConstellationBonus::BuildSpeed(multiplier)) => multiplier,
+            _ => 1.0,
+        }
+    }
+
+    pub fn extra_connections(&self, star_entity: Entity) -> u32 {
+        match self.bonus_of(star_entity) {
+            Some(ConstellationBonus::Connections(extra)) => extra,
+            _ => 0,
+        }
+    }
+
+    pub fn zodiac_signs(&self) -> impl Iterator<Item = ZodiacSign> + '_ {
+        self.constellations
+            .iter()
+            .filter_map(|constellation| constellation.zodiac)
+    }
+
+    // Signs held by `empire`'s constellations
+    pub fn zodiac_signs_of(&self, empire: EmpireId) -> impl Iterator<Item = ZodiacSign> + '_ {
+        self.constellations
+            .iter()
+            .filter(move |constellation| constellation.empire == empire)
+            .filter_map(|constellation| constellation.zodiac)
+    }
+
+    // Extra capacity per resource type from every storage constellation of `empire`
+    pub fn storage_bonus(&self, empire: EmpireId) -> f32 {
+        self.constellations
+            .iter()
+            .filter(|constellation| constellation.empire == empire)
+            .map(|constellation| match constellation.kind.bonus() {
+                ConstellationBonus::Storage(amount) => amount * constellation.stars.len() as f32,
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    // Form a constellation from the shortest cycle closed by the connection
+    // between `from` and `to`, if one exists among stars that are still free
+    // The event to send is returned for the player's constellations only
+    pub fn try_form(
+        &mut self,
+        from: Entity,
+        to: Entity,
+        star_query: &Query<&Star>,
+    ) -> Option<ConstellationFormed> {
+        // Stars can only belong to one constellation,
+        let members: HashSet<Entity> = self
+            .constellations
+            .iter()
+            .flat_map(|constellation| constellation.stars.iter().copied())
+            .collect();
+        // and every member must belong to the empire closing the cycle
+        let empire = star_query
+            .get(from)
+            .map_or(EmpireId::PLAYER, |star| star.empire);
+        let cycle_entities = shortest_cycle_through(from, to, star_query, |star| {
+            members.contains(&star)
+                || star_query
+                    .get(star)
+                    .map_or(true, |star| !star.is_owned_by(empire))
+        })?;
+
+        // Create a new constellation with varied colors
+        let hue = (self.next_id as f32 * 137.5) % 360.0; // Golden angle for color distribution
+        let color = Color::hsla(
+            hue,  // Spread hues around the color wheel
+            0.7,  // Good saturation
+            0.6,  // Medium lightness
+            0.25, // Semi-transparent
+        );
+
+        let members: Vec<&Star> = cycle_entities
+            .iter()
+            .filter_map(|entity| star_query.get(*entity).ok())
+            .collect();
+        let kind = ConstellationKind::classify(&members);
+
+        info!(
+            "New {} constellation formed with {} stars!",
+            kind.name(),
+            cycle_entities.len()
+        );
+
+        let formed = ConstellationFormed {
+            id: self.next_id,
+            stars: cycle_entities.clone(),
+            kind,
+        };
+        self.constellations.push(Constellation {
+            id: self.next_id,
+            stars: cycle_entities,
+            color,
+            kind,
+            zodiac: None,
+            empire,
+        });
+        self.next_id += 1;
+        // Events only report the player's own constellations
+        empire.is_player().then_some(formed)
+    }
+}
+
+fn are_connected(a: Entity, b: Entity, star_query: &Query<&Star>) -> bool {
+    star_query
+        .get(a)
+        .is_ok_and(|star| star.connections_to.contains(&b) || star.connections_from.contains(&b))
+}
+
+// System to create constellations when new connections close cycles
+// Only the connections added since the last run are looked at: a new cycle
+// must go through one of them, so the rest of the graph is never enumerated
+pub fn detect_and_create_constellations(
+    new_connections: Query<&Connection, Added<Connection>>,
+    star_query: Query<&Star>,
+    mut constellation_tracker: ResMut<ConstellationTracker>,
+    mut formed_events: EventWriter<ConstellationFormed>,
+) {
+    for connection in &new_connections {
+        if let Some(formed) =
+            constellation_tracker.try_form(connection.from, connection.to, &star_query)
+        {
+            formed_events.send(formed);
+        }
+    }
+}
+
+// System to break up constellations whose cycle lost a connection or a star
+// The freed stars may then form new constellations with the connections left
+pub fn dissolve_broken_constellations(
+    mut removed_connections: RemovedComponents<Connection>,
+    mut removed_stars: RemovedComponents<Star>,
+    connection_query: Query<&Connection>,
+    star_query: Query<&Star>,
+    mut constellation_tracker: ResMut<ConstellationTracker>,
+    mut dissolved_events: EventWriter<ConstellationDissolved>,
+    mut formed_events: EventWriter<ConstellationFormed>,
+) {
+    if removed_connections.read().count() == 0 && removed_stars.read().count() == 0 {
+        return;
+    }
+
+    let mut freed_stars = HashSet::new();
+    constellation_tracker
+        .constellations
+        .retain(|constellation| {
+            let stars = &constellation.stars;
+            let intact = (0..stars.len())
+                .all(|i| are_connected(stars[i], stars[(i + 1) % stars.len()], &star_query));
+            if !intact {
+                info!("Constellation {} dissolved", constellation.id);
+                freed_stars.extend(stars.iter().copied());
+                dissolved_events.send(ConstellationDissolved {
+                    id: constellation.id,
+                    stars: stars.clone(),
+                    reason: DissolveReason::ConnectionRemoved,
+                });
+            }
+            intact
+        });
+
+    for connection in &connection_query {
+        if freed_stars.contains(&connection.from) || freed_stars.contains(&connection.to) {
+            if let Some(formed) =
+                constellation_tracker.try_form(connection.from, connection.to, &star_query)
+            {
+                formed_events.send(formed);
+            }
+        }
+    }
+}
+
+// Production multiplier of the constellation a star belongs to
+// Constellations with other kinds of bonuses leave production unchanged
+pub fn check_constellation_bonuses(
+    star_entity: Entity,
+    constellation_tracker: &ConstellationTracker,
+) -> f32 {
+    match constellation_tracker.bonus_of(star_entity) {
+        Some(ConstellationBonus::Production(multiplier)) => multiplier,
+        _ => 1.0, // No bonus
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::empire::EmpireId;
+
+    fn star(richest: ResourceType, specialization: Specialization) -> Star {
+        let mut star = Star::new_colony(&[(richest, 100.0), (ResourceType::Water, 10.0)]);
+        star.specialization = specialization;
+        star
+    }
+
+    fn classify(stars: &[Star]) -> ConstellationKind {
+        let stars: Vec<&Star> = stars.iter().collect();
+        ConstellationKind::classify(&stars)
+    }
+
+    #[test]
+    fn shape_follows_star_count() {
+        let mixed = [
+            ResourceType::Iron,
+            ResourceType::Copper,
+            ResourceType::Silicon,
+        ]
+        .iter()
+        .cycle();
+        let stars: Vec<Star> = mixed
+            .take(6)
+            .map(|&resource| star(resource, Specialization::None))
+            .collect();
+        assert_eq!(classify(&stars[..3]), ConstellationKind::Triangle);
+        assert_eq!(classify(&stars[..4]), ConstellationKind::Quadrilateral);
+        assert_eq!(classify(&stars[..5]), ConstellationKind::Pentagram);
+        assert_eq!(classify(&stars), ConstellationKind::GreatCircle);
+    }
+
+    #[test]
+    fn same_richest_resource_is_a_monolith() {
+        let stars: Vec<Star> = (0..4)
+            .map(|_| star(ResourceType::Uranium, Specialization::None))
+            .collect();
+        assert_eq!(classify(&stars), ConstellationKind::Monolith);
+    }
+
+    #[test]
+    fn different_specializations_are_an_assembly() {
+        let stars = [
+            star(ResourceType::Iron, Specialization::Storage),
+            star(ResourceType::Copper, Specialization::Research),
+            star(ResourceType::Silicon, Specialization::Medical),
+        ];
+        assert_eq!(classify(&stars), ConstellationKind::Assembly);
+
+        // A star still extracting resources breaks the assembly
+        let stars = [
+            star(ResourceType::Iron, Specialization::Storage),
+            star(ResourceType::Copper, Specialization::Research),
+            star(ResourceType::Silicon, Specialization::None),
+        ];
+        assert_eq!(classify(&stars), ConstellationKind::Triangle);
+    }
+
+    #[test]
+    fn only_production_constellations_boost_production() {
+        let a = Entity::from_raw(1);
+        let b = Entity::from_raw(2);
+        let tracker = ConstellationTracker {
+            next_id: 2,
+            constellations: vec![
+                Constellation {
+                    id: 0,
+                    stars: vec![a],
+                    color: Color::WHITE,
+                    kind: ConstellationKind::Monolith,
+                    zodiac: None,
+                    empire: EmpireId::PLAYER,
+                },
+                Constellation {
+                    id: 1,
+                    stars: vec![b],
+                    color: Color::WHITE,
+                    kind: ConstellationKind::Quadrilateral,
+                    zodiac: None,
+                    empire: EmpireId::PLAYER,
+                },
+            ],
+        };
+        assert_eq!(check_constellation_bonuses(a, &tracker), 3.0);
+        assert_eq!(check_constellation_bonuses(b, &tracker), 1.0);
+        assert_eq!(tracker.build_speed_bonus(b), 2.0);
+        assert_eq!(tracker.build_speed_bonus(a), 1.0);
+    }
+}
@@ -0,0 +1,133 @@
+//! Paying for specializations and upgrades, and cancelling them
+
+use crate::research::TechTree;
+use crate::resources::{PlayerResources, ResourceType};
+use crate::star::{BuildingState, Specialization, Star};
+use std::fmt;
+
+// Share of the paid cost returned when a construction is cancelled
+pub const CANCEL_REFUND: f32 = 0.5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstructionError {
+    AlreadySpecialized(Specialization),
+    Busy,
+    NothingToCancel,
+    NoRoomForRefund(Vec<(ResourceType, f32)>), // Refund that does not fit in storage
+    Unaffordable(Vec<(ResourceType, f32)>),    // Missing amount per resource
+}
+
+impl fmt::Display for ConstructionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstructionError::AlreadySpecialized(spec) => {
+                write!(f, "Star is already a {}", spec.name())
+            }
+            ConstructionError::Busy => write!(f, "Star is busy with another construction"),
+            ConstructionError::NothingToCancel => write!(f, "Nothing is being built"),
+            ConstructionError::NoRoomForRefund(refund) => {
+                write!(f, "No storage room for the refund of")?;
+                for (resource_type, amount) in refund {
+                    write!(f, " {} {:.1}", resource_type.name(), amount)?;
+                }
+                Ok(())
+            }
+            ConstructionError::Unaffordable(missing) => {
+                write!(f, "Not enough resources, missing:")?;
+                for (resource_type, amount) in missing {
+                    write!(f, " {} {:.1}", resource_type.name(), amount)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConstructionError {}
+
+fn pay(
+    player_resources: &mut PlayerResources,
+    costs: &[(ResourceType, f32)],
+) -> Result<(), ConstructionError> {
+    if player_resources.pay(costs) {
+        Ok(())
+    } else {
+        Err(ConstructionError::Unaffordable(
+            player_resources.missing(costs),
+        ))
+    }
+}
+
+// Pay for and start switching `star` to `spec`
+pub fn specialize(
+    star: &mut Star,
+    spec: Specialization,
+    player_resources: &mut PlayerResources,
+) -> Result<(), ConstructionError> {
+    if star.specialization == spec {
+        return Err(ConstructionError::AlreadySpecialized(spec));
+    }
+    if star.building_state != BuildingState::Ready {
+        return Err(ConstructionError::Busy);
+    }
+
+    let costs = spec.construction_cost();
+    pay(player_resources, &costs)?;
+    star.set_specialization(spec, costs);
+    Ok(())
+}
+
+// Pay for and start upgrading `star` to the next level
+pub fn upgrade(
+    star: &mut Star,
+    player_resources: &mut PlayerResources,
+    tech_tree: &TechTree,
+) -> Result<(), ConstructionError> {
+    if star.building_state != BuildingState::Ready {
+        return Err(ConstructionError::Busy);
+    }
+
+    let costs = star
+        .specialization
+        .upgrade_cost(star.specialization_level + 1);
+    pay(player_resources, &costs)?;
+    star.start_upgrade(
+        tech_tree.upgrade_time(star.specialization, star.specialization_level),
+        costs,
+    );
+    Ok(())
+}
+
+// Stop the current construction and refund part of what was paid for it
+// A cancelled specialization falls back to resource extraction, a cancelled
+// upgrade keeps the current level. The construction goes on if the refund does
+// not fit in storage. Returns what was refunded.
+pub fn cancel(
+    star: &mut Star,
+    player_resources: &mut PlayerResources,
+) -> Result<Vec<(ResourceType, f32)>, ConstructionError> {
+    let (paid, building) = match &star.building_state {
+        BuildingState::Ready => return Err(ConstructionError::NothingToCancel),
+        BuildingState::Building { paid, .. } => (paid, true),
+        BuildingState::Upgrading { paid, .. } => (paid, false),
+    };
+    let refund: Vec<(ResourceType, f32)> = paid
+        .iter()
+        .map(|&(resource_type, cost)| (resource_type, cost * CANCEL_REFUND))
+        .collect();
+    if refund
+        .iter()
+        .any(|&(resource_type, amount)| player_resources.free_space(resource_type) < amount)
+    {
+        return Err(ConstructionError::NoRoomForRefund(refund));
+    }
+
+    if building {
+        star.set_specialization(Specialization::None, Vec::new());
+    }
+    star.building_state = BuildingState::Ready;
+    for &(resource_type, amount) in &refund {
+        player_resources.store(resource_type, amount);
+    }
+    Ok(refund)
+}
@@ -0,0 +1,352 @@
+//! Resource collection and unit production
+
+use crate::cargo::{cargo_in_transit, CargoPacket};
+use crate::combat::{blockaded_stars, Fleet};
+use crate::connection::Connection;
+use crate::constellation::{check_constellation_bonuses, ConstellationTracker};
+use crate::empire::{EmpireId, RivalEmpires};
+use crate::events::{GoodsRefined, StarDepleted, UnitsProduced, UpgradeCompleted};
+use crate::graph::RouteDistance;
+use crate::refining::{choose_recipe, GOODS_STORAGE};
+use crate::research::TechTree;
+use crate::resources::{PlayerResources, ResourceType};
+use crate::star::{BuildingState, Specialization, Star, UnitType};
+use crate::units::{population_factor, unit_support, STORAGE_PER_MODULE};
+use crate::zodiac::ZodiacPerks;
+use bevy::{ecs::system::ParamSet, prelude::*};
+use std::collections::HashMap;
+
+// Calculate production efficiency based on route distance to storage hub
+// Stars need supply routes to maintain efficiency - the longer the route, the less efficient
+pub fn production_rate_modifier_from_distance(route_distance: Option<u32>) -> f32 {
+    match route_distance {
+        None => 0.1,     // No route to storage hub: 10% production (isolated)
+        Some(0) => 1.0,  // Is a storage hub: 100% production
+        Some(1) => 0.9,  // 1 connection hop: 90% production
+        Some(2) => 0.75, // 2 connection hops: 75% production
+        Some(3) => 0.6,  // 3 connection hops: 60% production
+        Some(4) => 0.45, // 4 connection hops: 45% production
+        Some(5) => 0.35, // 5 connection hops: 35% production
+        Some(d) => (0.3 / (d as f32 - 4.0)).max(0.1), // Longer routes: diminishing returns, min 10%
+    }
+}
+
+// Empire storage is the sum of the capacities of every operational storage hub
+// the empire owns plus whatever its storage constellations add
+pub fn update_storage_capacity(
+    star_query: Query<&Star>,
+    constellation_tracker: Res<ConstellationTracker>,
+    mut player_resources: ResMut<PlayerResources>,
+    mut rivals: ResMut<RivalEmpires>,
+) {
+    let empires: Vec<EmpireId> = std::iter::once(EmpireId::PLAYER)
+        .chain(rivals.rivals.iter().map(|rival| rival.id))
+        .collect();
+    for empire in empires {
+        if let Some(stockpile) = rivals.stockpile(&mut player_resources, empire) {
+            stockpile.capacity = empire_capacity(empire, &star_query, &constellation_tracker);
+        }
+    }
+}
+
+fn empire_capacity(
+    empire: EmpireId,
+    star_query: &Query<&Star>,
+    constellation_tracker: &ConstellationTracker,
+) -> HashMap<ResourceType, f32> {
+    let mut capacity: HashMap<ResourceType, f32> = HashMap::new();
+    for star in star_query {
+        if !star.is_storage_hub
+            || !star.is_owned_by(empire)
+            || matches!(star.building_state, BuildingState::Building { .. })
+        {
+            continue;
+        }
+        for (resource_type, amount) in &star.storage_capacity {
+            *capacity.entry(*resource_type).or_insert(0.0) += amount;
+        }
+        for good in ResourceType::GOODS {
+            *capacity.entry(good).or_insert(0.0) += GOODS_STORAGE;
+        }
+        // Storage modules add room for every resource type
+        let modules = star.unit_count(UnitType::StorageModule);
+        if modules > 0 {
+            for resource_type in ResourceType::ALL {
+                *capacity.entry(resource_type).or_insert(0.0) +=
+                    modules as f32 * STORAGE_PER_MODULE;
+            }
+        }
+    }
+    let constellation_storage = constellation_tracker.storage_bonus(empire)
+        + ZodiacPerks::new(constellation_tracker, empire).storage;
+    if constellation_storage > 0.0 {
+        for resource_type in ResourceType::ALL {
+            *capacity.entry(resource_type).or_insert(0.0) += constellation_storage;
+        }
+    }
+    capacity
+}
+
+pub fn collect_resources_system(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut connection_query: Query<&mut Connection>,
+    mut star_queries: ParamSet<(Query<(Entity, &mut Star)>, Query<(Entity, &Star)>)>,
+    route_query: Query<&RouteDistance>,
+    cargo_query: Query<&CargoPacket>,
+    fleet_query: Query<&Fleet>,
+    mut player_resources: ResMut<PlayerResources>,
+    mut rivals: ResMut<RivalEmpires>,
+    constellation_tracker: Res<ConstellationTracker>,
+    tech_tree: Res<TechTree>,
+    mut upgraded_events: EventWriter<UpgradeCompleted>,
+    mut depleted_events: EventWriter<StarDepleted>,
+    mut produced_events: EventWriter<UnitsProduced>,
+    mut refined_events: EventWriter<GoodsRefined>,
+) {
+    // Rivals do no research, and zodiac perks only help the empire holding them
+    let no_research = TechTree::default();
+    let mut in_transit: HashMap<EmpireId, HashMap<ResourceType, f32>> = HashMap::new();
+
+    // Mining ships and builders help their own star and connected stars
+    let (support, owners) = {
+        let star_query = star_queries.p1();
+        let stars: Vec<(Entity, &Star)> = star_query.iter().collect();
+        let owners: HashMap<Entity, EmpireId> = stars
+            .iter()
+            .map(|(entity, star)| (*entity, star.empire))
+            .collect();
+        (unit_support(&stars), owners)
+    };
+    let blockaded = blockaded_stars(&fleet_query);
+
+    // First, update building timers
+    for (entity, mut star) in &mut star_queries.p0() {
+        let perks = ZodiacPerks::new(&constellation_tracker, star.empire);
+        let build_progress = time.delta_seconds()
+            * support[&entity].build_speed()
+            * constellation_tracker.build_speed_bonus(entity)
+            * perks.build_speed;
+        match &mut star.building_state {
+            BuildingState::Building { timer, .. } => {
+                *timer -= build_progress;
+                if *timer <= 0.0 {
+                    star.building_state = BuildingState::Ready;
+                }
+            }
+            BuildingState::Upgrading { timer, .. } => {
+                *timer -= build_progress;
+                if *timer <= 0.0 {
+                    star.building_state = BuildingState::Ready;
+                    star.specialization_level += 1; // No limit on levels
+                    if star.empire.is_player() {
+                        upgraded_events.send(UpgradeCompleted {
+                            star: entity,
+                            level: star.specialization_level,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for mut connection in &mut connection_query {
+        // A front line to another empire collects nothing, and a blockaded
+        // star is cut off from its network
+        if owners.get(&connection.from) != owners.get(&connection.to)
+            || blockaded.contains(&connection.to)
+        {
+            continue;
+        }
+        if connection.is_collecting {
+            connection.collection_timer.tick(time.delta());
+
+            if connection.collection_timer.just_finished() {
+                // Then collect resources from the connected star
+                if let Ok((_, mut star)) = star_queries.p0().get_mut(connection.to) {
+                    // Only produce if building is ready
+                    if star.building_state != BuildingState::Ready {
+                        continue;
+                    }
+
+                    let empire = star.empire;
+                    let Some(stockpile) = rivals.stockpile(&mut player_resources, empire) else {
+                        continue;
+                    };
+                    let perks = ZodiacPerks::new(&constellation_tracker, empire);
+                    let tech_tree = if empire.is_player() {
+                        &*tech_tree
+                    } else {
+                        &no_research
+                    };
+
+                    // Route distance to the nearest storage hub, kept up to date by
+                    // `update_route_distances`
+                    let distance = route_query
+                        .get(connection.to)
+                        .ok()
+                        .and_then(|route| route.0);
+                    let distance_modifier = match distance {
+                        None => perks.isolated_production,
+                        Some(_) => tech_tree.route_efficiency(perks.route_distance(distance)),
+                    };
+
+                    // Only collect resources if star is not specialized for something other than storage
+                    if star.specialization == Specialization::None
+                        || star.specialization == Specialization::Storage
+                    {
+                        // Check if star is in a constellation for bonus
+                        let constellation_bonus =
+                            perks.constellation_bonus(tech_tree.constellation_bonus(
+                                check_constellation_bonuses(connection.to, &constellation_tracker),
+                            ));
+                        let unit_bonus = support
+                            .get(&connection.to)
+                            .map_or(1.0, |support| support.mining_multiplier());
+                        let production_rate = star.production_rate
+                            * distance_modifier
+                            * constellation_bonus
+                            * unit_bonus
+                            * population_factor(star.population)
+                            * stockpile.life_support
+                            * perks.production;
+                        let in_transit = in_transit
+                            .entry(empire)
+                            .or_insert_with(|| cargo_in_transit(&cargo_query, empire));
+                        for (resource_type, amount) in star.resources.iter_mut() {
+                            // Whatever would not fit in storage once the cargo already
+                            // on its way arrives stays backlogged at the star
+                            let shipped = in_transit.entry(*resource_type).or_insert(0.0);
+                            let room = stockpile.free_space(*resource_type) - *shipped;
+                            let collection_amount = (production_rate * 5.0).min(*amount).min(room);
+                            if collection_amount > 0.0 {
+                                *amount -= collection_amount;
+                                *shipped += collection_amount;
+                                commands.spawn(CargoPacket::new(
+                                    *resource_type,
+                                    collection_amount,
+                                    connection.to,
+                                    empire,
+                                ));
+                            }
+                        }
+                    } else {
+                        // Industry refines a batch of goods before paying for its own units
+                        if star.specialization == Specialization::Industrial {
+                            let level = star.specialization_level;
+                            let in_transit = in_transit
+                                .entry(empire)
+                                .or_insert_with(|| cargo_in_transit(&cargo_query, empire));
+                            if let Some(recipe) = choose_recipe(stockpile, in_transit, level) {
+                                stockpile.pay(&recipe.batch_inputs(level));
+                                let amount = recipe.batch_output(level);
+                                *in_transit.entry(recipe.output).or_insert(0.0) += amount;
+                                commands.spawn(CargoPacket::new(
+                                    recipe.output,
+                                    amount,
+                                    connection.to,
+                                    empire,
+                                ));
+                                if empire.is_player() {
+                                    refined_events.send(GoodsRefined {
+                                        star: connection.to,
+                                        resource_type: recipe.output,
+                                        amount,
+                                    });
+                                }
+                            }
+                        }
+
+                        // Specialized star: consume resources and produce units
+                        let production_costs = star
+                            .specialization
+                            .production_cost(star.specialization_level);
+
+                        // Produce units if we have resources
+                        if stockpile.pay(&production_costs) {
+                            // Produce units based on specialization (more at higher levels)
+                            let level_bonus = star.specialization_level as u32;
+                            let produced = match star.specialization {
+                                Specialization::Military => Some((UnitType::Warship, level_bonus)),
+                                Specialization::Mining => {
+                                    Some((UnitType::MiningShip, 2 * level_bonus))
+                                }
+                                Specialization::Agriculture => {
+                                    Some((UnitType::Farmer, 3 * level_bonus))
+                                }
+                                Specialization::Research => {
+                                    Some((UnitType::Scientist, level_bonus))
+                                }
+                                Specialization::Medical => {
+                                    Some((UnitType::Doctor, 2 * level_bonus))
+                                }
+                                Specialization::Industrial => {
+                                    Some((UnitType::Builder, 2 * level_bonus))
+                                }
+                                Specialization::Storage => {
+                                    Some((UnitType::StorageModule, level_bonus))
+                                }
+                                Specialization::None => None,
+                            };
+                            if let Some((unit_type, count)) = produced {
+                                let count = count + perks.extra_units;
+                                star.add_units(unit_type, count);
+                                if empire.is_player() {
+                                    produced_events.send(UnitsProduced {
+                                        star: connection.to,
+                                        unit_type,
+                                        count,
+                                    });
+                                }
+                            }
+                        }
+                    }
+
+                    // Check if star is depleted
+                    let total_resources: f32 = star.resources.values().sum();
+                    if total_resources < 0.1 {
+                        connection.is_collecting = false;
+                        if empire.is_player() {
+                            depleted_events.send(StarDepleted {
+                                star: connection.to,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_hub_produces_at_full_rate() {
+        assert_eq!(production_rate_modifier_from_distance(Some(0)), 1.0);
+    }
+
+    #[test]
+    fn isolated_star_produces_at_ten_percent() {
+        assert_eq!(production_rate_modifier_from_distance(None), 0.1);
+    }
+
+    #[test]
+    fn efficiency_never_increases_with_distance() {
+        let mut previous = production_rate_modifier_from_distance(Some(0));
+        for hops in 1..50 {
+            let modifier = production_rate_modifier_from_distance(Some(hops));
+            assert!(
+                modifier <= previous,
+                "{} hops: {} > {}",
+                hops,
+                modifier,
+                previous
+            );
+            assert!(modifier >= 0.1);
+            previous = modifier;
+        }
+    }
+}
@@ -0,0 +1,609 @@
+//! Empires competing for the same stars
+//!
+//! Every colonized star belongs to one empire. The player's empire follows the
+//! input, rival empires are run by an `EmpireStrategy` asked for one action every
+//! THINK_INTERVAL seconds. Rivals play by the player's rules: they pay from
+//! their own stockpile, respect connection limits, only claim free stars and
+//! take other empires' stars by sending their warships.
+
+use crate::combat::{connection_between, launch_fleet, resolve_battle, Mission};
+use crate::connection::Connection;
+use crate::constellation::ConstellationTracker;
+use crate::construction;
+use crate::research::TechTree;
+use crate::resources::PlayerResources;
+use crate::star::{max_connections_for_level, BuildingState, Specialization, Star, UnitType};
+use crate::zodiac::ZodiacPerks;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Seconds between two decisions of a rival empire
+pub const THINK_INTERVAL: f32 = 5.0;
+
+// Farthest a free star may be from one of its stars for the default strategy to claim it
+pub const MAX_REACH: f32 = 300.0;
+
+// One specialized star for every this many colonies
+pub const COLONIES_PER_SPECIALIZATION: usize = 3;
+
+// Order in which the default strategy specializes its colonies
+// Industry comes first, the others pay for their units and upgrades in its goods
+pub const SPECIALIZATION_PLAN: [Specialization; 6] = [
+    Specialization::Industrial,
+    Specialization::Mining,
+    Specialization::Agriculture,
+    Specialization::Medical,
+    Specialization::Research,
+    Specialization::Military,
+];
+
+const EMPIRE_NAMES: [&str; 5] = [
+    "Player",
+    "Draconid Hegemony",
+    "Orion Collective",
+    "Lyran Dominion",
+    "Cygnus Pact",
+];
+
+const EMPIRE_COLORS: [Color; 5] = [
+    Color::srgb(0.2, 0.9, 0.2),
+    Color::srgb(1.0, 0.25, 0.2),
+    Color::srgb(0.7, 0.3, 1.0),
+    Color::srgb(1.0, 0.6, 0.1),
+    Color::srgb(0.2, 0.8, 1.0),
+];
+
+// Rivals that still get a name and color of their own
+pub const MAX_RIVALS: usize = EMPIRE_NAMES.len() - 1;
+
+// Owner of a colonized star, the player is always empire 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct EmpireId(pub u8);
+
+impl EmpireId {
+    pub const PLAYER: EmpireId = EmpireId(0);
+
+    pub fn is_player(&self) -> bool {
+        *self == Self::PLAYER
+    }
+
+    pub fn name(&self) -> &'static str {
+        EMPIRE_NAMES[self.0 as usize % EMPIRE_NAMES.len()]
+    }
+
+    // Color of the empire's star borders and connections
+    pub fn color(&self) -> Color {
+        EMPIRE_COLORS[self.0 as usize % EMPIRE_COLORS.len()]
+    }
+}
+
+// Something a rival empire does on its turn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmpireAction {
+    Colonize {
+        from: Entity,
+        to: Entity,
+    },
+    Connect {
+        from: Entity,
+        to: Entity,
+    }, // Front line to another empire's star
+    SendFleet {
+        from: Entity,
+        to: Entity,
+        mission: Mission,
+    },
+    Specialize {
+        star: Entity,
+        spec: Specialization,
+    },
+    Upgrade {
+        star: Entity,
+    },
+}
+
+// A star as a strategy sees it
+pub struct StarView<'a> {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub star: &'a Star,
+    pub free_connections: u32, // Outbound connections still available
+}
+
+// The galaxy as seen by one empire when it decides
+pub struct EmpireView<'a> {
+    pub empire: EmpireId,
+    pub resources: &'a PlayerResources,
+    pub stars: &'a [StarView<'a>],
+}
+
+impl EmpireView<'_> {
+    pub fn owned(&self) -> impl Iterator<Item = &StarView<'_>> {
+        self.stars
+            .iter()
+            .filter(|view| view.star.is_owned_by(self.empire))
+    }
+
+    pub fn free(&self) -> impl Iterator<Item = &StarView<'_>> {
+        self.stars.iter().filter(|view| !view.star.is_colonized)
+    }
+
+    // Stars of every other empire
+    pub fn hostile(&self) -> impl Iterator<Item = &StarView<'_>> {
+        self.stars
+            .iter()
+            .filter(|view| view.star.is_colonized && view.star.empire != self.empire)
+    }
+}
+
+// Decides what a rival empire does, one action per turn
+pub trait EmpireStrategy: Send + Sync {
+    // None skips the turn
+    fn decide(&mut self, view: &EmpireView) -> Option<EmpireAction>;
+}
+
+// Default strategy: attack the nearest hostile star within MAX_REACH that a
+// garrison can take, opening a front line to it first, then specialize one
+// colony in COLONIES_PER_SPECIALIZATION once it can pay for it, otherwise claim
+// the nearest free star within MAX_REACH, and upgrade stars that ran out of
+// connections when nothing is left to claim
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExpansionStrategy;
+
+impl ExpansionStrategy {
+    fn attack(view: &EmpireView, owned: &[&StarView]) -> Option<EmpireAction> {
+        let (from, to, _) = owned
+            .iter()
+            .filter(|from| from.star.unit_count(UnitType::Warship) > 0)
+            .flat_map(|from| {
+                view.hostile()
+                    .map(move |to| (*from, to, from.position.distance(to.position)))
+            })
+            .filter(|(from, to, distance)| {
+                *distance <= MAX_REACH
+                    && resolve_battle(
+                        from.star.unit_count(UnitType::Warship),
+                        to.star.unit_count(UnitType::Warship),
+                    )
+                    .attackers_won()
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2))?;
+
+        let connected = from.star.connections_to.contains(&to.entity)
+            || from.star.connections_from.contains(&to.entity);
+        if connected {
+            Some(EmpireAction::SendFleet {
+                from: from.entity,
+                to: to.entity,
+                mission: Mission::Attack,
+            })
+        } else if from.free_connections > 0 {
+            Some(EmpireAction::Connect {
+                from: from.entity,
+                to: to.entity,
+            })
+        } else {
+            None
+        }
+    }
+
+    fn specialize(view: &EmpireView, owned: &[&StarView]) -> Option<EmpireAction> {
+        let specialized = owned
+            .iter()
+            .filter(|view_star| {
+                !view_star.star.is_home_star
+                    && view_star.star.specialization != Specialization::None
+            })
+            .count();
+        if (specialized + 1) * COLONIES_PER_SPECIALIZATION > owned.len() {
+            return None;
+        }
+        let spec = SPECIALIZATION_PLAN[specialized % SPECIALIZATION_PLAN.len()];
+        if !view.resources.can_afford(&spec.construction_cost()) {
+            return None;
+        }
+        // The extraction colony with the least left to mine gives the least up
+        owned
+            .iter()
+            .filter(|view_star| {
+                !view_star.star.is_home_star
+                    && view_star.star.specialization == Specialization::None
+                    && view_star.star.building_state == BuildingState::Ready
+            })
+            .min_by(|a, b| {
+                let left = |view_star: &StarView| view_star.star.resources.values().sum::<f32>();
+                left(a).total_cmp(&left(b))
+            })
+            .map(|view_star| EmpireAction::Specialize {
+                star: view_star.entity,
+                spec,
+            })
+    }
+}
+
+impl EmpireStrategy for ExpansionStrategy {
+    fn decide(&mut self, view: &EmpireView) -> Option<EmpireAction> {
+        let owned: Vec<&StarView> = view.owned().collect();
+        if let Some(action) = Self::attack(view, &owned) {
+            return Some(action);
+        }
+        if let Some(action) = Self::specialize(view, &owned) {
+            return Some(action);
+        }
+
+        let nearest = owned
+            .iter()
+            .filter(|from| from.free_connections > 0)
+            .flat_map(|from| {
+                view.free()
+                    .map(move |to| (from.entity, to.entity, from.position.distance(to.position)))
+            })
+            .filter(|(_, _, distance)| *distance <= MAX_REACH)
+            .min_by(|a, b| a.2.total_cmp(&b.2));
+        if let Some((from, to, _)) = nearest {
+            return Some(EmpireAction::Colonize { from, to });
+        }
+
+        owned
+            .iter()
+            .find(|view_star| {
+                let star = view_star.star;
+                view_star.free_connections == 0
+                    && star.building_state == BuildingState::Ready
+                    && view.resources.can_afford(
+                        &star
+                            .specialization
+                            .upgrade_cost(star.specialization_level + 1),
+                    )
+            })
+            .map(|view_star| EmpireAction::Upgrade {
+                star: view_star.entity,
+            })
+    }
+}
+
+pub struct RivalEmpire {
+    pub id: EmpireId,
+    pub resources: PlayerResources,
+    pub strategy: Box<dyn EmpireStrategy>,
+    think_timer: Timer,
+}
+
+impl RivalEmpire {
+    pub fn new(id: EmpireId, strategy: Box<dyn EmpireStrategy>) -> Self {
+        Self {
+            id,
+            resources: PlayerResources::default(),
+            strategy,
+            think_timer: Timer::from_seconds(THINK_INTERVAL, TimerMode::Repeating),
+        }
+    }
+}
+
+// Every AI empire in the game, the player's stockpile stays in `PlayerResources`
+#[derive(Resource, Default)]
+pub struct RivalEmpires {
+    pub rivals: Vec<RivalEmpire>,
+}
+
+impl RivalEmpires {
+    // Empires 1 to `count`, all playing the default strategy
+    pub fn new(count: usize) -> Self {
+        let mut rivals = Self::default();
+        for _ in 0..count {
+            rivals.add(Box::new(ExpansionStrategy));
+        }
+        rivals
+    }
+
+    // Add a rival played by `strategy` and return its id
+    // Its home star is the one generated for that id, see `generate_galaxy`
+    pub fn add(&mut self, strategy: Box<dyn EmpireStrategy>) -> EmpireId {
+        assert!(
+            self.rivals.len() < MAX_RIVALS,
+            "at most {} rivals",
+            MAX_RIVALS
+        );
+        let id = EmpireId(self.rivals.len() as u8 + 1);
+        self.rivals.push(RivalEmpire::new(id, strategy));
+        id
+    }
+
+    pub fn get(&self, id: EmpireId) -> Option<&RivalEmpire> {
+        self.rivals.iter().find(|rival| rival.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: EmpireId) -> Option<&mut RivalEmpire> {
+        self.rivals.iter_mut().find(|rival| rival.id == id)
+    }
+
+    // Stockpile of `empire`, which is `player` for the player's empire
+    pub fn stockpile<'a>(
+        &'a mut self,
+        player: &'a mut PlayerResources,
+        empire: EmpireId,
+    ) -> Option<&'a mut PlayerResources> {
+        if empire.is_player() {
+            return Some(player);
+        }
+        self.get_mut(empire).map(|rival| &mut rival.resources)
+    }
+}
+
+// Outbound connections `star` may have, the tech tree only helps the player
+pub fn max_connections(
+    entity: Entity,
+    star: &Star,
+    tech_tree: &TechTree,
+    constellation_tracker: &ConstellationTracker,
+) -> u32 {
+    let base = if star.empire.is_player() {
+        tech_tree.max_connections(star.specialization_level)
+    } else {
+        max_connections_for_level(star.specialization_level)
+    };
+    base + constellation_tracker.extra_connections(entity)
+        + ZodiacPerks::new(constellation_tracker, star.empire).extra_connections
+}
+
+// Give every rival its turn once its think timer runs out
+pub fn run_rival_empires(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut rivals: ResMut<RivalEmpires>,
+    mut star_query: Query<(Entity, &mut Star, Option<&Transform>)>,
+    connection_query: Query<(Entity, &Connection)>,
+    tech_tree: Res<TechTree>,
+    constellation_tracker: Res<ConstellationTracker>,
+) {
+    for rival in &mut rivals.rivals {
+        rival.think_timer.tick(time.delta());
+        if !rival.think_timer.just_finished() {
+            continue;
+        }
+
+        let action = {
+            let stars: Vec<StarView> = star_query
+                .iter()
+                .map(|(entity, star, transform)| StarView {
+                    entity,
+                    position: transform.map_or(Vec2::ZERO, |t| t.translation.truncate()),
+                    star,
+                    free_connections: max_connections(
+                        entity,
+                        star,
+                        &tech_tree,
+                        &constellation_tracker,
+                    )
+                    .saturating_sub(star.connections_to.len() as u32),
+                })
+                .collect();
+            let view = EmpireView {
+                empire: rival.id,
+                resources: &rival.resources,
+                stars: &stars,
+            };
+            rival.strategy.decide(&view)
+        };
+
+        let result = match action {
+            None => continue,
+            Some(EmpireAction::Colonize { from, to }) => {
+                connect(rival.id, from, to, true, &mut star_query, &connection_query).map(|()| {
+                    commands.spawn(Connection::new(from, to));
+                })
+            }
+            Some(EmpireAction::Connect { from, to }) => connect(
+                rival.id,
+                from,
+                to,
+                false,
+                &mut star_query,
+                &connection_query,
+            )
+            .map(|()| {
+                commands.spawn(Connection::new(from, to));
+            }),
+            Some(EmpireAction::SendFleet { from, to, mission }) => {
+                match star_query.get_many_mut([from, to]) {
+                    Ok([(_, mut from_star, from_transform), (_, to_star, to_transform)])
+                        if from_star.is_owned_by(rival.id) =>
+                    {
+                        launch_fleet(
+                            (from, &mut from_star, from_transform),
+                            (to, &to_star, to_transform),
+                            connection_between(&connection_query, from, to),
+                            mission,
+                        )
+                        .map(|fleet| {
+                            commands.spawn(fleet);
+                        })
+                        .map_err(|err| err.to_string())
+                    }
+                    _ => Err("not one of its colonies".to_string()),
+                }
+            }
+            Some(EmpireAction::Specialize { star, spec }) => match star_query.get_mut(star) {
+                Ok((_, mut star, _)) if star.is_owned_by(rival.id) && !star.is_home_star => {
+                    construction::specialize(&mut star, spec, &mut rival.resources)
+                        .map_err(|err| err.to_string())
+                }
+                _ => Err("not one of its colonies".to_string()),
+            },
+            Some(EmpireAction::Upgrade { star }) => match star_query.get_mut(star) {
+                // Rivals do no research
+                Ok((_, mut star, _)) if star.is_owned_by(rival.id) => {
+                    construction::upgrade(&mut star, &mut rival.resources, &TechTree::default())
+                        .map_err(|err| err.to_string())
+                }
+                _ => Err("not one of its colonies".to_string()),
+            },
+        };
+        if let Err(err) = result {
+            debug!("{} could not {:?}: {}", rival.id.name(), action, err);
+        }
+    }
+}
+
+// Connect one of `empire`'s stars to `to`, claiming it when `claim` is set and
+// opening a front line to another empire's star otherwise
+// The connection entity itself is left to the caller
+fn connect(
+    empire: EmpireId,
+    from: Entity,
+    to: Entity,
+    claim: bool,
+    star_query: &mut Query<(Entity, &mut Star, Option<&Transform>)>,
+    connection_query: &Query<(Entity, &Connection)>,
+) -> Result<(), String> {
+    if connection_between(connection_query, from, to).is_some() {
+        return Err("already connected".to_string());
+    }
+    let Ok([(_, mut from_star, _), (_, mut to_star, _)]) = star_query.get_many_mut([from, to])
+    else {
+        return Err("unknown star".to_string());
+    };
+    if !from_star.is_owned_by(empire) {
+        return Err("origin is not one of its colonies".to_string());
+    }
+    if claim && to_star.is_colonized {
+        return Err(format!("{} is already taken", to_star.name));
+    }
+    if !claim && (!to_star.is_colonized || to_star.empire == empire) {
+        return Err(format!("{} is not another empire's star", to_star.name));
+    }
+
+    if claim {
+        to_star.colonize_by(empire);
+    }
+    to_star.connections_from.push(from);
+    from_star.connections_to.push(to);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn star(empire: Option<EmpireId>) -> Star {
+        let mut star = Star::new_colony(&[]);
+        star.is_colonized = empire.is_some();
+        star.empire = empire.unwrap_or_default();
+        star
+    }
+
+    #[test]
+    fn expansion_claims_the_nearest_free_star_in_reach() {
+        let rival = EmpireId(1);
+        let stars = [
+            star(Some(rival)),
+            star(None),
+            star(None),
+            star(Some(EmpireId::PLAYER)),
+        ];
+        let resources = PlayerResources::default();
+        let decide = |positions: [f32; 4]| {
+            let views: Vec<StarView> = stars
+                .iter()
+                .zip(positions)
+                .enumerate()
+                .map(|(i, (star, x))| StarView {
+                    entity: Entity::from_raw(i as u32),
+                    position: Vec2::new(x, 0.0),
+                    star,
+                    free_connections: 1,
+                })
+                .collect();
+            ExpansionStrategy.decide(&EmpireView {
+                empire: rival,
+                resources: &resources,
+                stars: &views,
+            })
+        };
+
+        assert_eq!(
+            decide([0.0, 250.0, 100.0, 50.0]),
+            Some(EmpireAction::Colonize {
+                from: Entity::from_raw(0),
+                to: Entity::from_raw(2),
+            })
+        );
+        // Free stars out of reach are left alone, and the one owned star
+        // still has a connection to spare, so there is nothing to upgrade
+        assert_eq!(decide([0.0, MAX_REACH + 1.0, -MAX_REACH - 1.0, 50.0]), None);
+    }
+
+    #[test]
+    fn expansion_attacks_only_stars_it_can_take() {
+        let rival = EmpireId(1);
+        let resources = PlayerResources::default();
+        let decide = |garrison: u32, defenders: u32, connected: bool| {
+            let mut attacker = star(Some(rival));
+            attacker.add_units(UnitType::Warship, garrison);
+            let mut defender = star(Some(EmpireId::PLAYER));
+            defender.add_units(UnitType::Warship, defenders);
+            if connected {
+                attacker.connections_to.push(Entity::from_raw(1));
+            }
+            let stars = [attacker, defender];
+            let views: Vec<StarView> = stars
+                .iter()
+                .enumerate()
+                .map(|(i, star)| StarView {
+                    entity: Entity::from_raw(i as u32),
+                    position: Vec2::new(i as f32 * 100.0, 0.0),
+                    star,
+                    free_connections: 1,
+                })
+                .collect();
+            ExpansionStrategy.decide(&EmpireView {
+                empire: rival,
+                resources: &resources,
+                stars: &views,
+            })
+        };
+
+        assert_eq!(
+            decide(5, 2, true),
+            Some(EmpireAction::SendFleet {
+                from: Entity::from_raw(0),
+                to: Entity::from_raw(1),
+                mission: Mission::Attack,
+            })
+        );
+        assert_eq!(
+            decide(5, 2, false),
+            Some(EmpireAction::Connect {
+                from: Entity::from_raw(0),
+                to: Entity::from_raw(1),
+            })
+        );
+        assert_eq!(decide(3, 2, true), None);
+    }
+
+    #[test]
+    fn rivals_get_their_own_stockpile() {
+        let mut rivals = RivalEmpires::new(2);
+        let mut player = PlayerResources::default();
+        assert_eq!(rivals.rivals[1].id, EmpireId(2));
+
+        rivals
+            .stockpile(&mut player, EmpireId(2))
+            .unwrap()
+            .resources
+            .clear();
+        assert!(rivals
+            .get(EmpireId(2))
+            .unwrap()
+            .resources
+            .resources
+            .is_empty());
+        assert!(!rivals
+            .get(EmpireId(1))
+            .unwrap()
+            .resources
+            .resources
+            .is_empty());
+        assert!(rivals.stockpile(&mut player, EmpireId(3)).is_none());
+    }
+}
@@ -0,0 +1,144 @@
+//! Typed events for notable game occurrences
+//!
+//! Core systems and the player input code send these so UI, audio,
+//! achievements or analytics can react without touching the rules themselves.
+//! Constellations breaking up and zodiac signs being completed have their own
+//! events in `constellation` and `zodiac`. Events describe the player's own
+//! empire, rival empires play without sending any, except for the battles and
+//! captures the player takes part in. Stellar evolution is seen galaxy-wide.
+
+use crate::constellation::ConstellationKind;
+use crate::empire::EmpireId;
+use crate::lifecycle::Evolution;
+use crate::resources::ResourceType;
+use crate::star::UnitType;
+use bevy::prelude::*;
+use std::fmt;
+
+// A star was claimed by a new connection
+#[derive(Event, Debug, Clone, Copy)]
+pub struct StarColonized {
+    pub star: Entity,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ConnectionCreated {
+    pub connection: Entity,
+    pub from: Entity,
+    pub to: Entity,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ConnectionRemoved {
+    pub from: Entity,
+    pub to: Entity,
+}
+
+// A star ran out of resources and its connection stopped collecting until it regrows
+#[derive(Event, Debug, Clone, Copy)]
+pub struct StarDepleted {
+    pub star: Entity,
+}
+
+// A colony ran out of colonists after a life support shortage
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ColonyAbandoned {
+    pub star: Entity,
+}
+
+// A star evolved into a new stage, changing its resources and color
+#[derive(Event, Debug, Clone, Copy)]
+pub struct StarEvolved {
+    pub star: Entity,
+    pub evolution: Evolution,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct UpgradeCompleted {
+    pub star: Entity,
+    pub level: u8,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct ConstellationFormed {
+    pub id: u32,
+    pub stars: Vec<Entity>,
+    pub kind: ConstellationKind,
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct UnitsProduced {
+    pub star: Entity,
+    pub unit_type: UnitType,
+    pub count: u32,
+}
+
+// An industrial star refined a batch of goods and shipped it as cargo
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GoodsRefined {
+    pub star: Entity,
+    pub resource_type: ResourceType,
+    pub amount: f32,
+}
+
+// Cargo reached a storage hub and was stored
+#[derive(Event, Debug, Clone, Copy)]
+pub struct CargoDelivered {
+    pub hub: Entity,
+    pub resource_type: ResourceType,
+    pub amount: f32,
+}
+
+// Warships fought over a star, sent when the player is one of the sides
+#[derive(Event, Debug, Clone, Copy)]
+pub struct BattleFought {
+    pub star: Entity,
+    pub attacker: EmpireId,
+    pub defender: EmpireId,
+    pub attacker_losses: u32,
+    pub defender_losses: u32,
+}
+
+// A star changed owner after a battle, sent when the player won or lost it
+#[derive(Event, Debug, Clone, Copy)]
+pub struct StarCaptured {
+    pub star: Entity,
+    pub from: EmpireId,
+    pub to: EmpireId,
+    pub capital: bool, // The star was the home star of `from`
+}
+
+// Why a connection the player tried to make was not created
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionRefusal {
+    LimitReached { max: u32 }, // Origin already uses all its outbound connections
+    AlreadyConnected,
+    OriginNotColonized,
+    RivalTerritory(EmpireId), // The origin belongs to a rival empire
+}
+
+impl fmt::Display for ConnectionRefusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionRefusal::LimitReached { max } => write!(
+                f,
+                "Connection limit reached ({} outbound), upgrade the star for more",
+                max
+            ),
+            ConnectionRefusal::AlreadyConnected => write!(f, "These stars are already connected"),
+            ConnectionRefusal::OriginNotColonized => {
+                write!(f, "Connections must start from a colonized star")
+            }
+            ConnectionRefusal::RivalTerritory(empire) => {
+                write!(f, "That star belongs to the {}", empire.name())
+            }
+        }
+    }
+}
+
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ConnectionRefused {
+    pub from: Entity,
+    pub to: Entity,
+    pub reason: ConnectionRefusal,
+}
@@ -0,0 +1,239 @@
+//! Procedural generation of stars
+
+use crate::empire::{EmpireId, MAX_RIVALS};
+use crate::layout::GalaxyConfig;
+use crate::options::LaunchOptions;
+use crate::resources::ResourceType;
+use crate::star::{BuildingState, Specialization, Star};
+use crate::units::BASE_POPULATION;
+use bevy::prelude::*;
+use rand::{prelude::*, rngs::StdRng};
+use std::collections::HashMap;
+
+// Seed that drives every random roll of galaxy generation
+// The same seed always yields the same galaxy, so bug reports can be reproduced
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GalaxySeed(pub u64);
+
+impl GalaxySeed {
+    // Pick a fresh random seed
+    pub fn random() -> Self {
+        Self(rand::thread_rng().gen())
+    }
+
+    // Read the `seed` option, falling back to a random seed
+    pub fn from_options(options: &LaunchOptions) -> Self {
+        options.parse("seed").map(Self).unwrap_or_else(Self::random)
+    }
+
+    pub fn rng(&self) -> StdRng {
+        StdRng::seed_from_u64(self.0)
+    }
+}
+
+// Star name generator
+pub fn generate_star_name(rng: &mut impl Rng) -> String {
+    let prefixes = [
+        "Alpha", "Beta", "Gamma", "Delta", "Epsilon", "Zeta", "Eta", "Theta", "Iota", "Kappa",
+    ];
+    let suffixes = [
+        "Centauri",
+        "Orionis",
+        "Draconis",
+        "Pegasi",
+        "Andromedae",
+        "Leonis",
+        "Aquarii",
+        "Scorpii",
+        "Tauri",
+        "Geminorum",
+    ];
+    let prefix = prefixes[rng.gen_range(0..prefixes.len())];
+    let suffix = suffixes[rng.gen_range(0..suffixes.len())];
+    format!("{} {}", prefix, suffix)
+}
+
+// Get star color based on dominant resource type
+pub fn get_star_color_from_resources(resources: &HashMap<ResourceType, f32>) -> Color {
+    // Find the dominant resource type
+    let mut dominant_resource = None;
+    let mut max_amount = 0.0;
+
+    for (resource_type, amount) in resources {
+        if *amount > max_amount {
+            max_amount = *amount;
+            dominant_resource = Some(*resource_type);
+        }
+    }
+
+    // Return color based on dominant resource with HDR values for bloom
+    match dominant_resource {
+        Some(ResourceType::Water) => Color::srgba(0.3, 0.6, 4.0, 1.0), // Deep blue
+        Some(ResourceType::Oxygen) => Color::srgba(0.7, 3.0, 4.0, 1.0), // Cyan
+        Some(ResourceType::Food) => Color::srgba(0.3, 4.0, 0.3, 1.0),  // Green
+        Some(ResourceType::Iron) => Color::srgba(2.5, 2.5, 3.0, 1.0),  // Silver-gray
+        Some(ResourceType::Copper) => Color::srgba(4.0, 2.0, 0.8, 1.0), // Orange-copper
+        Some(ResourceType::Silicon) => Color::srgba(3.0, 3.0, 4.0, 1.0), // Light blue-white
+        Some(ResourceType::Uranium) => Color::srgba(0.5, 4.0, 0.5, 1.0), // Radioactive green
+        Some(ResourceType::Helium3) => Color::srgba(4.0, 3.0, 0.0, 1.0), // Yellow-gold
+        Some(ResourceType::EnergyCrystal) => Color::srgba(4.0, 0.5, 4.0, 1.0), // Purple
+        _ => Color::srgba(3.0, 3.0, 3.0, 1.0),                         // Default white
+    }
+}
+
+// Generate random resources for a star
+pub fn generate_star_resources(
+    rng: &mut impl Rng,
+    is_home: bool,
+) -> (HashMap<ResourceType, f32>, HashMap<ResourceType, f32>) {
+    let mut resources = HashMap::new();
+    let mut max_resources = HashMap::new();
+
+    if is_home {
+        // Home star has balanced resources
+        for resource in [
+            ResourceType::Water,
+            ResourceType::Oxygen,
+            ResourceType::Food,
+            ResourceType::Iron,
+            ResourceType::Copper,
+            ResourceType::Silicon,
+        ] {
+            let amount = rng.gen_range(100.0..200.0);
+            resources.insert(resource, amount);
+            max_resources.insert(resource, amount);
+        }
+    } else {
+        // Other stars have random resources (1-3 types)
+        let num_resources = rng.gen_range(1..=3);
+
+        let mut selected_resources = ResourceType::RAW.to_vec();
+        selected_resources.shuffle(rng);
+
+        for &resource in selected_resources.iter().take(num_resources) {
+            let amount = match resource {
+                ResourceType::EnergyCrystal | ResourceType::Helium3 => rng.gen_range(5.0..30.0),
+                ResourceType::Uranium => rng.gen_range(10.0..50.0),
+                _ => rng.gen_range(50.0..150.0),
+            };
+            resources.insert(resource, amount);
+            max_resources.insert(resource, amount);
+        }
+    }
+
+    (resources, max_resources)
+}
+
+// Home star has a special golden color
+pub const HOME_STAR_COLOR: Color = Color::srgba(4.0, 3.5, 0.5, 1.0);
+
+// Positions of the rival home stars, each as far as possible from the homes
+// already placed, the player's home being the first position
+pub fn rival_home_indices(positions: &[Vec2], rivals: usize) -> Vec<usize> {
+    let mut homes = vec![0];
+    for _ in 0..rivals.min(positions.len().saturating_sub(1)) {
+        let farthest = (1..positions.len())
+            .filter(|index| !homes.contains(index))
+            .max_by(|a, b| {
+                let gap = |index: &usize| {
+                    homes
+                        .iter()
+                        .map(|home| positions[*home].distance(positions[*index]))
+                        .fold(f32::INFINITY, f32::min)
+                };
+                gap(a).total_cmp(&gap(b))
+            });
+        homes.extend(farthest);
+    }
+    homes.split_off(1)
+}
+
+// Colonized storage hub an empire starts from
+fn home_star(rng: &mut impl Rng, id: usize, empire: EmpireId) -> Star {
+    let (_home_resources, home_max) = generate_star_resources(rng, true);
+
+    // Calculate storage capacity (10% of max capacity for each resource)
+    let mut storage_capacity = HashMap::new();
+    let mut storage_resources = HashMap::new();
+    for (resource_type, max_value) in &home_max {
+        let capacity = max_value * 10.0; // Storage hub has 10x the capacity
+        storage_capacity.insert(*resource_type, capacity);
+        // Start with 10% of storage capacity filled
+        storage_resources.insert(*resource_type, capacity * 0.1);
+    }
+
+    let name = if empire.is_player() {
+        "Sol System".to_string()
+    } else {
+        format!("{} Capital", empire.name())
+    };
+    Star {
+        id,
+        name: format!("{} (Storage Hub)", name),
+        resources: storage_resources, // Use storage resources instead
+        max_resources: home_max,
+        production_rate: 2.0,
+        is_colonized: true,
+        is_home_star: true,
+        empire,
+        specialization: Specialization::Storage, // Set as Storage hub
+        specialization_level: 1,
+        units: HashMap::new(),
+        building_state: BuildingState::Ready,
+        connections_from: vec![],
+        connections_to: vec![],
+        base_color: HOME_STAR_COLOR,
+        storage_capacity,
+        is_storage_hub: true,
+        population: BASE_POPULATION,
+    }
+}
+
+// Generate every star of the galaxy with its position, all rolled from the seed
+// Rival empire `n` gets the `n`th position from `rival_home_indices` as its home
+pub fn generate_galaxy(seed: GalaxySeed, config: &GalaxyConfig) -> Vec<(Vec2, Star)> {
+    assert!(config.rivals <= MAX_RIVALS, "at most {} rivals", MAX_RIVALS);
+    let mut rng = seed.rng();
+    let positions = config.generate_positions(&mut rng);
+    let rival_homes = rival_home_indices(&positions, config.rivals);
+    let mut stars = Vec::with_capacity(positions.len());
+
+    // Home star sits at the first position (the center)
+    stars.push((positions[0], home_star(&mut rng, 0, EmpireId::PLAYER)));
+
+    // Generate other stars
+    for (i, &pos) in positions.iter().enumerate().skip(1) {
+        if let Some(rank) = rival_homes.iter().position(|&home| home == i) {
+            let empire = EmpireId(rank as u8 + 1);
+            stars.push((pos, home_star(&mut rng, i, empire)));
+            continue;
+        }
+        let (star_resources, star_max) = generate_star_resources(&mut rng, false);
+        let star_color = get_star_color_from_resources(&star_resources);
+        stars.push((
+            pos,
+            Star {
+                id: i,
+                name: generate_star_name(&mut rng),
+                resources: star_resources,
+                max_resources: star_max,
+                production_rate: rng.gen_range(0.5..2.5),
+                is_colonized: false,
+                is_home_star: false,
+                empire: EmpireId::PLAYER,
+                specialization: Specialization::None,
+                specialization_level: 1,
+                units: HashMap::new(),
+                building_state: BuildingState::Ready,
+                connections_from: vec![],
+                connections_to: vec![],
+                base_color: star_color,
+                storage_capacity: HashMap::new(),
+                is_storage_hub: false,
+                population: 0.0,
+            },
+        ));
+    }
+
+    stars
+}
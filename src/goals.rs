@@ -0,0 +1,470 @@
+//! Victory and defeat conditions, and the statistics shown when the game ends
+//!
+//! The game ends with the first victory or defeat condition that holds. Defeats
+//! are checked first, so losing and winning in the same frame is a loss. Once
+//! the game has ended the simulation is paused, but the player may keep playing
+//! without further checks.
+//!
+//! A zodiac sign held by another empire cannot be completed by the player, so
+//! the zodiac victory is out of reach until that constellation falls. The
+//! player is told when it becomes unreachable and when it is open again.
+
+use crate::clock::SimulationClock;
+use crate::constellation::ConstellationTracker;
+use crate::empire::EmpireId;
+use crate::events::{
+    BattleFought, CargoDelivered, ColonyAbandoned, ConnectionCreated, ConnectionRemoved,
+    ConstellationFormed, StarCaptured, StarColonized, StarDepleted, UnitsProduced,
+    UpgradeCompleted,
+};
+use crate::options::LaunchOptions;
+use crate::resources::{PlayerResources, ResourceType};
+use crate::star::Star;
+use crate::zodiac::{ZodiacCompleted, ZodiacSign};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Resources colonists need, running out of any of them loses the game
+pub const LIFE_SUPPORT: [ResourceType; 3] = [
+    ResourceType::Water,
+    ResourceType::Oxygen,
+    ResourceType::Food,
+];
+
+// Stock below which a life support resource counts as exhausted
+const EXHAUSTED: f32 = 0.01;
+
+// Default victory targets
+const COLONIZED_PERCENT: f32 = 60.0;
+const CONSTELLATIONS: u32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Victory {
+    Colonized(f32),                      // Share of all stars the player owns, from 0 to 1
+    Constellations(u32),                 // Constellations standing at the same time
+    Stockpile(Vec<(ResourceType, f32)>), // Stored amount of every listed resource
+    Zodiac,                              // Every zodiac sign completed at least once
+}
+
+impl Victory {
+    pub fn describe(&self) -> String {
+        match self {
+            Victory::Colonized(share) => format!("Colonize {:.0}% of the stars", share * 100.0),
+            Victory::Constellations(count) => format!("Hold {} constellations", count),
+            Victory::Stockpile(targets) => {
+                let targets: Vec<String> = targets
+                    .iter()
+                    .map(|(resource_type, amount)| {
+                        format!("{:.0} {}", amount, resource_type.name())
+                    })
+                    .collect();
+                format!("Stockpile {}", targets.join(", "))
+            }
+            Victory::Zodiac => "Complete all 12 zodiac signs".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Defeat {
+    HomeIsolated,              // The home star lost every connection to its colonies
+    HomeCaptured,              // Another empire took the home star
+    LifeSupport(ResourceType), // Colonists ran out of a life support resource
+}
+
+impl Defeat {
+    pub fn describe(&self) -> String {
+        match self {
+            Defeat::HomeIsolated => "The home star was cut off from its colonies".to_string(),
+            Defeat::HomeCaptured => "The home star was captured".to_string(),
+            Defeat::LifeSupport(resource_type) => {
+                format!("The colonies ran out of {}", resource_type.name())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameOutcome {
+    Victory(Victory),
+    Defeat(Defeat),
+}
+
+impl GameOutcome {
+    pub fn is_victory(&self) -> bool {
+        matches!(self, GameOutcome::Victory(_))
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            GameOutcome::Victory(victory) => victory.describe(),
+            GameOutcome::Defeat(defeat) => defeat.describe(),
+        }
+    }
+}
+
+// Sent once, when the first victory or defeat condition holds
+#[derive(Event, Debug, Clone)]
+pub struct GameEnded {
+    pub outcome: GameOutcome,
+}
+
+// Sent while the zodiac victory is active, when another empire starts or
+// stops blocking it by holding a sign
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct ZodiacVictoryBlocked {
+    pub held_by: Option<(ZodiacSign, EmpireId)>, // None once it is reachable again
+}
+
+// Conditions that end the game
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct GameGoals {
+    pub victories: Vec<Victory>,
+    pub home_isolation: bool, // Lose when the home star is cut off from its colonies
+    pub home_capture: bool,   // Lose when the home star is captured
+    pub life_support: bool,   // Lose when water, oxygen or food runs out
+}
+
+impl Default for GameGoals {
+    fn default() -> Self {
+        Self {
+            victories: vec![
+                Victory::Colonized(COLONIZED_PERCENT / 100.0),
+                Victory::Constellations(CONSTELLATIONS),
+                Victory::Zodiac,
+            ],
+            home_isolation: true,
+            home_capture: true,
+            life_support: true,
+        }
+    }
+}
+
+impl GameGoals {
+    // Endless game without any condition
+    pub fn sandbox() -> Self {
+        Self {
+            victories: Vec::new(),
+            home_isolation: false,
+            home_capture: false,
+            life_support: false,
+        }
+    }
+
+    // Read `win_colonized` (percent), `win_constellations`, `win_stockpile`
+    // (`Iron:500,Water:300`), `win_zodiac`, `lose_isolated`, `lose_home_captured`
+    // and `lose_life_support`
+    // A percent or count of 0 and an empty stockpile turn that victory off
+    pub fn from_options(options: &LaunchOptions) -> Self {
+        let mut victories = Vec::new();
+
+        let percent = options
+            .parse::<f32>("win_colonized")
+            .unwrap_or(COLONIZED_PERCENT);
+        if percent > 0.0 {
+            victories.push(Victory::Colonized((percent / 100.0).min(1.0)));
+        }
+
+        let constellations = options
+            .parse::<u32>("win_constellations")
+            .unwrap_or(CONSTELLATIONS);
+        if constellations > 0 {
+            victories.push(Victory::Constellations(constellations));
+        }
+
+        if let Some(value) = options.get("win_stockpile") {
+            match parse_stockpile(value) {
+                Some(targets) if !targets.is_empty() => victories.push(Victory::Stockpile(targets)),
+                Some(_) => {}
+                None => warn!(
+                    "Ignoring invalid value {:?} for option win_stockpile",
+                    value
+                ),
+            }
+        }
+
+        if options.parse::<bool>("win_zodiac").unwrap_or(true) {
+            victories.push(Victory::Zodiac);
+        }
+
+        Self {
+            victories,
+            home_isolation: options.parse("lose_isolated").unwrap_or(true),
+            home_capture: options.parse("lose_home_captured").unwrap_or(true),
+            life_support: options.parse("lose_life_support").unwrap_or(true),
+        }
+    }
+}
+
+// Parse `Iron:500,Water:300`, resource names match without case, spaces or dashes
+pub fn parse_stockpile(value: &str) -> Option<Vec<(ResourceType, f32)>> {
+    let simplify = |name: &str| {
+        name.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase()
+    };
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (name, amount) = entry.split_once(':')?;
+            let resource_type = ResourceType::ALL
+                .into_iter()
+                .find(|resource_type| simplify(resource_type.name()) == simplify(name))?;
+            Some((resource_type, amount.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+// What happened during the game, for the end-of-game summary
+#[derive(Resource, Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionStats {
+    pub elapsed: f32, // Simulated seconds played
+    pub stars_colonized: u32,
+    pub connections_built: u32,
+    pub connections_removed: u32,
+    pub constellations_formed: u32,
+    pub zodiac_signs: Vec<ZodiacSign>, // Every sign completed at least once
+    pub upgrades: u32,
+    pub units_produced: u32,
+    pub stars_depleted: u32,
+    pub colonies_abandoned: u32,
+    pub battles_fought: u32,
+    pub stars_captured: u32, // Taken by the player
+    pub stars_lost: u32,     // Taken from the player
+    pub home_lost: bool,     // The player's home star was captured
+    pub resources_delivered: HashMap<ResourceType, f32>,
+    pub outcome: Option<GameOutcome>, // Set once the game has ended
+}
+
+impl SessionStats {
+    pub fn total_delivered(&self) -> f32 {
+        self.resources_delivered.values().sum()
+    }
+}
+
+pub fn record_session_stats(
+    time: Res<Time>,
+    mut stats: ResMut<SessionStats>,
+    mut colonized: EventReader<StarColonized>,
+    mut created: EventReader<ConnectionCreated>,
+    mut removed: EventReader<ConnectionRemoved>,
+    mut formed: EventReader<ConstellationFormed>,
+    mut zodiac: EventReader<ZodiacCompleted>,
+    mut upgraded: EventReader<UpgradeCompleted>,
+    mut produced: EventReader<UnitsProduced>,
+    mut depleted: EventReader<StarDepleted>,
+    mut delivered: EventReader<CargoDelivered>,
+    mut battles: EventReader<BattleFought>,
+    mut captured: EventReader<StarCaptured>,
+    mut abandoned: EventReader<ColonyAbandoned>,
+) {
+    stats.elapsed += time.delta_seconds();
+    stats.stars_colonized += colonized.read().count() as u32;
+    stats.connections_built += created.read().count() as u32;
+    stats.connections_removed += removed.read().count() as u32;
+    stats.constellations_formed += formed.read().count() as u32;
+    stats.upgrades += upgraded.read().count() as u32;
+    stats.stars_depleted += depleted.read().count() as u32;
+    stats.battles_fought += battles.read().count() as u32;
+    stats.colonies_abandoned += abandoned.read().count() as u32;
+    for event in captured.read() {
+        if event.to.is_player() {
+            stats.stars_captured += 1;
+        } else {
+            stats.stars_lost += 1;
+            stats.home_lost |= event.capital;
+        }
+    }
+    for event in zodiac.read() {
+        if !stats.zodiac_signs.contains(&event.sign) {
+            stats.zodiac_signs.push(event.sign);
+        }
+    }
+    for event in produced.read() {
+        stats.units_produced += event.count;
+    }
+    for event in delivered.read() {
+        *stats
+            .resources_delivered
+            .entry(event.resource_type)
+            .or_insert(0.0) += event.amount;
+    }
+}
+
+// The first defeat or victory condition that currently holds for the player
+pub fn evaluate_goals(
+    goals: &GameGoals,
+    stars: &[&Star],
+    tracker: &ConstellationTracker,
+    player_resources: &PlayerResources,
+    stats: &SessionStats,
+) -> Option<GameOutcome> {
+    if goals.home_isolation {
+        let has_colonies = stars
+            .iter()
+            .any(|star| star.is_owned_by(EmpireId::PLAYER) && !star.is_home_star);
+        let home_cut_off = stars.iter().any(|star| {
+            star.is_home_star
+                && star.is_owned_by(EmpireId::PLAYER)
+                && star.connections_from.is_empty()
+                && star.connections_to.is_empty()
+        });
+        if has_colonies && home_cut_off {
+            return Some(GameOutcome::Defeat(Defeat::HomeIsolated));
+        }
+    }
+    if goals.home_capture && stats.home_lost {
+        return Some(GameOutcome::Defeat(Defeat::HomeCaptured));
+    }
+    if goals.life_support {
+        if let Some(resource_type) = LIFE_SUPPORT
+            .into_iter()
+            .find(|resource_type| player_resources.amount(*resource_type) < EXHAUSTED)
+        {
+            return Some(GameOutcome::Defeat(Defeat::LifeSupport(resource_type)));
+        }
+    }
+
+    goals
+        .victories
+        .iter()
+        .find(|victory| match victory {
+            Victory::Colonized(share) => {
+                let colonized = stars
+                    .iter()
+                    .filter(|star| star.is_owned_by(EmpireId::PLAYER))
+                    .count();
+                !stars.is_empty() && colonized as f32 >= share * stars.len() as f32
+            }
+            Victory::Constellations(count) => {
+                let owned = tracker
+                    .constellations
+                    .iter()
+                    .filter(|constellation| constellation.empire.is_player())
+                    .count();
+                owned as u32 >= *count
+            }
+            Victory::Stockpile(targets) => targets
+                .iter()
+                .all(|(resource_type, amount)| player_resources.amount(*resource_type) >= *amount),
+            Victory::Zodiac => stats.zodiac_signs.len() == ZodiacSign::ALL.len(),
+        })
+        .map(|victory| GameOutcome::Victory(victory.clone()))
+}
+
+// A sign held by another empire, which keeps the player from completing them all
+pub fn zodiac_sign_held_by_rival(tracker: &ConstellationTracker) -> Option<(ZodiacSign, EmpireId)> {
+    tracker
+        .constellations
+        .iter()
+        .filter(|constellation| !constellation.empire.is_player())
+        .find_map(|constellation| Some((constellation.zodiac?, constellation.empire)))
+}
+
+// Tell the player when the zodiac victory goes out of reach or comes back
+pub fn watch_zodiac_victory(
+    goals: Res<GameGoals>,
+    tracker: Res<ConstellationTracker>,
+    mut held_by: Local<Option<(ZodiacSign, EmpireId)>>,
+    mut blocked_events: EventWriter<ZodiacVictoryBlocked>,
+) {
+    if !goals.victories.contains(&Victory::Zodiac) {
+        return;
+    }
+    let current = zodiac_sign_held_by_rival(&tracker);
+    if current != *held_by {
+        *held_by = current;
+        blocked_events.send(ZodiacVictoryBlocked { held_by: current });
+    }
+}
+
+// End the game and pause the simulation once a condition holds
+pub fn check_game_goals(
+    goals: Res<GameGoals>,
+    star_query: Query<&Star>,
+    tracker: Res<ConstellationTracker>,
+    player_resources: Res<PlayerResources>,
+    mut stats: ResMut<SessionStats>,
+    mut clock: ResMut<SimulationClock>,
+    mut ended_events: EventWriter<GameEnded>,
+) {
+    if stats.outcome.is_some() {
+        return;
+    }
+
+    let stars: Vec<&Star> = star_query.iter().collect();
+    if let Some(outcome) = evaluate_goals(&goals, &stars, &tracker, &player_resources, &stats) {
+        info!("Game over: {}", outcome.describe());
+        stats.outcome = Some(outcome.clone());
+        clock.paused = true;
+        ended_events.send(GameEnded { outcome });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stockpile_names_ignore_case_and_punctuation() {
+        assert_eq!(
+            parse_stockpile("iron:500, Helium 3: 20,energycrystal:5"),
+            Some(vec![
+                (ResourceType::Iron, 500.0),
+                (ResourceType::Helium3, 20.0),
+                (ResourceType::EnergyCrystal, 5.0),
+            ])
+        );
+        assert_eq!(parse_stockpile(""), Some(vec![]));
+        assert_eq!(parse_stockpile("Unobtanium:5"), None);
+        assert_eq!(parse_stockpile("Iron"), None);
+    }
+
+    #[test]
+    fn options_turn_victories_off_and_on() {
+        let options = LaunchOptions::new(
+            [
+                "--win_colonized=0".to_string(),
+                "--win_stockpile=Iron:100".to_string(),
+            ],
+            "win_zodiac = false\nlose_isolated = false",
+        );
+        let goals = GameGoals::from_options(&options);
+        assert_eq!(
+            goals.victories,
+            vec![
+                Victory::Constellations(5),
+                Victory::Stockpile(vec![(ResourceType::Iron, 100.0)]),
+            ]
+        );
+        assert!(!goals.home_isolation);
+        assert!(goals.life_support);
+    }
+
+    #[test]
+    fn losing_the_home_star_is_a_defeat() {
+        let goals = GameGoals::default();
+        let mut stats = SessionStats::default();
+        let tracker = ConstellationTracker::default();
+        let resources = PlayerResources::default();
+        assert_eq!(
+            evaluate_goals(&goals, &[], &tracker, &resources, &stats),
+            None
+        );
+
+        stats.home_lost = true;
+        assert_eq!(
+            evaluate_goals(&goals, &[], &tracker, &resources, &stats),
+            Some(GameOutcome::Defeat(Defeat::HomeCaptured))
+        );
+    }
+
+    #[test]
+    fn default_options_give_the_default_goals() {
+        let options = LaunchOptions::new(Vec::new(), "");
+        assert_eq!(GameGoals::from_options(&options), GameGoals::default());
+    }
+}
@@ -0,0 +1,326 @@
+//! Graph searches over the star connection network
+
+use crate::connection::Connection;
+use crate::empire::EmpireId;
+use crate::star::Star;
+use bevy::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+// Route distance to the nearest storage hub, in connection hops
+// This measures the number of connection hops, not physical distance
+// A star can be physically close but have a long route distance if not directly connected
+// None means the star has no route to any storage hub
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RouteDistance(pub Option<u32>);
+
+// Hop count from every star to its nearest storage hub, found with one
+// breadth-first search starting from all hubs at once
+// Connections carry supplies both ways, so they are walked in both directions,
+// but only between stars of the same owner: a front line to another empire
+// carries nothing. Stars with no route to a hub are left out of the map
+pub fn storage_route_distances(stars: &[(Entity, &Star)]) -> HashMap<Entity, u32> {
+    let neighbours: HashMap<Entity, &Star> = stars.iter().copied().collect();
+    let mut distances = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for &(entity, star) in stars {
+        if star.is_storage_hub {
+            distances.insert(entity, 0);
+            queue.push_back(entity);
+        }
+    }
+
+    while let Some(current) = queue.pop_front() {
+        let hops = distances[&current] + 1;
+        let star = neighbours[&current];
+        for &next in star.connections_from.iter().chain(&star.connections_to) {
+            let same_owner = neighbours
+                .get(&next)
+                .is_some_and(|neighbour| neighbour.empire == star.empire);
+            if same_owner && !distances.contains_key(&next) {
+                distances.insert(next, hops);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    distances
+}
+
+// Recompute every `RouteDistance` when the connection graph, the set of
+// storage hubs or the owner of a star changes, instead of searching routes on
+// every collection
+pub fn update_route_distances(
+    mut commands: Commands,
+    star_query: Query<(Entity, &Star, Option<&RouteDistance>)>,
+    added_connections: Query<(), Added<Connection>>,
+    mut removed_connections: RemovedComponents<Connection>,
+    mut removed_stars: RemovedComponents<Star>,
+    mut owners: Local<HashMap<Entity, EmpireId>>,
+) {
+    // A hub is always 0 hops away and nothing else is, so a mismatch means a
+    // star became or stopped being a hub
+    let hubs_changed = star_query.iter().any(|(_, star, route)| match route {
+        Some(RouteDistance(distance)) => star.is_storage_hub != (*distance == Some(0)),
+        None => true,
+    });
+    let mut owners_changed = false;
+    for (entity, star, _) in &star_query {
+        if owners.insert(entity, star.empire) != Some(star.empire) {
+            owners_changed = true;
+        }
+    }
+    let connections_changed = !added_connections.is_empty()
+        || removed_connections.read().count() > 0
+        || removed_stars.read().count() > 0;
+    if !hubs_changed && !connections_changed && !owners_changed {
+        return;
+    }
+
+    let distances = {
+        let stars: Vec<(Entity, &Star)> = star_query
+            .iter()
+            .map(|(entity, star, _)| (entity, star))
+            .collect();
+        storage_route_distances(&stars)
+    };
+    for (entity, _, route) in &star_query {
+        let distance = RouteDistance(distances.get(&entity).copied());
+        if route != Some(&distance) {
+            commands.entity(entity).insert(distance);
+        }
+    }
+}
+
+// Shortest cycle closed by the connection between `from` and `to`: the
+// shortest route from `from` to `to` that does not use that connection
+// Stars for which `blocked` returns true are never walked through. The cycle
+// comes back in route order starting at `from`, with at least 3 stars.
+pub fn shortest_cycle_through(
+    from: Entity,
+    to: Entity,
+    stars: &Query<&Star>,
+    blocked: impl Fn(Entity) -> bool,
+) -> Option<Vec<Entity>> {
+    if from == to || blocked(from) || blocked(to) {
+        return None;
+    }
+
+    let mut previous: HashMap<Entity, Entity> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    previous.insert(from, from);
+
+    while let Some(current) = queue.pop_front() {
+        let Ok(star) = stars.get(current) else {
+            continue;
+        };
+        for &next in star.connections_from.iter().chain(&star.connections_to) {
+            // The closing connection itself does not count as a route
+            if current == from && next == to {
+                continue;
+            }
+            if previous.contains_key(&next) || blocked(next) {
+                continue;
+            }
+            previous.insert(next, current);
+            if next == to {
+                let mut cycle = vec![to];
+                let mut step = to;
+                while step != from {
+                    step = previous[&step];
+                    cycle.push(step);
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            queue.push_back(next);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::SystemState;
+
+    fn star(is_storage_hub: bool) -> Star {
+        let mut star = Star::new_colony(&[]);
+        star.is_storage_hub = is_storage_hub;
+        star
+    }
+
+    // Spawn one star per entry and connect them along `edges` (from, to)
+    fn spawn_graph(world: &mut World, hubs: &[bool], edges: &[(usize, usize)]) -> Vec<Entity> {
+        let entities: Vec<Entity> = hubs
+            .iter()
+            .map(|&hub| world.spawn(star(hub)).id())
+            .collect();
+        for &(from, to) in edges {
+            let (from, to) = (entities[from], entities[to]);
+            world.get_mut::<Star>(from).unwrap().connections_to.push(to);
+            world
+                .get_mut::<Star>(to)
+                .unwrap()
+                .connections_from
+                .push(from);
+        }
+        entities
+    }
+
+    fn distance(world: &mut World, entity: Entity) -> Option<u32> {
+        let mut state: SystemState<Query<(Entity, &Star)>> = SystemState::new(world);
+        let query = state.get(world);
+        let stars: Vec<(Entity, &Star)> = query.iter().collect();
+        storage_route_distances(&stars).get(&entity).copied()
+    }
+
+    fn cycle(
+        world: &mut World,
+        from: Entity,
+        to: Entity,
+        blocked: &[Entity],
+    ) -> Option<Vec<Entity>> {
+        let mut state: SystemState<Query<&Star>> = SystemState::new(world);
+        let query = state.get(world);
+        shortest_cycle_through(from, to, &query, |e| blocked.contains(&e))
+    }
+
+    #[test]
+    fn storage_hub_is_zero_hops_from_itself() {
+        let mut world = World::new();
+        let stars = spawn_graph(&mut world, &[true], &[]);
+        assert_eq!(distance(&mut world, stars[0]), Some(0));
+    }
+
+    #[test]
+    fn distance_follows_connections_in_both_directions() {
+        let mut world = World::new();
+        // hub -> 1 -> 2, and 3 -> 2
+        let stars = spawn_graph(
+            &mut world,
+            &[true, false, false, false],
+            &[(0, 1), (1, 2), (3, 2)],
+        );
+        assert_eq!(distance(&mut world, stars[1]), Some(1));
+        assert_eq!(distance(&mut world, stars[2]), Some(2));
+        assert_eq!(distance(&mut world, stars[3]), Some(3));
+    }
+
+    #[test]
+    fn distance_is_the_shortest_route() {
+        let mut world = World::new();
+        // A long way round hub -> 1 -> 2 -> 3 -> 4 and a shortcut hub -> 4
+        let stars = spawn_graph(
+            &mut world,
+            &[true, false, false, false, false],
+            &[(0, 1), (1, 2), (2, 3), (3, 4), (0, 4)],
+        );
+        assert_eq!(distance(&mut world, stars[4]), Some(1));
+        assert_eq!(distance(&mut world, stars[3]), Some(2));
+        assert_eq!(distance(&mut world, stars[2]), Some(2));
+    }
+
+    #[test]
+    fn distance_uses_the_nearest_hub() {
+        let mut world = World::new();
+        let stars = spawn_graph(
+            &mut world,
+            &[true, false, false, false, true],
+            &[(0, 1), (1, 2), (2, 3), (3, 4)],
+        );
+        assert_eq!(distance(&mut world, stars[3]), Some(1));
+        assert_eq!(distance(&mut world, stars[2]), Some(2));
+    }
+
+    #[test]
+    fn isolated_star_has_no_route() {
+        let mut world = World::new();
+        let stars = spawn_graph(&mut world, &[true, false, false], &[(1, 2)]);
+        assert_eq!(distance(&mut world, stars[1]), None);
+        assert_eq!(distance(&mut world, stars[2]), None);
+    }
+
+    #[test]
+    fn routes_stop_at_another_empire() {
+        let mut world = World::new();
+        let stars = spawn_graph(&mut world, &[true, false, false], &[(0, 1), (1, 2)]);
+        world.get_mut::<Star>(stars[1]).unwrap().empire = EmpireId(1);
+        assert_eq!(distance(&mut world, stars[1]), None);
+        assert_eq!(distance(&mut world, stars[2]), None);
+    }
+
+    #[test]
+    fn closing_a_triangle_finds_it() {
+        let mut world = World::new();
+        let stars = spawn_graph(&mut world, &[true, false, false], &[(0, 1), (1, 2), (2, 0)]);
+        assert_eq!(
+            cycle(&mut world, stars[2], stars[0], &[]),
+            Some(vec![stars[2], stars[1], stars[0]])
+        );
+    }
+
+    #[test]
+    fn chain_has_no_cycle() {
+        let mut world = World::new();
+        let stars = spawn_graph(
+            &mut world,
+            &[true, false, false, false],
+            &[(0, 1), (1, 2), (2, 3)],
+        );
+        assert_eq!(cycle(&mut world, stars[2], stars[3], &[]), None);
+    }
+
+    #[test]
+    fn two_stars_connected_both_ways_are_not_a_cycle() {
+        let mut world = World::new();
+        let stars = spawn_graph(&mut world, &[true, false], &[(0, 1), (1, 0)]);
+        assert_eq!(cycle(&mut world, stars[1], stars[0], &[]), None);
+    }
+
+    #[test]
+    fn finds_cycle_that_skips_the_first_star() {
+        let mut world = World::new();
+        // 0 -> 1, then a triangle 1 -> 2 -> 3 -> 1 that does not contain star 0
+        let stars = spawn_graph(
+            &mut world,
+            &[true, false, false, false],
+            &[(0, 1), (1, 2), (2, 3), (3, 1)],
+        );
+        let mut found = cycle(&mut world, stars[3], stars[1], &[]).unwrap();
+        found.sort();
+        assert_eq!(found, stars[1..].to_vec());
+    }
+
+    #[test]
+    fn picks_the_shortest_cycle() {
+        let mut world = World::new();
+        // Square 0-1-2-3 with the diagonal 0 -> 2 added last
+        let stars = spawn_graph(
+            &mut world,
+            &[true, false, false, false],
+            &[(0, 1), (1, 2), (2, 3), (3, 0), (0, 2)],
+        );
+        assert_eq!(cycle(&mut world, stars[0], stars[2], &[]).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn blocked_stars_are_routed_around() {
+        let mut world = World::new();
+        // Square with the diagonal 0 -> 2, star 1 is blocked
+        let stars = spawn_graph(
+            &mut world,
+            &[true, false, false, false],
+            &[(0, 1), (1, 2), (2, 3), (3, 0), (0, 2)],
+        );
+        assert_eq!(
+            cycle(&mut world, stars[0], stars[2], &[stars[1]]),
+            Some(vec![stars[0], stars[3], stars[2]])
+        );
+        assert_eq!(
+            cycle(&mut world, stars[0], stars[2], &[stars[1], stars[3]]),
+            None
+        );
+    }
+}
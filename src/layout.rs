@@ -0,0 +1,311 @@
+//! Galaxy size and star placement strategies
+//!
+//! Every layout keeps stars at least `min_distance` apart. When the requested
+//! number of stars does not fit, the layout is scaled up instead of letting
+//! stars overlap.
+
+use crate::empire::MAX_RIVALS;
+use crate::options::LaunchOptions;
+use bevy::prelude::*;
+use rand::prelude::*;
+use std::collections::HashMap;
+use std::f32::consts::TAU;
+use std::str::FromStr;
+
+// Candidates tried per star before the layout is scaled up
+const MAX_ATTEMPTS: u32 = 60;
+
+// Growth factor applied to the layout when it runs out of room
+const GROWTH: f32 = 1.15;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GalaxyLayout {
+    Uniform,                     // Poisson-disc sampling over the whole area
+    Spiral { arms: u32 },        // Stars along logarithmic-ish spiral arms
+    Clustered { clusters: u32 }, // Stars grouped around a few cluster centers
+    Ring,                        // Stars on a band around the home star
+}
+
+impl GalaxyLayout {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GalaxyLayout::Uniform => "uniform",
+            GalaxyLayout::Spiral { .. } => "spiral",
+            GalaxyLayout::Clustered { .. } => "clustered",
+            GalaxyLayout::Ring => "ring",
+        }
+    }
+}
+
+impl FromStr for GalaxyLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "uniform" => Ok(GalaxyLayout::Uniform),
+            "spiral" => Ok(GalaxyLayout::Spiral { arms: 2 }),
+            "clustered" => Ok(GalaxyLayout::Clustered { clusters: 4 }),
+            "ring" => Ok(GalaxyLayout::Ring),
+            other => Err(format!("unknown galaxy layout {}", other)),
+        }
+    }
+}
+
+// Size and shape of the generated galaxy
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct GalaxyConfig {
+    pub star_count: usize,  // Including the home star
+    pub half_extents: Vec2, // Stars are placed in [-half_extents, half_extents]
+    pub min_distance: f32,  // Minimum distance between two stars
+    pub layout: GalaxyLayout,
+    pub rivals: usize, // AI empires, each with a home star far from the player's, up to MAX_RIVALS
+}
+
+impl Default for GalaxyConfig {
+    fn default() -> Self {
+        Self {
+            star_count: 12,
+            half_extents: Vec2::new(300.0, 200.0),
+            min_distance: 90.0,
+            layout: GalaxyLayout::Uniform,
+            rivals: 1,
+        }
+    }
+}
+
+impl GalaxyConfig {
+    // Read `stars`, `width`, `height`, `min_distance`, `layout`, `arms`, `clusters`
+    // and `rivals`
+    pub fn from_options(options: &LaunchOptions) -> Self {
+        let mut config = Self::default();
+        if let Some(stars) = options.parse::<usize>("stars") {
+            config.star_count = stars.max(1);
+        }
+        if let Some(width) = options.parse::<f32>("width") {
+            config.half_extents.x = width.max(0.0) / 2.0;
+        }
+        if let Some(height) = options.parse::<f32>("height") {
+            config.half_extents.y = height.max(0.0) / 2.0;
+        }
+        if let Some(min_distance) = options.parse::<f32>("min_distance") {
+            config.min_distance = min_distance.max(1.0);
+        }
+        if let Some(layout) = options.parse::<GalaxyLayout>("layout") {
+            config.layout = layout;
+        }
+        if let Some(rivals) = options.parse::<usize>("rivals") {
+            config.rivals = rivals.min(MAX_RIVALS);
+        }
+        match &mut config.layout {
+            GalaxyLayout::Spiral { arms } => {
+                *arms = options.parse::<u32>("arms").unwrap_or(*arms).max(1);
+            }
+            GalaxyLayout::Clustered { clusters } => {
+                *clusters = options.parse::<u32>("clusters").unwrap_or(*clusters).max(1);
+            }
+            _ => {}
+        }
+        config
+    }
+
+    // Place `star_count` stars, the first one (home) at the origin
+    pub fn generate_positions(&self, rng: &mut impl Rng) -> Vec<Vec2> {
+        if self.layout == GalaxyLayout::Uniform {
+            return self.poisson_disc(rng);
+        }
+
+        let mut grid = SpacingGrid::new(self.min_distance);
+        grid.insert(Vec2::ZERO);
+        let mut positions = vec![Vec2::ZERO];
+        let mut scale = 1.0;
+
+        let cluster_centers = match self.layout {
+            // A layout built by hand may ask for no clusters or arms, take one
+            GalaxyLayout::Clustered { clusters } => (0..clusters.max(1))
+                .map(|_| {
+                    Vec2::new(
+                        rng.gen_range(-1.0..=1.0) * self.half_extents.x * 0.7,
+                        rng.gen_range(-1.0..=1.0) * self.half_extents.y * 0.7,
+                    )
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        while positions.len() < self.star_count {
+            let mut placed = false;
+            for _ in 0..MAX_ATTEMPTS {
+                let candidate = self.sample(rng, &cluster_centers) * scale;
+                if grid.is_free(candidate) {
+                    grid.insert(candidate);
+                    positions.push(candidate);
+                    placed = true;
+                    break;
+                }
+            }
+
+            if !placed {
+                scale *= GROWTH;
+                debug!("Galaxy layout out of room, scaling up to {:.2}x", scale);
+            }
+        }
+
+        positions
+    }
+
+    // Bridson's Poisson-disc sampling grown from the home star, then a random
+    // subset of the samples so the stars spread over the whole area
+    fn poisson_disc(&self, rng: &mut impl Rng) -> Vec<Vec2> {
+        let mut half_extents = self.half_extents.max(Vec2::splat(self.min_distance));
+        loop {
+            let mut grid = SpacingGrid::new(self.min_distance);
+            let mut samples = vec![Vec2::ZERO];
+            let mut active = vec![Vec2::ZERO];
+            grid.insert(Vec2::ZERO);
+
+            while !active.is_empty() {
+                let index = rng.gen_range(0..active.len());
+                let origin = active[index];
+                let mut found = false;
+
+                for _ in 0..MAX_ATTEMPTS {
+                    let angle = rng.gen_range(0.0..TAU);
+                    let radius = rng.gen_range(self.min_distance..2.0 * self.min_distance);
+                    let candidate = origin + Vec2::from_angle(angle) * radius;
+                    if candidate.x.abs() <= half_extents.x
+                        && candidate.y.abs() <= half_extents.y
+                        && grid.is_free(candidate)
+                    {
+                        grid.insert(candidate);
+                        samples.push(candidate);
+                        active.push(candidate);
+                        found = true;
+                        break;
+                    }
+                }
+
+                if !found {
+                    active.swap_remove(index);
+                }
+            }
+
+            if samples.len() >= self.star_count {
+                let home = samples.remove(0);
+                samples.shuffle(rng);
+                samples.truncate(self.star_count.saturating_sub(1));
+                samples.insert(0, home);
+                return samples;
+            }
+
+            half_extents *= GROWTH;
+            debug!(
+                "Galaxy bounds too small for {} stars, growing to {:?}",
+                self.star_count, half_extents
+            );
+        }
+    }
+
+    // Candidate position for the non-uniform layouts, before scaling
+    fn sample(&self, rng: &mut impl Rng, cluster_centers: &[Vec2]) -> Vec2 {
+        let radius = self.half_extents.min_element().max(self.min_distance);
+        match self.layout {
+            GalaxyLayout::Uniform => Vec2::new(
+                rng.gen_range(-self.half_extents.x..=self.half_extents.x),
+                rng.gen_range(-self.half_extents.y..=self.half_extents.y),
+            ),
+            GalaxyLayout::Spiral { arms } => {
+                let arms = arms.max(1);
+                let arm = rng.gen_range(0..arms) as f32;
+                let t: f32 = rng.gen_range(0.15..1.0);
+                let angle = arm * TAU / arms as f32 + t * 1.5 * TAU;
+                let jitter = jitter(rng) * self.min_distance * 0.5;
+                Vec2::from_angle(angle) * t * radius + jitter
+            }
+            GalaxyLayout::Clustered { .. } => {
+                let center = cluster_centers[rng.gen_range(0..cluster_centers.len())];
+                center + jitter(rng) * radius * 0.35
+            }
+            GalaxyLayout::Ring => {
+                let angle = rng.gen_range(0.0..TAU);
+                let distance = rng.gen_range(0.6..=1.0) * radius;
+                Vec2::from_angle(angle) * distance
+            }
+        }
+    }
+}
+
+// Roughly normal offset in [-1.5, 1.5] on each axis
+fn jitter(rng: &mut impl Rng) -> Vec2 {
+    let mut axis = || (0..3).map(|_| rng.gen_range(-0.5..0.5)).sum::<f32>();
+    Vec2::new(axis(), axis())
+}
+
+// Grid with cells small enough to hold at most one star, so spacing checks only
+// look at the neighbouring cells instead of every placed star
+struct SpacingGrid {
+    min_distance: f32,
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec2>,
+}
+
+impl SpacingGrid {
+    fn new(min_distance: f32) -> Self {
+        Self {
+            min_distance,
+            cell_size: min_distance / std::f32::consts::SQRT_2,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, pos: Vec2) -> (i32, i32) {
+        (
+            (pos.x / self.cell_size).floor() as i32,
+            (pos.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn is_free(&self, pos: Vec2) -> bool {
+        let (cx, cy) = self.cell(pos);
+        for x in (cx - 2)..=(cx + 2) {
+            for y in (cy - 2)..=(cy + 2) {
+                if let Some(other) = self.cells.get(&(x, y)) {
+                    if other.distance(pos) < self.min_distance {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn insert(&mut self, pos: Vec2) {
+        let cell = self.cell(pos);
+        self.cells.insert(cell, pos);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    fn positions(layout: GalaxyLayout) -> Vec<Vec2> {
+        let config = GalaxyConfig {
+            layout,
+            ..GalaxyConfig::default()
+        };
+        config.generate_positions(&mut StdRng::seed_from_u64(1))
+    }
+
+    #[test]
+    fn spiral_without_arms_uses_one() {
+        let stars = positions(GalaxyLayout::Spiral { arms: 0 });
+        assert_eq!(stars.len(), GalaxyConfig::default().star_count);
+    }
+
+    #[test]
+    fn clustered_without_clusters_uses_one() {
+        let stars = positions(GalaxyLayout::Clustered { clusters: 0 });
+        assert_eq!(stars.len(), GalaxyConfig::default().star_count);
+    }
+}
@@ -0,0 +1,135 @@
+//! Headless simulation core for the Zodiakos space colonization game
+//!
+//! Everything in here runs without a window, so the economy can be driven from
+//! tests, balance scripts or a server with just `MinimalPlugins`:
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use zodiakos::SimulationPlugin;
+//!
+//! App::new()
+//!     .add_plugins((MinimalPlugins, SimulationPlugin))
+//!     .run();
+//! ```
+
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+pub mod cargo;
+pub mod clock;
+pub mod combat;
+pub mod connection;
+pub mod constellation;
+pub mod construction;
+pub mod economy;
+pub mod empire;
+pub mod events;
+pub mod galaxy;
+pub mod goals;
+pub mod graph;
+pub mod layout;
+pub mod lifecycle;
+pub mod options;
+pub mod population;
+pub mod refining;
+pub mod research;
+pub mod resources;
+pub mod save;
+pub mod star;
+pub mod units;
+pub mod zodiac;
+
+use bevy::{prelude::*, time::TimeSystem};
+
+pub mod prelude {
+    pub use crate::cargo::CargoPacket;
+    pub use crate::clock::SimulationClock;
+    pub use crate::combat::{Fleet, Mission};
+    pub use crate::connection::Connection;
+    pub use crate::constellation::{Constellation, ConstellationTracker};
+    pub use crate::empire::{EmpireId, RivalEmpires};
+    pub use crate::galaxy::GalaxySeed;
+    pub use crate::goals::{GameGoals, SessionStats};
+    pub use crate::graph::RouteDistance;
+    pub use crate::layout::{GalaxyConfig, GalaxyLayout};
+    pub use crate::research::{Tech, TechTree};
+    pub use crate::resources::{PlayerResources, ResourceType};
+    pub use crate::star::{BuildingState, Specialization, Star, UnitType};
+    pub use crate::zodiac::ZodiacSign;
+    pub use crate::{SimulationPlugin, SimulationSet};
+}
+
+// All game rule systems run in this set, so presentation code can order itself after them
+// The timed rules run in `FixedUpdate`, the ones reacting to graph changes in `Update`
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationSet;
+
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<resources::PlayerResources>()
+            .init_resource::<constellation::ConstellationTracker>()
+            .init_resource::<research::TechTree>()
+            .init_resource::<save::SaveSettings>()
+            .init_resource::<clock::SimulationClock>()
+            .init_resource::<goals::GameGoals>()
+            .init_resource::<goals::SessionStats>()
+            .init_resource::<empire::RivalEmpires>()
+            .init_resource::<lifecycle::StellarAge>()
+            .add_event::<events::StarColonized>()
+            .add_event::<events::ConnectionCreated>()
+            .add_event::<events::ConnectionRemoved>()
+            .add_event::<events::ConnectionRefused>()
+            .add_event::<events::StarDepleted>()
+            .add_event::<events::UpgradeCompleted>()
+            .add_event::<events::ConstellationFormed>()
+            .add_event::<events::UnitsProduced>()
+            .add_event::<events::CargoDelivered>()
+            .add_event::<events::GoodsRefined>()
+            .add_event::<events::BattleFought>()
+            .add_event::<events::StarCaptured>()
+            .add_event::<events::ColonyAbandoned>()
+            .add_event::<events::StarEvolved>()
+            .add_event::<constellation::ConstellationDissolved>()
+            .add_event::<zodiac::ZodiacCompleted>()
+            .add_event::<goals::GameEnded>()
+            .add_event::<goals::ZodiacVictoryBlocked>()
+            .add_event::<save::SaveRequest>()
+            .add_event::<save::LoadRequest>()
+            .add_event::<save::GameLoaded>()
+            .add_systems(First, clock::apply_simulation_clock.after(TimeSystem))
+            .add_systems(
+                FixedUpdate,
+                (
+                    connection::age_connections,
+                    empire::run_rival_empires,
+                    combat::move_fleets,
+                    economy::collect_resources_system,
+                    cargo::move_cargo,
+                    units::apply_unit_effects,
+                    population::sustain_colonies,
+                    lifecycle::run_stellar_lifecycle,
+                )
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            // Graph changes made while paused must still be seen, and removed
+            // components are only kept for a frame or two
+            .add_systems(
+                Update,
+                (
+                    economy::update_storage_capacity,
+                    graph::update_route_distances,
+                    constellation::dissolve_broken_constellations,
+                    constellation::detect_and_create_constellations,
+                    zodiac::detect_zodiac_signs,
+                    goals::record_session_stats,
+                    goals::check_game_goals,
+                    goals::watch_zodiac_victory,
+                )
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(Update, save::handle_save_requests.before(SimulationSet));
+    }
+}
@@ -0,0 +1,263 @@
+//! Star lifecycle: slow resource regeneration and rare stellar evolution
+//!
+//! Drained stars slowly regrow their resources toward REGENERATION_LIMIT of
+//! their maximum, life resources fastest and energy crystals slowest. Once a
+//! depleted star has regrown REVIVAL_SHARE of its maximum, the connections
+//! collecting from it start again. Every EVOLUTION_INTERVAL seconds each star
+//! other than a home star has EVOLUTION_CHANCE to evolve: a red giant swells
+//! with a fresh and richer mix of resources, so its connections collect again
+//! at once, while a nova forges rare energy resources and its blast severs every
+//! connection touching it. The rolls come from the galaxy seed and the age of
+//! the stars, both kept in saves, so the same game always evolves the same way.
+
+use crate::connection::{sever_connections, Connection};
+use crate::events::{ConnectionRemoved, StarEvolved};
+use crate::galaxy::{generate_star_resources, GalaxySeed};
+use crate::resources::ResourceType;
+use crate::star::Star;
+use bevy::prelude::*;
+use rand::{prelude::*, rngs::StdRng};
+
+// Share of its maximum a star regrows on its own
+pub const REGENERATION_LIMIT: f32 = 0.5;
+
+// Share of its maximum a depleted star must regrow before it is collected again
+pub const REVIVAL_SHARE: f32 = 0.25;
+
+// Seconds between two evolution rolls
+pub const EVOLUTION_INTERVAL: f32 = 60.0;
+
+// Chance of every star to evolve at each roll
+pub const EVOLUTION_CHANCE: f32 = 0.01;
+
+// Share of the evolutions that are novas, the others are red giants
+pub const NOVA_SHARE: f32 = 0.2;
+
+// How much richer a red giant is than a freshly generated star
+pub const RED_GIANT_RICHNESS: f32 = 1.5;
+
+pub const RED_GIANT_COLOR: Color = Color::srgba(4.0, 0.8, 0.3, 1.0);
+pub const NOVA_COLOR: Color = Color::srgba(4.0, 4.0, 4.5, 1.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evolution {
+    RedGiant, // New and richer resources, its connections collect again
+    Nova,     // Rare energy resources, its connections are destroyed
+}
+
+impl Evolution {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Evolution::RedGiant => "red giant",
+            Evolution::Nova => "nova",
+        }
+    }
+}
+
+// Simulated seconds the stars have aged, evolution is rolled each time it
+// crosses a multiple of EVOLUTION_INTERVAL
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct StellarAge(pub f32);
+
+// Share of its maximum a resource regrows per second
+pub fn regeneration_rate(resource_type: ResourceType) -> f32 {
+    match resource_type {
+        ResourceType::Water | ResourceType::Oxygen | ResourceType::Food => 0.002,
+        ResourceType::Iron | ResourceType::Copper | ResourceType::Silicon => 0.001,
+        ResourceType::Uranium | ResourceType::Helium3 => 0.0005,
+        ResourceType::EnergyCrystal => 0.00025,
+        // Goods are only made by industry
+        ResourceType::Alloy | ResourceType::Circuits | ResourceType::FuelRods => 0.0,
+    }
+}
+
+// Regrow a star's resources over `dt` seconds, never past REGENERATION_LIMIT
+// of their maximum
+pub fn regenerate(star: &mut Star, dt: f32) {
+    for (resource_type, max) in &star.max_resources {
+        let limit = max * REGENERATION_LIMIT;
+        let amount = star.resources.entry(*resource_type).or_insert(0.0);
+        if *amount < limit {
+            *amount = (*amount + max * regeneration_rate(*resource_type) * dt).min(limit);
+        }
+    }
+}
+
+// Whether a depleted star has regrown enough to be collected again
+pub fn has_revived(star: &Star) -> bool {
+    let total: f32 = star.resources.values().sum();
+    let max: f32 = star.max_resources.values().sum();
+    max > 0.0 && total >= max * REVIVAL_SHARE
+}
+
+// Random rolls of one star at one evolution roll
+pub fn evolution_rng(seed: u64, roll: u64, star_id: usize) -> StdRng {
+    let mixed = seed
+        .wrapping_add(roll.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .wrapping_add(star_id as u64);
+    StdRng::seed_from_u64(mixed)
+}
+
+pub fn roll_evolution(rng: &mut impl Rng) -> Option<Evolution> {
+    if rng.gen::<f32>() >= EVOLUTION_CHANCE {
+        return None;
+    }
+    if rng.gen::<f32>() < NOVA_SHARE {
+        Some(Evolution::Nova)
+    } else {
+        Some(Evolution::RedGiant)
+    }
+}
+
+// Replace a star's resources and color with the ones of its new stage
+pub fn evolve(star: &mut Star, evolution: Evolution, rng: &mut impl Rng) {
+    let max = match evolution {
+        Evolution::RedGiant => {
+            star.base_color = RED_GIANT_COLOR;
+            let (_, max) = generate_star_resources(rng, false);
+            max.into_iter()
+                .map(|(resource_type, amount)| (resource_type, amount * RED_GIANT_RICHNESS))
+                .collect()
+        }
+        Evolution::Nova => {
+            star.base_color = NOVA_COLOR;
+            [
+                ResourceType::Uranium,
+                ResourceType::Helium3,
+                ResourceType::EnergyCrystal,
+            ]
+            .into_iter()
+            .map(|resource_type| (resource_type, rng.gen_range(20.0..60.0)))
+            .collect()
+        }
+    };
+    star.resources.clone_from(&max);
+    star.max_resources = max;
+}
+
+// Regrow every star, restart the connections of revived stars and roll for
+// stellar evolution
+pub fn run_stellar_lifecycle(
+    time: Res<Time>,
+    mut commands: Commands,
+    seed: Option<Res<GalaxySeed>>,
+    mut age: ResMut<StellarAge>,
+    mut star_query: Query<(Entity, &mut Star)>,
+    mut connection_query: Query<(Entity, &mut Connection)>,
+    mut evolved_events: EventWriter<StarEvolved>,
+    mut removed_events: EventWriter<ConnectionRemoved>,
+) {
+    let dt = time.delta_seconds();
+    for (_, mut star) in &mut star_query {
+        regenerate(&mut star, dt);
+    }
+
+    let previous_roll = (age.0 / EVOLUTION_INTERVAL) as u64;
+    age.0 += dt;
+    let roll = (age.0 / EVOLUTION_INTERVAL) as u64;
+    if roll > previous_roll {
+        let seed = seed.map_or(0, |seed| seed.0);
+        let mut novas = Vec::new();
+        for (entity, mut star) in &mut star_query {
+            if star.is_home_star {
+                continue;
+            }
+            let mut rng = evolution_rng(seed, roll, star.id);
+            let Some(evolution) = roll_evolution(&mut rng) else {
+                continue;
+            };
+            info!("{} became a {}", star.name, evolution.name());
+            evolve(&mut star, evolution, &mut rng);
+            if evolution == Evolution::Nova {
+                novas.push(entity);
+            }
+            evolved_events.send(StarEvolved {
+                star: entity,
+                evolution,
+            });
+        }
+
+        for nova in novas {
+            sever_connections(
+                nova,
+                connection_query.iter(),
+                &mut star_query,
+                &mut commands,
+                &mut removed_events,
+            );
+        }
+    }
+
+    for (_, mut connection) in &mut connection_query {
+        if connection.is_collecting {
+            continue;
+        }
+        if let Ok((_, star)) = star_query.get(connection.to) {
+            if has_revived(star) {
+                connection.is_collecting = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::GalaxyConfig;
+
+    fn drained_star() -> Star {
+        let (_, mut star) =
+            crate::galaxy::generate_galaxy(GalaxySeed(3), &GalaxyConfig::default()).swap_remove(1);
+        star.max_resources = [
+            (ResourceType::Water, 100.0),
+            (ResourceType::EnergyCrystal, 100.0),
+        ]
+        .into_iter()
+        .collect();
+        star.resources.clear();
+        star
+    }
+
+    #[test]
+    fn life_resources_regrow_faster_up_to_the_limit() {
+        let mut star = drained_star();
+        regenerate(&mut star, 10.0);
+        assert!((star.resources[&ResourceType::Water] - 2.0).abs() < 1e-4);
+        assert!((star.resources[&ResourceType::EnergyCrystal] - 0.25).abs() < 1e-4);
+        assert!(!has_revived(&star));
+
+        regenerate(&mut star, 1000.0);
+        assert_eq!(star.resources[&ResourceType::Water], 50.0);
+        assert!(has_revived(&star));
+        regenerate(&mut star, 1000.0);
+        assert_eq!(star.resources[&ResourceType::Water], 50.0);
+    }
+
+    #[test]
+    fn evolution_is_rare_and_follows_the_seed() {
+        let rolls: Vec<Option<Evolution>> = (0..2000)
+            .map(|star| roll_evolution(&mut evolution_rng(42, 1, star)))
+            .collect();
+        let evolved = rolls.iter().flatten().count();
+        assert!((5..=50).contains(&evolved), "{} evolved", evolved);
+
+        let again: Vec<Option<Evolution>> = (0..2000)
+            .map(|star| roll_evolution(&mut evolution_rng(42, 1, star)))
+            .collect();
+        assert_eq!(rolls, again);
+    }
+
+    #[test]
+    fn nova_forges_rare_resources() {
+        let mut star = drained_star();
+        evolve(&mut star, Evolution::Nova, &mut evolution_rng(0, 1, 0));
+        assert_eq!(star.base_color, NOVA_COLOR);
+        assert_eq!(star.resources, star.max_resources);
+        assert!(star.resources.contains_key(&ResourceType::EnergyCrystal));
+        assert!(!star.resources.contains_key(&ResourceType::Water));
+
+        evolve(&mut star, Evolution::RedGiant, &mut evolution_rng(0, 2, 0));
+        assert_eq!(star.base_color, RED_GIANT_COLOR);
+        assert!(has_revived(&star));
+    }
+}
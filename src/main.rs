@@ -1,383 +1,40 @@
 //! Space colonization game with resource management
 
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
 use bevy::{
     core_pipeline::{bloom::*, tonemapping::Tonemapping},
     ecs::system::ParamSet,
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
     prelude::*,
     render::mesh::Indices,
     sprite::MaterialMesh2dBundle,
     window::PrimaryWindow,
 };
-use rand::prelude::*;
-use std::collections::HashMap;
-
-// Specialization types for stars
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Specialization {
-    None,        // Default - collects resources
-    Storage,     // Storage hub - increased resource capacity
-    Military,    // Produces warships
-    Mining,      // Produces mining ships (increases resource collection)
-    Agriculture, // Produces food and biological resources
-    Research,    // Produces scientists and technology
-    Medical,     // Produces medical units and health resources
-    Industrial,  // Produces builders and construction units
-}
-
-impl Specialization {
-    fn name(&self) -> &'static str {
-        match self {
-            Specialization::None => "Resource Extraction",
-            Specialization::Storage => "Storage Hub",
-            Specialization::Military => "Military Base",
-            Specialization::Mining => "Mining Station",
-            Specialization::Agriculture => "Agricultural Colony",
-            Specialization::Research => "Research Center",
-            Specialization::Medical => "Medical Facility",
-            Specialization::Industrial => "Industrial Complex",
-        }
-    }
-
-    fn build_time(&self) -> f32 {
-        match self {
-            Specialization::None => 5.0,         // 5 seconds to start extraction
-            Specialization::Storage => 10.0,     // 10 seconds
-            Specialization::Military => 20.0,    // 20 seconds
-            Specialization::Mining => 15.0,      // 15 seconds
-            Specialization::Agriculture => 12.0, // 12 seconds
-            Specialization::Research => 25.0,    // 25 seconds
-            Specialization::Medical => 15.0,     // 15 seconds
-            Specialization::Industrial => 18.0,  // 18 seconds
-        }
-    }
-
-    fn upgrade_time(&self, level: u8) -> f32 {
-        let base_time = self.build_time();
-        base_time * (level as f32 * 1.5) // Higher levels take longer
-    }
-
-    fn icon(&self) -> &'static str {
-        match self {
-            Specialization::None => "⛏️",
-            Specialization::Storage => "📦",
-            Specialization::Military => "🚀",
-            Specialization::Mining => "⚒️",
-            Specialization::Agriculture => "🌾",
-            Specialization::Research => "🔬",
-            Specialization::Medical => "🏥",
-            Specialization::Industrial => "🏭",
-        }
-    }
-
-    fn production_cost(&self, level: u8) -> Vec<(ResourceType, f32)> {
-        let multiplier = 1.0 / (1.0 + (level - 1) as f32 * 0.2); // Higher levels are more efficient
-        match self {
-            Specialization::None => vec![],
-            Specialization::Storage => vec![
-                (ResourceType::Iron, 10.0 * multiplier),
-                (ResourceType::Silicon, 5.0 * multiplier),
-            ],
-            Specialization::Military => vec![
-                (ResourceType::Iron, 20.0 * multiplier),
-                (ResourceType::Uranium, 10.0 * multiplier),
-                (ResourceType::Silicon, 15.0 * multiplier),
-            ],
-            Specialization::Mining => vec![
-                (ResourceType::Iron, 15.0 * multiplier),
-                (ResourceType::Copper, 10.0 * multiplier),
-            ],
-            Specialization::Agriculture => vec![
-                (ResourceType::Water, 20.0 * multiplier),
-                (ResourceType::Food, 10.0 * multiplier),
-            ],
-            Specialization::Research => vec![
-                (ResourceType::Silicon, 20.0 * multiplier),
-                (ResourceType::EnergyCrystal, 2.0 * multiplier),
-            ],
-            Specialization::Medical => vec![
-                (ResourceType::Oxygen, 15.0 * multiplier),
-                (ResourceType::Water, 10.0 * multiplier),
-            ],
-            Specialization::Industrial => vec![
-                (ResourceType::Iron, 25.0 * multiplier),
-                (ResourceType::Copper, 15.0 * multiplier),
-                (ResourceType::Silicon, 10.0 * multiplier),
-            ],
-        }
-    }
-}
-
-// Unit types produced by specialized stars
-#[derive(Debug, Clone)]
-struct Unit {
-    unit_type: UnitType,
-    count: u32,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum UnitType {
-    Warship,
-    MiningShip,
-    Farmer,
-    Scientist,
-    Doctor,
-    Builder,
-    StorageModule,
-}
-
-// Resource types in the game
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum ResourceType {
-    // Basic life resources
-    Water,  // 💧
-    Oxygen, // 🌬️
-    Food,   // 🌱
-
-    // Construction minerals
-    Iron,    // 🪨
-    Copper,  // ⚡
-    Silicon, // 💻
-
-    // Energy resources
-    Uranium,       // ☢️
-    Helium3,       // 🔋
-    EnergyCrystal, // ✨
-}
-
-impl ResourceType {
-    fn color(&self) -> Color {
-        match self {
-            ResourceType::Water => Color::srgb(0.0, 1.0, 1.0),
-            ResourceType::Oxygen => Color::srgb(0.7, 0.9, 1.0),
-            ResourceType::Food => Color::srgb(0.0, 1.0, 0.0),
-            ResourceType::Iron => Color::srgb(0.5, 0.5, 0.6),
-            ResourceType::Copper => Color::srgb(0.72, 0.45, 0.20),
-            ResourceType::Silicon => Color::srgb(0.8, 0.8, 0.9),
-            ResourceType::Uranium => Color::srgb(0.0, 1.0, 0.0),
-            ResourceType::Helium3 => Color::srgb(1.0, 0.8, 0.0),
-            ResourceType::EnergyCrystal => Color::srgb(1.0, 0.0, 1.0),
-        }
-    }
-
-    fn icon(&self) -> &'static str {
-        match self {
-            ResourceType::Water => "💧",
-            ResourceType::Oxygen => "🌬️",
-            ResourceType::Food => "🌱",
-            ResourceType::Iron => "🪨",
-            ResourceType::Copper => "⚡",
-            ResourceType::Silicon => "💻",
-            ResourceType::Uranium => "☢️",
-            ResourceType::Helium3 => "🔋",
-            ResourceType::EnergyCrystal => "✨",
-        }
-    }
-
-    fn name(&self) -> &'static str {
-        match self {
-            ResourceType::Water => "Water",
-            ResourceType::Oxygen => "Oxygen",
-            ResourceType::Food => "Food",
-            ResourceType::Iron => "Iron",
-            ResourceType::Copper => "Copper",
-            ResourceType::Silicon => "Silicon",
-            ResourceType::Uranium => "Uranium",
-            ResourceType::Helium3 => "Helium-3",
-            ResourceType::EnergyCrystal => "Energy Crystal",
-        }
-    }
-}
-
-// Building state for stars
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum BuildingState {
-    Ready,
-    Building { timer: f32, total_time: f32 },
-    Upgrading { timer: f32, total_time: f32 },
-}
-
-// Calculate Fibonacci number for connection limit
-fn fibonacci(n: u8) -> u32 {
-    if n <= 1 {
-        return 1;
-    }
-
-    let mut a = 1u32;
-    let mut b = 2u32;
-
-    for _ in 2..n {
-        let temp = a + b;
-        a = b;
-        b = temp;
-    }
-
-    b
-}
-
-// Get maximum connections based on star level
-fn max_connections_for_level(level: u8) -> u32 {
-    fibonacci(level)
-}
-
-// Calculate route distance to nearest storage hub through connection paths
-// This measures the number of connection hops, not physical distance
-// A star can be physically close but have a long route distance if not directly connected
-fn calculate_distance_to_nearest_storage(
-    star_entity: Entity,
-    star_query: &Query<&Star>,
-    visited: &mut Vec<Entity>,
-) -> Option<u32> {
-    // Check if already visited to avoid cycles
-    if visited.contains(&star_entity) {
-        return None;
-    }
-    visited.push(star_entity);
-    
-    if let Ok(star) = star_query.get(star_entity) {
-        // If this star is a storage hub, route distance is 0
-        if star.is_storage_hub {
-            return Some(0);
-        }
-        
-        // Check all connection routes to find shortest path
-        let mut min_route_distance = None;
-        
-        // Check routes through incoming connections
-        for &connected_entity in &star.connections_from {
-            if let Some(dist) = calculate_distance_to_nearest_storage(connected_entity, star_query, visited) {
-                let route_dist = dist + 1; // Add 1 hop for this connection
-                min_route_distance = Some(min_route_distance.map_or(route_dist, |d: u32| d.min(route_dist)));
-            }
-        }
-        
-        // Check routes through outgoing connections
-        for &connected_entity in &star.connections_to {
-            if let Some(dist) = calculate_distance_to_nearest_storage(connected_entity, star_query, visited) {
-                let route_dist = dist + 1; // Add 1 hop for this connection
-                min_route_distance = Some(min_route_distance.map_or(route_dist, |d: u32| d.min(route_dist)));
-            }
-        }
-        
-        min_route_distance
-    } else {
-        None
-    }
-}
-
-// Find all cycles of 3 or more stars in the connection graph
-fn find_cycles_in_graph(
-    stars: &Query<(Entity, &Star)>,
-    min_cycle_size: usize,
-) -> Vec<Vec<Entity>> {
-    let mut cycles = Vec::new();
-    let mut visited = Vec::new();
-    
-    for (entity, _star) in stars.iter() {
-        if !visited.contains(&entity) {
-            let mut path = Vec::new();
-            find_cycles_dfs(entity, entity, &mut path, &mut visited, &mut cycles, stars, min_cycle_size, None);
-        }
-    }
-    
-    // Remove duplicate cycles (same nodes in different order)
-    let mut unique_cycles = Vec::new();
-    for cycle in cycles {
-        let mut sorted_cycle = cycle.clone();
-        sorted_cycle.sort_by_key(|e| e.index());
-        if !unique_cycles.iter().any(|existing: &Vec<Entity>| {
-            let mut sorted_existing = existing.clone();
-            sorted_existing.sort_by_key(|e| e.index());
-            sorted_existing == sorted_cycle
-        }) {
-            unique_cycles.push(cycle);
-        }
-    }
-    
-    unique_cycles
-}
-
-fn find_cycles_dfs(
-    current: Entity,
-    start: Entity,
-    path: &mut Vec<Entity>,
-    visited: &mut Vec<Entity>,
-    cycles: &mut Vec<Vec<Entity>>,
-    stars: &Query<(Entity, &Star)>,
-    min_size: usize,
-    parent: Option<Entity>,
-) {
-    path.push(current);
-    visited.push(current);
-    
-    if let Ok((_entity, star)) = stars.get(current) {
-        // Check all connected stars
-        let mut connected: Vec<Entity> = star.connections_to.clone();
-        connected.extend(star.connections_from.clone());
-        
-        for &next in &connected {
-            // Skip parent to avoid immediate backtracking
-            if Some(next) == parent {
-                continue;
-            }
-            
-            // If we found the start and path is long enough, we have a cycle
-            if next == start && path.len() >= min_size {
-                cycles.push(path.clone());
-            } 
-            // Continue DFS if not visited in current path
-            else if !path.contains(&next) {
-                find_cycles_dfs(next, start, path, visited, cycles, stars, min_size, Some(current));
-            }
-        }
-    }
-    
-    path.pop();
-}
-
-// Calculate production efficiency based on route distance to storage hub
-// Stars need supply routes to maintain efficiency - the longer the route, the less efficient
-fn production_rate_modifier_from_distance(route_distance: Option<u32>) -> f32 {
-    match route_distance {
-        None => 0.1,    // No route to storage hub: 10% production (isolated)
-        Some(0) => 1.0, // Is a storage hub: 100% production
-        Some(1) => 0.9, // 1 connection hop: 90% production
-        Some(2) => 0.75, // 2 connection hops: 75% production
-        Some(3) => 0.6,  // 3 connection hops: 60% production
-        Some(4) => 0.45, // 4 connection hops: 45% production
-        Some(5) => 0.35, // 5 connection hops: 35% production
-        Some(d) => (0.3 / (d as f32 - 4.0)).max(0.1), // Longer routes: diminishing returns, min 10%
-    }
-}
-
-// Components
-#[derive(Component)]
-struct Star {
-    id: usize,
-    name: String,
-    resources: HashMap<ResourceType, f32>,
-    max_resources: HashMap<ResourceType, f32>,
-    production_rate: f32,  // Resources per second
-    is_colonized: bool,
-    is_home_star: bool,
-    specialization: Specialization, // None = extraction; other = specialization (stops extraction)
-    specialization_level: u8,      // Level (no limit, follows Fibonacci for connections)
-    units: Vec<Unit>,               // Units produced if specialized
-    building_state: BuildingState,  // Current construction/upgrade state
-    connections_from: Vec<Entity>,  // List of stars connected TO this star
-    connections_to: Vec<Entity>,    // List of stars this star connects TO
-    base_color: Color,              // Base color based on resources
-    storage_capacity: HashMap<ResourceType, f32>, // Storage capacity if it's a storage hub
-    is_storage_hub: bool,          // Whether this star is a storage hub
-}
-
-#[derive(Component)]
-struct Connection {
-    from: Entity,
-    to: Entity,
-    collection_timer: Timer,
-    is_collecting: bool,
-    creation_time: f32, // Time in seconds since creation
-}
+use std::collections::HashSet;
+use std::f32::consts::FRAC_PI_2;
+use std::path::Path;
+use zodiakos::{
+    cargo::cargo_in_transit,
+    clock::STEP_SECONDS,
+    combat::{connection_between, launch_fleet, FleetError},
+    constellation::{
+        check_constellation_bonuses, Constellation, ConstellationBonus, ConstellationDissolved,
+    },
+    construction, empire,
+    events::{
+        BattleFought, ColonyAbandoned, ConnectionCreated, ConnectionRefusal, ConnectionRefused,
+        ConnectionRemoved, ConstellationFormed, GoodsRefined, StarCaptured, StarColonized,
+        StarDepleted, StarEvolved, UnitsProduced, UpgradeCompleted,
+    },
+    galaxy::generate_galaxy,
+    goals::{GameEnded, ZodiacVictoryBlocked},
+    options::{LaunchOptions, CONFIG_FILE},
+    prelude::*,
+    refining::RECIPES,
+    save::{GameLoaded, LoadRequest, SaveRequest, SAVE_SLOTS},
+    zodiac::{ZodiacCompleted, ZodiacPerks},
+};
 
 #[derive(Component)]
 struct ConnectionLine;
@@ -401,19 +58,9 @@ struct ResourcePanel;
 #[derive(Component)]
 struct SelectedStar;
 
-#[derive(Component)]
-struct StarSprite;
-
 #[derive(Component)]
 struct StarBorder;
 
-// Constellation data structure
-struct Constellation {
-    id: u32,
-    stars: Vec<Entity>,
-    color: Color,
-}
-
 #[derive(Component)]
 struct ConstellationMarker {
     id: u32,
@@ -442,165 +89,194 @@ struct DragState {
     is_dragging: bool,
     start_star: Option<Entity>,
     current_line: Option<Entity>,
+    refused_origin: Option<Entity>, // Uncolonized star a drag was started from
 }
 
 #[derive(Resource)]
-struct StarMaterials {
-    normal: Handle<ColorMaterial>,
-    hovered: Handle<ColorMaterial>,
-    selected: Handle<ColorMaterial>,
-    colonized: Handle<ColorMaterial>,
-    home: Handle<ColorMaterial>,
+struct GameState {
+    selected_star: Option<Entity>,
 }
 
-#[derive(Resource)]
-struct PlayerResources {
-    resources: HashMap<ResourceType, f32>,
+// Specializations in the order of the Digit1-8 keys
+const SPECIALIZATION_KEYS: [Specialization; 8] = [
+    Specialization::None,
+    Specialization::Storage,
+    Specialization::Military,
+    Specialization::Mining,
+    Specialization::Agriculture,
+    Specialization::Research,
+    Specialization::Medical,
+    Specialization::Industrial,
+];
+
+// Message categories, each can be filtered out of the message log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum MessageCategory {
+    Network,       // Colonization and connections
+    Construction,  // Specializations and upgrades
+    Constellation, // Constellations and zodiac signs
+    Economy,       // Depletion, stellar evolution, refining and unit production
+    Research,
+    Combat, // Fleets, battles and captured stars
+    System, // Saving and loading
 }
 
-impl Default for PlayerResources {
-    fn default() -> Self {
-        let mut resources = HashMap::new();
-        // Start with a small amount of each resource
-        resources.insert(ResourceType::Water, 50.0);
-        resources.insert(ResourceType::Oxygen, 30.0);
-        resources.insert(ResourceType::Food, 40.0);
-        resources.insert(ResourceType::Iron, 20.0);
-        resources.insert(ResourceType::Copper, 15.0);
-        resources.insert(ResourceType::Silicon, 10.0);
-        resources.insert(ResourceType::Uranium, 5.0);
-        resources.insert(ResourceType::Helium3, 2.0);
-        resources.insert(ResourceType::EnergyCrystal, 1.0);
-        Self { resources }
+impl MessageCategory {
+    const ALL: [MessageCategory; 7] = [
+        MessageCategory::Network,
+        MessageCategory::Construction,
+        MessageCategory::Constellation,
+        MessageCategory::Economy,
+        MessageCategory::Research,
+        MessageCategory::Combat,
+        MessageCategory::System,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            MessageCategory::Network => "Network",
+            MessageCategory::Construction => "Construction",
+            MessageCategory::Constellation => "Constellation",
+            MessageCategory::Economy => "Economy",
+            MessageCategory::Research => "Research",
+            MessageCategory::Combat => "Combat",
+            MessageCategory::System => "System",
+        }
     }
-}
 
-#[derive(Resource)]
-struct GameState {
-    selected_star: Option<Entity>,
+    fn color(&self) -> Color {
+        match self {
+            MessageCategory::Network => Color::srgb(0.5, 1.0, 0.5),
+            MessageCategory::Construction => Color::srgb(1.0, 0.85, 0.3),
+            MessageCategory::Constellation => Color::srgb(1.0, 0.6, 1.0),
+            MessageCategory::Economy => Color::srgb(0.6, 0.8, 1.0),
+            MessageCategory::Research => Color::srgb(0.7, 0.9, 1.0),
+            MessageCategory::Combat => Color::srgb(1.0, 0.45, 0.4),
+            MessageCategory::System => Color::srgb(0.8, 0.8, 0.8),
+        }
+    }
 }
 
-#[derive(Resource, Default)]
-struct ConstellationTracker {
-    next_id: u32,
-    constellations: Vec<Constellation>,
+struct LogEntry {
+    time: f32, // Game time in seconds
+    category: MessageCategory,
+    text: String,
 }
 
-// Star name generator
-fn generate_star_name(rng: &mut ThreadRng) -> String {
-    let prefixes = [
-        "Alpha", "Beta", "Gamma", "Delta", "Epsilon", "Zeta", "Eta", "Theta", "Iota", "Kappa",
-    ];
-    let suffixes = [
-        "Centauri",
-        "Orionis",
-        "Draconis",
-        "Pegasi",
-        "Andromedae",
-        "Leonis",
-        "Aquarii",
-        "Scorpii",
-        "Tauri",
-        "Geminorum",
-    ];
-    let prefix = prefixes[rng.gen_range(0..prefixes.len())];
-    let suffix = suffixes[rng.gen_range(0..suffixes.len())];
-    format!("{} {}", prefix, suffix)
+// Messages for the player: recent ones pop up as toasts, all of them are kept
+// in the message log
+#[derive(Resource, Default)]
+struct Notifications {
+    now: f32, // Game time stamped on new messages
+    log: Vec<LogEntry>,
+    toasts: Vec<(usize, f32)>,        // Log index and remaining seconds
+    hidden: HashSet<MessageCategory>, // Categories filtered out of the log
+    scroll: usize,                    // Entries scrolled back from the newest
 }
 
-// Get star color based on dominant resource type
-fn get_star_color_from_resources(resources: &HashMap<ResourceType, f32>) -> Color {
-    // Find the dominant resource type
-    let mut dominant_resource = None;
-    let mut max_amount = 0.0;
-
-    for (resource_type, amount) in resources {
-        if *amount > max_amount {
-            max_amount = *amount;
-            dominant_resource = Some(*resource_type);
+impl Notifications {
+    const TOAST_DURATION: f32 = 4.0;
+    const MAX_TOASTS: usize = 4;
+    const MAX_LOG: usize = 200;
+    const LOG_LINES: usize = 12;
+
+    // Log a message and show it as a toast
+    fn push(&mut self, category: MessageCategory, text: String) {
+        self.log(category, text);
+        self.toasts.push((self.log.len() - 1, Self::TOAST_DURATION));
+        if self.toasts.len() > Self::MAX_TOASTS {
+            self.toasts.remove(0);
         }
     }
 
-    // Return color based on dominant resource with HDR values for bloom
-    match dominant_resource {
-        Some(ResourceType::Water) => Color::srgba(0.3, 0.6, 4.0, 1.0), // Deep blue
-        Some(ResourceType::Oxygen) => Color::srgba(0.7, 3.0, 4.0, 1.0), // Cyan
-        Some(ResourceType::Food) => Color::srgba(0.3, 4.0, 0.3, 1.0),  // Green
-        Some(ResourceType::Iron) => Color::srgba(2.5, 2.5, 3.0, 1.0),  // Silver-gray
-        Some(ResourceType::Copper) => Color::srgba(4.0, 2.0, 0.8, 1.0), // Orange-copper
-        Some(ResourceType::Silicon) => Color::srgba(3.0, 3.0, 4.0, 1.0), // Light blue-white
-        Some(ResourceType::Uranium) => Color::srgba(0.5, 4.0, 0.5, 1.0), // Radioactive green
-        Some(ResourceType::Helium3) => Color::srgba(4.0, 3.0, 0.0, 1.0), // Yellow-gold
-        Some(ResourceType::EnergyCrystal) => Color::srgba(4.0, 0.5, 4.0, 1.0), // Purple
-        None => Color::srgba(3.0, 3.0, 3.0, 1.0),                      // Default white
+    // Log a message without a toast, for frequent events
+    fn log(&mut self, category: MessageCategory, text: String) {
+        if self.log.len() == Self::MAX_LOG {
+            self.log.remove(0);
+            self.toasts.retain_mut(|(index, _)| {
+                let kept = *index > 0;
+                *index = index.saturating_sub(1);
+                kept
+            });
+        }
+        self.log.push(LogEntry {
+            time: self.now,
+            category,
+            text,
+        });
     }
 }
 
-// Generate random resources for a star
-fn generate_star_resources(
-    rng: &mut ThreadRng,
-    is_home: bool,
-) -> (HashMap<ResourceType, f32>, HashMap<ResourceType, f32>) {
-    let mut resources = HashMap::new();
-    let mut max_resources = HashMap::new();
-
-    if is_home {
-        // Home star has balanced resources
-        for resource in [
-            ResourceType::Water,
-            ResourceType::Oxygen,
-            ResourceType::Food,
-            ResourceType::Iron,
-            ResourceType::Copper,
-            ResourceType::Silicon,
-        ] {
-            let amount = rng.gen_range(100.0..200.0);
-            resources.insert(resource, amount);
-            max_resources.insert(resource, amount);
-        }
-    } else {
-        // Other stars have random resources (1-3 types)
-        let num_resources = rng.gen_range(1..=3);
-        let all_resources = [
-            ResourceType::Water,
-            ResourceType::Oxygen,
-            ResourceType::Food,
-            ResourceType::Iron,
-            ResourceType::Copper,
-            ResourceType::Silicon,
-            ResourceType::Uranium,
-            ResourceType::Helium3,
-            ResourceType::EnergyCrystal,
-        ];
-
-        let mut selected_resources = all_resources.to_vec();
-        selected_resources.shuffle(rng);
-
-        for i in 0..num_resources {
-            let resource = selected_resources[i];
-            let amount = match resource {
-                ResourceType::EnergyCrystal | ResourceType::Helium3 => rng.gen_range(5.0..30.0),
-                ResourceType::Uranium => rng.gen_range(10.0..50.0),
-                _ => rng.gen_range(50.0..150.0),
-            };
-            resources.insert(resource, amount);
-            max_resources.insert(resource, amount);
+#[derive(Component)]
+struct ToastArea;
+
+#[derive(Component)]
+struct MessageLogPanel;
+
+// Panel that takes the digit keys while open
+#[derive(Resource, Default, PartialEq, Eq)]
+enum OpenPanel {
+    #[default]
+    None,
+    Research,
+    MessageLog,
+}
+
+#[derive(Component)]
+struct ResearchPanel;
+
+// Tuning for camera panning and zooming
+#[derive(Resource)]
+struct CameraSettings {
+    pan_speed: f32,   // Screen pixels per second for keyboard and edge panning
+    edge_margin: f32, // Distance from the window border that triggers edge scrolling
+    zoom_step: f32,   // Fraction of the current zoom applied per wheel notch
+    min_zoom: f32,
+    max_zoom: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            pan_speed: 600.0,
+            edge_margin: 12.0,
+            zoom_step: 0.1,
+            min_zoom: 0.25,
+            max_zoom: 12.0,
         }
     }
-
-    (resources, max_resources)
 }
 
+// Slot used by quicksave and quickload
+#[derive(Resource)]
+struct SaveSlot(u8);
+
+#[derive(Component)]
+struct SaveSlotText;
+
+#[derive(Component)]
+struct SimulationSpeedText;
+
+#[derive(Component)]
+struct GameSummaryPanel;
+
 fn main() {
+    let options = LaunchOptions::load(std::env::args().skip(1), Path::new(CONFIG_FILE));
+
     App::new()
-        .add_plugins(DefaultPlugins)
+        .add_plugins((DefaultPlugins, SimulationPlugin))
+        .insert_resource(GalaxySeed::from_options(&options))
+        .insert_resource(GalaxyConfig::from_options(&options))
+        .insert_resource(GameGoals::from_options(&options))
         .init_resource::<DragState>()
-        .init_resource::<PlayerResources>()
-        .init_resource::<ConstellationTracker>()
         .insert_resource(GameState {
             selected_star: None,
         })
         .insert_resource(ConfigMenuState { visible: false })
+        .insert_resource(SaveSlot(1))
+        .init_resource::<CameraSettings>()
+        .init_resource::<Notifications>()
+        .init_resource::<OpenPanel>()
         .add_systems(Startup, setup)
         .add_systems(
             Update,
@@ -611,14 +287,48 @@ fn main() {
                 connection_selection_system,
                 update_dragging_line,
                 update_connections,
-                collect_resources_system,
-                detect_and_create_constellations,
+                attach_cargo_visuals.after(SimulationSet),
+                update_cargo_dots.after(attach_cargo_visuals),
+                remove_dissolved_constellations.after(SimulationSet),
+                announce_zodiac_signs.after(SimulationSet),
+                spawn_constellation_visuals.after(SimulationSet),
                 update_star_borders,
                 toggle_config_menu,
                 update_bloom_settings,
                 update_ui,
+                research_panel_system.before(update_ui),
+                message_log_system.before(update_ui),
+                log_game_events.after(SimulationSet).before(update_toasts),
+                update_toasts.after(update_ui),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                fleet_orders_input.before(SimulationSet),
+                attach_fleet_visuals.after(SimulationSet),
+                update_fleet_markers.after(attach_fleet_visuals),
+                recolor_connections.after(SimulationSet),
             ),
         )
+        .add_systems(
+            Update,
+            (camera_zoom_system, camera_pan_system, camera_focus_system)
+                .chain()
+                .before(star_hover_system),
+        )
+        .add_systems(
+            Update,
+            (
+                save_load_input,
+                simulation_speed_input,
+                game_summary_system.after(SimulationSet),
+                rebuild_after_load,
+                attach_star_visuals,
+                attach_connection_visuals,
+            )
+                .chain(),
+        )
         .run();
 }
 
@@ -626,6 +336,8 @@ fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    galaxy_seed: Res<GalaxySeed>,
+    galaxy_config: Res<GalaxyConfig>,
 ) {
     // Camera with HDR and Bloom
     commands.spawn((
@@ -653,112 +365,35 @@ fn setup(
     // Create star mesh handle
     let star_mesh = meshes.add(Circle::new(25.0));
 
-    // Spawn stars at random positions with minimum distance
-    let mut rng = rand::thread_rng();
-    let mut positions: Vec<Vec2> = Vec::new();
-    let min_distance = 90.0;
-    let max_attempts = 500;
-    let margin = 50.0;
-    let num_stars = 12;
-
-    // Generate home star first (center position)
-    let home_pos = Vec2::new(0.0, 0.0);
-    positions.push(home_pos);
-
-    let (_home_resources, home_max) = generate_star_resources(&mut rng, true);
-    
-    // Calculate storage capacity (10% of max capacity for each resource)
-    let mut storage_capacity = HashMap::new();
-    let mut storage_resources = HashMap::new();
-    for (resource_type, max_value) in &home_max {
-        let capacity = max_value * 10.0; // Storage hub has 10x the capacity
-        storage_capacity.insert(*resource_type, capacity);
-        // Start with 10% of storage capacity filled
-        storage_resources.insert(*resource_type, capacity * 0.1);
-    }
-    
-    // Home star has a special golden color
-    let home_color = Color::srgba(4.0, 3.5, 0.5, 1.0);
-    let _home_star = commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: star_mesh.clone().into(),
-            material: materials.add(ColorMaterial::from(home_color)),
-            transform: Transform::from_xyz(home_pos.x, home_pos.y, 1.0),
-            ..default()
-        },
-        Star {
-            id: 0,
-            name: "Sol System (Storage Hub)".to_string(),
-            resources: storage_resources, // Use storage resources instead
-            max_resources: home_max.clone(),
-            production_rate: 2.0,
-            is_colonized: true,
-            is_home_star: true,
-            specialization: Specialization::Storage, // Set as Storage hub
-            specialization_level: 1,
-            units: vec![],
-            building_state: BuildingState::Ready,
-            connections_from: vec![],
-            connections_to: vec![],
-            base_color: home_color,
-            storage_capacity,
-            is_storage_hub: true,
-        },
-    )).id();
-
-    // Generate other stars
-    for i in 1..num_stars {
-        let mut position_found = false;
-        let mut attempts = 0;
-        let mut pos = Vec2::ZERO;
-
-        while !position_found && attempts < max_attempts {
-            let x = rng.gen_range((-350.0 + margin)..(350.0 - margin));
-            let y = rng.gen_range((-250.0 + margin)..(250.0 - margin));
-            pos = Vec2::new(x, y);
-
-            position_found = true;
-            for existing_pos in &positions {
-                if pos.distance(*existing_pos) < min_distance {
-                    position_found = false;
-                    break;
-                }
-            }
-
-            attempts += 1;
+    // Spawn the stars generated from the galaxy seed
+    info!(
+        "Generating {} galaxy of {} stars with seed {}",
+        galaxy_config.layout.name(),
+        galaxy_config.star_count,
+        galaxy_seed.0
+    );
+    let mut rivals = 0;
+    for (pos, star) in generate_galaxy(*galaxy_seed, &galaxy_config) {
+        let star_color = star.base_color;
+        let is_colonized = star.is_colonized;
+        if star.is_home_star && !star.empire.is_player() {
+            rivals += 1;
         }
-
-        positions.push(pos);
-
-        let (star_resources, star_max) = generate_star_resources(&mut rng, false);
-        let star_color = get_star_color_from_resources(&star_resources);
-        commands.spawn((
+        let mut entity = commands.spawn((
             MaterialMesh2dBundle {
                 mesh: star_mesh.clone().into(),
                 material: materials.add(ColorMaterial::from(star_color)),
                 transform: Transform::from_xyz(pos.x, pos.y, 1.0),
                 ..default()
             },
-            Star {
-                id: i,
-                name: generate_star_name(&mut rng),
-                resources: star_resources,
-                max_resources: star_max,
-                production_rate: rng.gen_range(0.5..2.5),
-                is_colonized: false,
-                is_home_star: false,
-                specialization: Specialization::None,
-                specialization_level: 1,
-                units: vec![],
-                building_state: BuildingState::Ready,
-                connections_from: vec![],
-                connections_to: vec![],
-                base_color: star_color,
-                storage_capacity: HashMap::new(),
-                is_storage_hub: false,
-            },
+            star,
         ));
+        // Colonized stars are ringed in the color of their empire
+        if is_colonized {
+            entity.insert(StarBorder);
+        }
     }
+    commands.insert_resource(RivalEmpires::new(rivals));
 
     // UI Setup
     // Title
@@ -782,7 +417,7 @@ fn setup(
     // Instructions
     commands.spawn(
         TextBundle::from_section(
-            "Click stars to select | Drag to connect and colonize",
+            "Click stars to select | Drag to connect and colonize | Wheel zoom | Middle-drag/WASD pan | [F] Focus star | [L] Message log | [Space] Pause",
             TextStyle {
                 font_size: 14.0,
                 color: Color::srgb(0.8, 0.8, 0.8),
@@ -797,70 +432,173 @@ fn setup(
         }),
     );
 
-    // Resource panel
+    // Toasts for recent messages
+    commands.spawn((
+        TextBundle::from_sections([]).with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(60.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        ToastArea,
+    ));
+
+    // Message log (toggled with L)
+    commands.spawn((
+        TextBundle::from_sections([]).with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        MessageLogPanel,
+        Visibility::Hidden,
+    ));
+
+    // Research panel (toggled with T)
     commands.spawn((
         TextBundle::from_section(
-            "Resources:",
+            "",
             TextStyle {
-                font_size: 16.0,
-                color: Color::WHITE,
+                font_size: 14.0,
+                color: Color::srgb(0.7, 0.9, 1.0),
                 ..default()
             },
         )
         .with_style(Style {
             position_type: PositionType::Absolute,
-            top: Val::Px(70.0),
-            right: Val::Px(10.0),
+            top: Val::Px(160.0),
+            left: Val::Px(10.0),
             ..default()
         }),
-        ResourcePanel,
+        ResearchPanel,
+        Visibility::Hidden,
     ));
 
-    // Star info panel
+    // Save slot indicator
     commands.spawn((
         TextBundle::from_section(
             "",
             TextStyle {
                 font_size: 14.0,
-                color: Color::WHITE,
+                color: Color::srgb(0.8, 0.8, 0.8),
                 ..default()
             },
         )
         .with_style(Style {
             position_type: PositionType::Absolute,
-            bottom: Val::Px(10.0),
-            left: Val::Px(10.0),
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
             ..default()
         }),
-        StarInfoPanel,
+        SaveSlotText,
     ));
 
-    // Configuration Menu (initially hidden) - Title
-    let menu_title = commands
-        .spawn((
-            TextBundle::from_section(
-                "=== BLOOM CONFIGURATION ===\n[ESC] Toggle Menu | [R] Reset Defaults",
-                TextStyle {
-                    font_size: 18.0,
-                    color: Color::srgb(1.0, 1.0, 0.2),
-                    ..default()
-                },
-            )
-            .with_style(Style {
-                position_type: PositionType::Absolute,
-                top: Val::Px(100.0),
-                left: Val::Percent(50.0),
+    // End-of-game summary, shown when a victory or defeat condition holds
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::WHITE,
                 ..default()
-            }),
-            ConfigMenu,
-        ))
-        .id();
-    commands.entity(menu_title).insert(Visibility::Hidden);
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(20.0),
+            left: Val::Percent(30.0),
+            padding: UiRect::all(Val::Px(20.0)),
+            ..default()
+        })
+        .with_background_color(Color::srgba(0.0, 0.0, 0.05, 0.9)),
+        GameSummaryPanel,
+        Visibility::Hidden,
+    ));
 
-    // Intensity control
-    let intensity_text = commands
-        .spawn((
-            TextBundle::from_section(
+    // Simulation speed indicator
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 14.0,
+                color: Color::srgb(0.8, 0.8, 0.8),
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(40.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        SimulationSpeedText,
+    ));
+
+    // Resource panel
+    commands.spawn((
+        TextBundle::from_section(
+            "Resources:",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(70.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        ResourcePanel,
+    ));
+
+    // Star info panel
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 14.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(10.0),
+            left: Val::Px(10.0),
+            ..default()
+        }),
+        StarInfoPanel,
+    ));
+
+    // Configuration Menu (initially hidden) - Title
+    let menu_title = commands
+        .spawn((
+            TextBundle::from_section(
+                "=== BLOOM CONFIGURATION ===\n[ESC] Toggle Menu | [R] Reset Defaults",
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::srgb(1.0, 1.0, 0.2),
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(100.0),
+                left: Val::Percent(50.0),
+                ..default()
+            }),
+            ConfigMenu,
+        ))
+        .id();
+    commands.entity(menu_title).insert(Visibility::Hidden);
+
+    // Intensity control
+    let intensity_text = commands
+        .spawn((
+            TextBundle::from_section(
                 "Intensity: 1.0 (Q/A to adjust)",
                 TextStyle {
                     font_size: 16.0,
@@ -1043,6 +781,11 @@ fn handle_mouse_input(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     existing_connections: Query<&Connection>,
+    tech_tree: Res<TechTree>,
+    constellation_tracker: Res<ConstellationTracker>,
+    mut colonized_events: EventWriter<StarColonized>,
+    mut created_events: EventWriter<ConnectionCreated>,
+    mut refused_events: EventWriter<ConnectionRefused>,
 ) {
     let Ok(window) = windows.get_single() else {
         return;
@@ -1064,7 +807,12 @@ fn handle_mouse_input(
         for (transform, entity, star) in &star_query {
             let distance = transform.translation.truncate().distance(cursor_pos);
 
-            if distance < 25.0 && star.is_colonized {
+            if distance < 25.0 && !star.is_owned_by(EmpireId::PLAYER) {
+                drag_state.refused_origin = Some(entity);
+                break;
+            }
+
+            if distance < 25.0 {
                 drag_state.is_dragging = true;
                 drag_state.start_star = Some(entity);
 
@@ -1087,6 +835,27 @@ fn handle_mouse_input(
         }
     }
 
+    if mouse_button.just_released(MouseButton::Left) {
+        if let Some(origin) = drag_state.refused_origin.take() {
+            let target = star_query.iter().find(|(transform, entity, _)| {
+                *entity != origin && transform.translation.truncate().distance(cursor_pos) < 25.0
+            });
+            if let Some((_transform, target_entity, _star)) = target {
+                let reason = match star_query.get(origin) {
+                    Ok((_, _, star)) if star.is_colonized => {
+                        ConnectionRefusal::RivalTerritory(star.empire)
+                    }
+                    _ => ConnectionRefusal::OriginNotColonized,
+                };
+                refused_events.send(ConnectionRefused {
+                    from: origin,
+                    to: target_entity,
+                    reason,
+                });
+            }
+        }
+    }
+
     if mouse_button.just_released(MouseButton::Left) && drag_state.is_dragging {
         // First, find target star and check basic conditions
         let mut target_star_data = None;
@@ -1101,13 +870,27 @@ fn handle_mouse_input(
         // Process connection if we found a target
         if let Some((target_entity, target_is_colonized)) = target_star_data {
             if let Some(start_star_entity) = drag_state.start_star {
-                // Check connection limit
+                // Check connection limit, a connection to a rival star is a
+                // front line that fleets can travel along
                 let can_connect = if let Ok((_transform, _entity, start_star)) =
                     star_query.get(start_star_entity)
                 {
-                    let max_connections =
-                        max_connections_for_level(start_star.specialization_level);
+                    let max_connections = empire::max_connections(
+                        start_star_entity,
+                        start_star,
+                        &tech_tree,
+                        &constellation_tracker,
+                    );
                     let current_connections = start_star.connections_to.len() as u32;
+                    if current_connections >= max_connections {
+                        refused_events.send(ConnectionRefused {
+                            from: start_star_entity,
+                            to: target_entity,
+                            reason: ConnectionRefusal::LimitReached {
+                                max: max_connections,
+                            },
+                        });
+                    }
                     current_connections < max_connections
                 } else {
                     false
@@ -1123,13 +906,21 @@ fn handle_mouse_input(
                         }
                     }
 
-                    if !connection_exists {
+                    if connection_exists {
+                        refused_events.send(ConnectionRefused {
+                            from: start_star_entity,
+                            to: target_entity,
+                            reason: ConnectionRefusal::AlreadyConnected,
+                        });
+                    } else {
                         // Update target star
                         if let Ok((_transform, _entity, mut target_star)) =
                             star_query.get_mut(target_entity)
                         {
-                            if !target_is_colonized {
-                                target_star.is_colonized = true;
+                            if !target_is_colonized && target_star.colonize() {
+                                colonized_events.send(StarColonized {
+                                    star: target_entity,
+                                });
                             }
                             target_star.connections_from.push(start_star_entity);
                         }
@@ -1146,23 +937,22 @@ fn handle_mouse_input(
                         commands.entity(target_entity).insert(StarBorder);
 
                         // Create a connection
-                        commands.spawn((
+                        let connection = commands.spawn((
                             MaterialMesh2dBundle {
                                 mesh: meshes.add(Rectangle::new(1.0, 1.0)).into(),
                                 material: materials
-                                    .add(ColorMaterial::from(Color::srgb(0.2, 0.8, 0.2))),
+                                    .add(ColorMaterial::from(EmpireId::PLAYER.color())),
                                 transform: Transform::from_xyz(0.0, 0.0, -1.0),
                                 ..default()
                             },
-                            Connection {
-                                from: start_star_entity,
-                                to: target_entity,
-                                collection_timer: Timer::from_seconds(2.0, TimerMode::Repeating),
-                                is_collecting: true,
-                                creation_time: 0.0,
-                            },
+                            Connection::new(start_star_entity, target_entity),
                             ConnectionLine,
                         ));
+                        created_events.send(ConnectionCreated {
+                            connection: connection.id(),
+                            from: start_star_entity,
+                            to: target_entity,
+                        });
                     }
                 }
             }
@@ -1229,13 +1019,9 @@ fn update_dragging_line(
 
 fn update_connections(
     star_query: Query<&Transform, With<Star>>,
-    mut connection_query: Query<(&mut Transform, &mut Connection), Without<Star>>,
-    time: Res<Time>,
+    mut connection_query: Query<(&mut Transform, &Connection), Without<Star>>,
 ) {
-    for (mut line_transform, mut connection) in &mut connection_query {
-        // Update creation time
-        connection.creation_time += time.delta_seconds();
-
+    for (mut line_transform, connection) in &mut connection_query {
         if let Ok(from_transform) = star_query.get(connection.from) {
             if let Ok(to_transform) = star_query.get(connection.to) {
                 let start_pos = from_transform.translation.truncate();
@@ -1255,24 +1041,196 @@ fn update_connections(
     }
 }
 
+// Give new cargo packets a small dot in the color of their resource
+fn attach_cargo_visuals(
+    mut commands: Commands,
+    packet_query: Query<(Entity, &CargoPacket), Added<CargoPacket>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, packet) in &packet_query {
+        commands.entity(entity).insert(MaterialMesh2dBundle {
+            mesh: meshes.add(Circle::new(4.0)).into(),
+            material: materials.add(ColorMaterial::from(packet.resource_type.color())),
+            transform: Transform::from_xyz(0.0, 0.0, 0.5),
+            visibility: Visibility::Hidden,
+            ..default()
+        });
+    }
+}
+
+// Move cargo dots along their connection, packets waiting at a star are hidden
+fn update_cargo_dots(
+    star_query: Query<&Transform, (With<Star>, Without<CargoPacket>)>,
+    mut packet_query: Query<(&CargoPacket, &mut Transform, &mut Visibility)>,
+) {
+    for (packet, mut transform, mut visibility) in &mut packet_query {
+        let Some(leg) = packet.leg else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        if let (Ok(from), Ok(to)) = (star_query.get(packet.at), star_query.get(leg.to)) {
+            let position = from
+                .translation
+                .truncate()
+                .lerp(to.translation.truncate(), leg.progress());
+            transform.translation = position.extend(0.5);
+            *visibility = Visibility::Visible;
+        }
+    }
+}
+
+// Give new fleets a marker in the color of their empire
+fn attach_fleet_visuals(
+    mut commands: Commands,
+    fleet_query: Query<(Entity, &Fleet), Added<Fleet>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, fleet) in &fleet_query {
+        commands.entity(entity).insert(MaterialMesh2dBundle {
+            mesh: meshes.add(RegularPolygon::new(9.0, 3)).into(),
+            material: materials.add(ColorMaterial::from(fleet.empire.color())),
+            transform: Transform::from_xyz(0.0, 0.0, 0.6),
+            visibility: Visibility::Hidden,
+            ..default()
+        });
+    }
+}
+
+// Move fleet markers along their connection, blockading fleets circle their target
+fn update_fleet_markers(
+    time: Res<Time>,
+    star_query: Query<&Transform, (With<Star>, Without<Fleet>)>,
+    mut fleet_query: Query<(&Fleet, &mut Transform, &mut Visibility)>,
+) {
+    for (fleet, mut transform, mut visibility) in &mut fleet_query {
+        let (Ok(from), Ok(to)) = (star_query.get(fleet.from), star_query.get(fleet.target)) else {
+            continue;
+        };
+        let (from, to) = (from.translation.truncate(), to.translation.truncate());
+        let position = if fleet.has_arrived() {
+            let angle = time.elapsed_seconds();
+            to + Vec2::new(angle.cos(), angle.sin()) * 38.0
+        } else {
+            from.lerp(to, fleet.progress())
+        };
+        transform.translation = position.extend(0.6);
+        transform.rotation = Quat::from_rotation_z((to - from).to_angle() - FRAC_PI_2);
+        *visibility = Visibility::Visible;
+    }
+}
+
+// With another empire's star selected, X sends the warships of the best armed
+// connected star of the player to attack it and B to blockade it
+fn fleet_orders_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    game_state: Res<GameState>,
+    mut star_query: Query<(Entity, &mut Star, Option<&Transform>)>,
+    connection_query: Query<(Entity, &Connection)>,
+    mut commands: Commands,
+    mut notifications: ResMut<Notifications>,
+) {
+    let mission = if keyboard.just_pressed(KeyCode::KeyX) {
+        Mission::Attack
+    } else if keyboard.just_pressed(KeyCode::KeyB) {
+        Mission::Blockade
+    } else {
+        return;
+    };
+    let Some(target) = game_state.selected_star else {
+        return;
+    };
+    let Ok((_, target_star, _)) = star_query.get(target) else {
+        return;
+    };
+    if !target_star.is_colonized || target_star.is_owned_by(EmpireId::PLAYER) {
+        return;
+    }
+
+    let origin = target_star
+        .connections_from
+        .iter()
+        .chain(&target_star.connections_to)
+        .filter_map(|neighbour| star_query.get(*neighbour).ok())
+        .filter(|(_, star, _)| star.is_owned_by(EmpireId::PLAYER))
+        .max_by_key(|(_, star, _)| star.unit_count(UnitType::Warship))
+        .map(|(entity, _, _)| entity);
+    let Some(origin) = origin else {
+        notifications.push(
+            MessageCategory::Combat,
+            FleetError::NotConnected.to_string(),
+        );
+        return;
+    };
+    let Ok([(_, mut from_star, from_transform), (_, to_star, to_transform)]) =
+        star_query.get_many_mut([origin, target])
+    else {
+        return;
+    };
+    match launch_fleet(
+        (origin, &mut from_star, from_transform),
+        (target, &to_star, to_transform),
+        connection_between(&connection_query, origin, target),
+        mission,
+    ) {
+        Ok(fleet) => {
+            notifications.push(
+                MessageCategory::Combat,
+                format!(
+                    "{} warships left {} to {} {}",
+                    fleet.ships,
+                    from_star.name,
+                    mission.name(),
+                    to_star.name
+                ),
+            );
+            commands.spawn(fleet);
+        }
+        Err(err) => notifications.push(MessageCategory::Combat, err.to_string()),
+    }
+}
+
+// Keep connection lines in the color of the empire owning their origin, which
+// changes when a star is captured
+fn recolor_connections(
+    connection_query: Query<(&Connection, &Handle<ColorMaterial>), With<ConnectionLine>>,
+    star_query: Query<&Star>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (connection, material) in &connection_query {
+        let Ok(star) = star_query.get(connection.from) else {
+            continue;
+        };
+        let color = star.empire.color();
+        if materials.get(material).is_some_and(|m| m.color != color) {
+            if let Some(material) = materials.get_mut(material) {
+                material.color = color;
+            }
+        }
+    }
+}
+
 fn connection_selection_system(
     mouse_button: Res<ButtonInput<MouseButton>>,
     windows: Query<&Window, With<PrimaryWindow>>,
-    camera_q: Query<(&Camera, &GlobalTransform)>,
+    camera_q: Query<(&Camera, &GlobalTransform, &OrthographicProjection)>,
     connection_query: Query<(Entity, &Connection, &Transform), With<ConnectionLine>>,
     star_query: Query<&Transform, With<Star>>,
     mut commands: Commands,
     keyboard: Res<ButtonInput<KeyCode>>,
     mut stars: Query<&mut Star>,
     selected_connection: Option<Res<SelectedConnection>>,
+    mut removed_events: EventWriter<ConnectionRemoved>,
 ) {
     let Ok(window) = windows.get_single() else {
         return;
     };
 
-    let Ok((camera, camera_transform)) = camera_q.get_single() else {
+    let Ok((camera, camera_transform, projection)) = camera_q.get_single() else {
         return;
     };
+    let camera_scale = projection.scale;
 
     let Some(cursor_pos) = window
         .cursor_position()
@@ -1284,31 +1242,27 @@ fn connection_selection_system(
     // Check for delete key press on selected connection
     if keyboard.just_pressed(KeyCode::Delete) {
         if let Some(selected) = selected_connection {
-            let mut from_has_no_connections = false;
-            let mut to_has_no_connections = false;
-
-            // Remove connection from stars
+            // Rival networks can be looked at but not cut
+            if !stars
+                .get(selected.from)
+                .is_ok_and(|star| star.is_owned_by(EmpireId::PLAYER))
+            {
+                return;
+            }
+            // Remove connection from stars, both stay colonized and keep their border
             if let Ok(mut from_star) = stars.get_mut(selected.from) {
                 from_star.connections_to.retain(|&x| x != selected.to);
-                from_has_no_connections =
-                    from_star.connections_to.is_empty() && from_star.connections_from.is_empty();
             }
             if let Ok(mut to_star) = stars.get_mut(selected.to) {
                 to_star.connections_from.retain(|&x| x != selected.from);
-                to_has_no_connections =
-                    to_star.connections_to.is_empty() && to_star.connections_from.is_empty();
-            }
-
-            // Remove StarBorder component if star has no more connections
-            if from_has_no_connections {
-                commands.entity(selected.from).remove::<StarBorder>();
-            }
-            if to_has_no_connections {
-                commands.entity(selected.to).remove::<StarBorder>();
             }
 
             // Delete the connection entity
             commands.entity(selected.entity).despawn();
+            removed_events.send(ConnectionRemoved {
+                from: selected.from,
+                to: selected.to,
+            });
             commands.remove_resource::<SelectedConnection>();
             return;
         }
@@ -1340,8 +1294,8 @@ fn connection_selection_system(
                     let closest_point = start_pos + line_vec * t;
                     let distance = cursor_pos.distance(closest_point);
 
-                    // Check if click is close enough to the line
-                    if distance < 10.0 {
+                    // Check if click is close enough to the line (10 pixels at any zoom)
+                    if distance < 10.0 * camera_scale {
                         commands.insert_resource(SelectedConnection {
                             entity,
                             from: connection.from,
@@ -1355,166 +1309,6 @@ fn connection_selection_system(
     }
 }
 
-fn collect_resources_system(
-    time: Res<Time>,
-    mut connection_query: Query<&mut Connection>,
-    mut star_queries: ParamSet<(
-        Query<&mut Star>,
-        Query<&Star>,
-    )>,
-    mut player_resources: ResMut<PlayerResources>,
-    constellation_tracker: Res<ConstellationTracker>,
-) {
-    // First, update building timers
-    for mut star in &mut star_queries.p0() {
-        match star.building_state {
-            BuildingState::Building {
-                mut timer,
-                total_time,
-            } => {
-                timer -= time.delta_seconds();
-                if timer <= 0.0 {
-                    star.building_state = BuildingState::Ready;
-                } else {
-                    star.building_state = BuildingState::Building { timer, total_time };
-                }
-            }
-            BuildingState::Upgrading {
-                mut timer,
-                total_time,
-            } => {
-                timer -= time.delta_seconds();
-                if timer <= 0.0 {
-                    star.building_state = BuildingState::Ready;
-                    star.specialization_level = star.specialization_level + 1; // No limit on levels
-                } else {
-                    star.building_state = BuildingState::Upgrading { timer, total_time };
-                }
-            }
-            _ => {}
-        }
-    }
-
-    for mut connection in &mut connection_query {
-        if connection.is_collecting {
-            connection.collection_timer.tick(time.delta());
-
-            if connection.collection_timer.just_finished() {
-                // First calculate distance to nearest storage hub
-                let mut visited = Vec::new();
-                let distance = {
-                    let star_readonly = star_queries.p1();
-                    calculate_distance_to_nearest_storage(connection.to, &star_readonly, &mut visited)
-                };
-                let distance_modifier = production_rate_modifier_from_distance(distance);
-                
-                // Then collect resources from the connected star
-                if let Ok(mut star) = star_queries.p0().get_mut(connection.to) {
-                    // Only produce if building is ready
-                    if star.building_state != BuildingState::Ready {
-                        continue;
-                    }
-
-                    // Only collect resources if star is not specialized for something other than storage
-                    if star.specialization == Specialization::None || star.specialization == Specialization::Storage {
-                        // Check if star is in a constellation for bonus
-                        let constellation_bonus = check_constellation_bonuses(connection.to, &constellation_tracker);
-                        let production_rate = star.production_rate * distance_modifier * constellation_bonus;
-                        for (resource_type, amount) in star.resources.iter_mut() {
-                            let collection_amount = (production_rate * 5.0).min(*amount);
-                            if collection_amount > 0.0 {
-                                *amount -= collection_amount;
-                                *player_resources.resources.entry(*resource_type).or_insert(0.0) += collection_amount;
-                            }
-                        }
-                    } else {
-                        // Specialized star: consume resources and produce units
-                        let production_costs = star
-                            .specialization
-                            .production_cost(star.specialization_level);
-                        let mut can_produce = true;
-
-                        // Check if we have enough resources
-                        for (resource_type, cost) in &production_costs {
-                            if *player_resources
-                                .resources
-                                .get(resource_type)
-                                .unwrap_or(&0.0)
-                                < *cost
-                            {
-                                can_produce = false;
-                                break;
-                            }
-                        }
-
-                        // Produce units if we have resources
-                        if can_produce {
-                            // Consume resources
-                            for (resource_type, cost) in &production_costs {
-                                *player_resources.resources.get_mut(resource_type).unwrap() -= cost;
-                            }
-
-                            // Produce units based on specialization (more at higher levels)
-                            let level_bonus = star.specialization_level;
-                            match star.specialization {
-                                Specialization::Military => {
-                                    star.units.push(Unit {
-                                        unit_type: UnitType::Warship,
-                                        count: level_bonus as u32,
-                                    });
-                                }
-                                Specialization::Mining => {
-                                    star.units.push(Unit {
-                                        unit_type: UnitType::MiningShip,
-                                        count: (2 * level_bonus) as u32,
-                                    });
-                                }
-                                Specialization::Agriculture => {
-                                    star.units.push(Unit {
-                                        unit_type: UnitType::Farmer,
-                                        count: (3 * level_bonus) as u32,
-                                    });
-                                }
-                                Specialization::Research => {
-                                    star.units.push(Unit {
-                                        unit_type: UnitType::Scientist,
-                                        count: level_bonus as u32,
-                                    });
-                                }
-                                Specialization::Medical => {
-                                    star.units.push(Unit {
-                                        unit_type: UnitType::Doctor,
-                                        count: (2 * level_bonus) as u32,
-                                    });
-                                }
-                                Specialization::Industrial => {
-                                    star.units.push(Unit {
-                                        unit_type: UnitType::Builder,
-                                        count: (2 * level_bonus) as u32,
-                                    });
-                                }
-                                Specialization::Storage => {
-                                    star.units.push(Unit {
-                                        unit_type: UnitType::StorageModule,
-                                        count: level_bonus as u32,
-                                    });
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-
-                    // Check if star is depleted
-                    let total_resources: f32 = star.resources.values().sum();
-                    if total_resources < 0.1 {
-                        connection.is_collecting = false;
-                    }
-                }
-            }
-        }
-    }
-}
-
 fn update_star_borders(
     mut commands: Commands,
     star_query: Query<(Entity, &Transform, &Star), With<StarBorder>>,
@@ -1527,16 +1321,16 @@ fn update_star_borders(
         commands.entity(border_entity).despawn();
     }
 
-    // Create new borders for stars with connections
+    // Create new borders for colonized stars, in the color of their empire
     for (entity, transform, star) in &star_query {
-        let has_connections = !star.connections_from.is_empty() || !star.connections_to.is_empty();
+        if star.is_colonized {
+            let color = star.empire.color();
 
-        if has_connections {
             // Create a border ring around the star
             commands.spawn((
                 MaterialMesh2dBundle {
                     mesh: meshes.add(Circle::new(30.0)).into(),
-                    material: materials.add(ColorMaterial::from(Color::srgba(0.2, 1.0, 0.2, 0.3))),
+                    material: materials.add(ColorMaterial::from(color.with_alpha(0.3))),
                     transform: Transform::from_xyz(
                         transform.translation.x,
                         transform.translation.y,
@@ -1551,7 +1345,7 @@ fn update_star_borders(
             commands.spawn((
                 MaterialMesh2dBundle {
                     mesh: meshes.add(Circle::new(32.0)).into(),
-                    material: materials.add(ColorMaterial::from(Color::srgba(0.1, 0.8, 0.1, 0.2))),
+                    material: materials.add(ColorMaterial::from(color.with_alpha(0.2))),
                     transform: Transform::from_xyz(
                         transform.translation.x,
                         transform.translation.y,
@@ -1562,7 +1356,7 @@ fn update_star_borders(
                 StarBorder,
             ));
         } else {
-            // Remove StarBorder component if no connections
+            // Remove StarBorder component if not colonized
             commands.entity(entity).remove::<StarBorder>();
         }
     }
@@ -1685,7 +1479,7 @@ fn update_bloom_settings(
 }
 
 fn update_ui(
-    player_resources: Res<PlayerResources>,
+    mut player_resources: ResMut<PlayerResources>,
     mut resource_panel_query: Query<&mut Text, (With<ResourcePanel>, Without<StarInfoPanel>)>,
     mut star_info_query: Query<&mut Text, (With<StarInfoPanel>, Without<ResourcePanel>)>,
     mut star_queries: ParamSet<(Query<&Star>, Query<&mut Star>)>,
@@ -1695,20 +1489,57 @@ fn update_ui(
     _commands: Commands,
     selected_connection: Option<Res<SelectedConnection>>,
     constellation_tracker: Res<ConstellationTracker>,
+    mut notifications: ResMut<Notifications>,
+    tech_tree: Res<TechTree>,
+    open_panel: Res<OpenPanel>,
+    route_query: Query<&RouteDistance>,
+    cargo_query: Query<&CargoPacket>,
 ) {
     // Update resource panel
     if let Ok(mut text) = resource_panel_query.get_single_mut() {
         let mut resource_text = "=== RESOURCES ===\n".to_string();
+        let in_transit = cargo_in_transit(&cargo_query, EmpireId::PLAYER);
 
-        for (resource_type, amount) in &player_resources.resources {
+        for resource_type in ResourceType::ALL {
+            let amount = player_resources.amount(resource_type);
+            let capacity = player_resources.capacity(resource_type);
+            let shipping = in_transit.get(&resource_type).copied().unwrap_or(0.0);
             resource_text.push_str(&format!(
-                "{} {}: {:.1}\n",
+                "{} {}: {:.1}/{:.0}{}{}\n",
                 resource_type.icon(),
                 resource_type.name(),
-                amount
+                amount,
+                capacity,
+                if shipping > 0.0 {
+                    format!(" (+{:.1} in transit)", shipping)
+                } else {
+                    String::new()
+                },
+                if amount >= capacity { " FULL" } else { "" }
             ));
         }
 
+        resource_text.push_str(&format!(
+            "🔬 Research: {:.1}\n",
+            player_resources.research_points
+        ));
+        let colonists: f32 = star_queries
+            .p0()
+            .iter()
+            .filter(|star| star.is_owned_by(EmpireId::PLAYER))
+            .map(|star| star.population)
+            .sum();
+        resource_text.push_str(&format!(
+            "👥 Colonists: {:.0} (life support {:.0}%{})\n",
+            colonists,
+            player_resources.life_support * 100.0,
+            if player_resources.life_support < 1.0 {
+                " SHORTAGE"
+            } else {
+                ""
+            }
+        ));
+
         text.sections[0].value = resource_text;
     }
 
@@ -1722,15 +1553,17 @@ fn update_ui(
                     star.name.clone(),
                     star.is_home_star,
                     star.is_colonized,
+                    star.empire,
                     star.specialization,
                     star.specialization_level,
                     star.production_rate,
                     star.units.clone(),
                     star.resources.clone(),
                     star.max_resources.clone(),
-                    star.building_state,
+                    star.building_state.clone(),
                     star.connections_from.len() as u32,
                     star.connections_to.len() as u32,
+                    star.population,
                 )
             });
 
@@ -1739,6 +1572,7 @@ fn update_ui(
                 name,
                 is_home_star,
                 is_colonized,
+                owner,
                 specialization,
                 level,
                 production_rate,
@@ -1748,9 +1582,15 @@ fn update_ui(
                 building_state,
                 connections_from,
                 connections_to,
+                population,
             )) = star_data
             {
                 let mut info_text = format!("=== STAR INFO ===\n{} (ID: {})\n", name, id);
+                // Only the player's own stars can be rebuilt or upgraded
+                let is_own = is_colonized && owner.is_player();
+                let perks = ZodiacPerks::new(&constellation_tracker, owner);
+                let extra_connections = constellation_tracker.extra_connections(selected_entity)
+                    + perks.extra_connections;
 
                 if is_home_star {
                     info_text.push_str("HOME SYSTEM\n");
@@ -1758,6 +1598,15 @@ fn update_ui(
 
                 if is_colonized {
                     info_text.push_str("Status: COLONIZED\n");
+                    if !is_own {
+                        info_text.push_str(&format!("Owner: {}\n", owner.name()));
+                    }
+                    let garrison = units.get(&UnitType::Warship).copied().unwrap_or(0);
+                    info_text.push_str(&format!("Garrison: {} warships\n", garrison));
+                    if !is_own {
+                        info_text.push_str("[X] ATTACK  [B] BLOCKADE from a connected star\n");
+                    }
+                    info_text.push_str(&format!("Population: {:.0}\n", population));
                     info_text.push_str(&format!(
                         "Specialization: {} {} (Level {})
 ",
@@ -1768,14 +1617,18 @@ fn update_ui(
 
                     // Show building state
                     match building_state {
-                        BuildingState::Building { timer, total_time } => {
+                        BuildingState::Building {
+                            timer, total_time, ..
+                        } => {
                             let progress = ((total_time - timer) / total_time * 100.0) as u32;
                             info_text.push_str(&format!(
                                 "⚙️ BUILDING: {}% complete ({:.1}s remaining)\n",
                                 progress, timer
                             ));
                         }
-                        BuildingState::Upgrading { timer, total_time } => {
+                        BuildingState::Upgrading {
+                            timer, total_time, ..
+                        } => {
                             let progress = ((total_time - timer) / total_time * 100.0) as u32;
                             info_text.push_str(&format!(
                                 "⬆️ UPGRADING: {}% complete ({:.1}s remaining)\n",
@@ -1786,26 +1639,37 @@ fn update_ui(
                             info_text.push_str("✅ OPERATIONAL\n");
                         }
                     }
+                    if is_own && building_state != BuildingState::Ready && !is_home_star {
+                        info_text.push_str(&format!(
+                            "[C] CANCEL ({:.0}% refund)\n",
+                            construction::CANCEL_REFUND * 100.0
+                        ));
+                    }
 
                     // Show specialization options if colonized
-                    if !is_home_star && building_state == BuildingState::Ready {
+                    if is_own && !is_home_star && building_state == BuildingState::Ready {
                         info_text.push_str("\n=== CHANGE SPECIALIZATION ===\n");
-                        info_text.push_str("[1] Resource Extraction\n");
-                        info_text.push_str("[2] Storage Hub\n");
-                        info_text.push_str("[3] Military Base\n");
-                        info_text.push_str("[4] Mining Station\n");
-                        info_text.push_str("[5] Agricultural Colony\n");
-                        info_text.push_str("[6] Research Center\n");
-                        info_text.push_str("[7] Medical Facility\n");
-                        info_text.push_str("[8] Industrial Complex\n");
+                        for (key, spec) in SPECIALIZATION_KEYS.iter().enumerate() {
+                            info_text.push_str(&format!(
+                                "[{}] {} ({})\n",
+                                key + 1,
+                                spec.name(),
+                                format_costs(&spec.construction_cost())
+                            ));
+                        }
 
                         // No level limit, show upgrade option and next Fibonacci connection limit
-                        let next_max_conn = max_connections_for_level(level + 1);
+                        let next_max_conn =
+                            tech_tree.max_connections(level + 1) + extra_connections;
                         info_text.push_str(&format!(
                             "\n[U] UPGRADE to Level {} (Next max connections: {})\n",
                             level + 1,
                             next_max_conn
                         ));
+                        info_text.push_str(&format!(
+                            "    Cost: {}\n",
+                            format_costs(&specialization.upgrade_cost(level + 1))
+                        ));
 
                         // Show connection progression for next few levels
                         info_text.push_str("\nConnection Limit Progression:\n");
@@ -1814,7 +1678,7 @@ fn update_ui(
                             info_text.push_str(&format!(
                                 "  Level {}: {} connections\n",
                                 future_level,
-                                max_connections_for_level(future_level)
+                                tech_tree.max_connections(future_level) + extra_connections
                             ));
                         }
                     }
@@ -1822,16 +1686,18 @@ fn update_ui(
                     info_text.push_str("Status: UNCOLONIZED\n");
                 }
 
-                let max_conn = max_connections_for_level(level);
+                let max_conn = tech_tree.max_connections(level) + extra_connections;
                 info_text.push_str(&format!(
                     "Connections: {} inbound, {} outbound (max outbound: {})\n",
                     connections_from, connections_to, max_conn
                 ));
 
                 // Show route distance to storage hub and production efficiency
-                let mut visited = Vec::new();
-                let route_distance = calculate_distance_to_nearest_storage(selected_entity, &star_queries.p0(), &mut visited);
-                let efficiency_modifier = production_rate_modifier_from_distance(route_distance);
+                let route_distance = route_query
+                    .get(selected_entity)
+                    .ok()
+                    .and_then(|route| route.0);
+                let efficiency_modifier = tech_tree.route_efficiency(route_distance);
                 
                 if let Some(hops) = route_distance {
                     info_text.push_str(&format!("Supply Route Distance: {} connection(s)\n", hops));
@@ -1850,9 +1716,31 @@ fn update_ui(
                 }
 
                 // Check if star is in a constellation
-                let constellation_bonus = check_constellation_bonuses(selected_entity, &constellation_tracker);
-                if constellation_bonus > 1.0 {
-                    info_text.push_str("\n⭐ CONSTELLATION BONUS: 2x Production! ⭐\n");
+                let base_bonus =
+                    check_constellation_bonuses(selected_entity, &constellation_tracker);
+                let constellation_bonus =
+                    perks.constellation_bonus(tech_tree.constellation_bonus(base_bonus));
+                if let Some(constellation) = constellation_tracker.constellation_of(selected_entity)
+                {
+                    let bonus = match constellation.kind.bonus() {
+                        ConstellationBonus::Production(_) => {
+                            ConstellationBonus::Production(constellation_bonus)
+                        }
+                        other => other,
+                    };
+                    info_text.push_str(&format!(
+                        "\n⭐ {} CONSTELLATION: {}! ⭐\n",
+                        constellation.kind.name().to_uppercase(),
+                        bonus.describe()
+                    ));
+                    if let Some(sign) = constellation.zodiac {
+                        info_text.push_str(&format!(
+                            "{} {} completed: {}\n",
+                            sign.symbol(),
+                            sign.name(),
+                            sign.perk_description()
+                        ));
+                    }
                     info_text.push_str("This star is part of a constellation.\n");
                     info_text.push_str("No new constellations can be formed with this star.\n\n");
                 }
@@ -1867,12 +1755,18 @@ fn update_ui(
                     // Show units produced
                     if !units.is_empty() {
                         info_text.push_str("\nUnits Produced:\n");
-                        for unit in &units {
-                            info_text.push_str(&format!(
-                                "  {} x{}\n",
-                                format!("{:?}", unit.unit_type),
-                                unit.count
-                            ));
+                        for unit_type in UnitType::ALL {
+                            if let Some(count) = units.get(&unit_type) {
+                                info_text.push_str(&format!("  {} x{}\n", unit_type.name(), count));
+                            }
+                        }
+                    }
+
+                    // Industry refines the scarcest good it can afford
+                    if specialization == Specialization::Industrial {
+                        info_text.push_str(&format!("\nRefines (x{} per cycle):\n", level));
+                        for recipe in &RECIPES {
+                            info_text.push_str(&format!("  {}\n", recipe.describe()));
                         }
                     }
 
@@ -1904,66 +1798,66 @@ fn update_ui(
                 text.sections[0].value = info_text;
 
                 // Handle specialization selection
-                if is_colonized && !is_home_star {
+                // Digit keys belong to the research panel or message log while one is open
+                if is_own && !is_home_star && *open_panel == OpenPanel::None {
                     if let Ok(mut selected_star) = star_queries.p1().get_mut(selected_entity) {
-                        let mut new_spec = None;
-
-                        if keyboard.just_pressed(KeyCode::Digit1) {
-                            new_spec = Some(Specialization::None);
-                        } else if keyboard.just_pressed(KeyCode::Digit2) {
-                            new_spec = Some(Specialization::Storage);
-                        } else if keyboard.just_pressed(KeyCode::Digit3) {
-                            new_spec = Some(Specialization::Military);
-                        } else if keyboard.just_pressed(KeyCode::Digit4) {
-                            new_spec = Some(Specialization::Mining);
-                        } else if keyboard.just_pressed(KeyCode::Digit5) {
-                            new_spec = Some(Specialization::Agriculture);
-                        } else if keyboard.just_pressed(KeyCode::Digit6) {
-                            new_spec = Some(Specialization::Research);
-                        } else if keyboard.just_pressed(KeyCode::Digit7) {
-                            new_spec = Some(Specialization::Medical);
-                        } else if keyboard.just_pressed(KeyCode::Digit8) {
-                            new_spec = Some(Specialization::Industrial);
-                        }
-
-                        if let Some(spec) = new_spec {
-                            if selected_star.specialization != spec {
-                                selected_star.specialization = spec;
-                                selected_star.specialization_level = 1; // Reset level when changing
-                                selected_star.units.clear();
-                                
-                                // If becoming a storage hub, set up storage capacity
-                                if spec == Specialization::Storage {
-                                    selected_star.is_storage_hub = true;
-                                    let max_resources_copy = selected_star.max_resources.clone();
-                                    for (resource_type, max_value) in &max_resources_copy {
-                                        let capacity = max_value * 10.0; // Storage hub has 10x capacity
-                                        selected_star.storage_capacity.insert(*resource_type, capacity);
-                                    }
-                                } else {
-                                    selected_star.is_storage_hub = false;
-                                    selected_star.storage_capacity.clear();
-                                }
-                                
-                                let build_time = spec.build_time();
-                                selected_star.building_state = BuildingState::Building {
-                                    timer: build_time,
-                                    total_time: build_time,
-                                };
+                        let digit_keys = [
+                            KeyCode::Digit1,
+                            KeyCode::Digit2,
+                            KeyCode::Digit3,
+                            KeyCode::Digit4,
+                            KeyCode::Digit5,
+                            KeyCode::Digit6,
+                            KeyCode::Digit7,
+                            KeyCode::Digit8,
+                        ];
+                        let new_spec = digit_keys
+                            .iter()
+                            .zip(SPECIALIZATION_KEYS)
+                            .find(|(key, _)| keyboard.just_pressed(**key))
+                            .map(|(_, spec)| spec);
+
+                        let result = if let Some(spec) = new_spec {
+                            Some(
+                                construction::specialize(
+                                    &mut selected_star,
+                                    spec,
+                                    &mut player_resources,
+                                )
+                                .map(|()| format!("Building {}", spec.name())),
+                            )
+                        } else if keyboard.just_pressed(KeyCode::KeyU) {
+                            // Handle upgrade (no level limit)
+                            Some(
+                                construction::upgrade(
+                                    &mut selected_star,
+                                    &mut player_resources,
+                                    &tech_tree,
+                                )
+                                .map(|()| format!("Upgrading to level {}", level + 1)),
+                            )
+                        } else if keyboard.just_pressed(KeyCode::KeyC) {
+                            Some(
+                                construction::cancel(&mut selected_star, &mut player_resources)
+                                    .map(|refund| {
+                                        format!(
+                                            "Construction cancelled, refunded {}",
+                                            format_costs(&refund)
+                                        )
+                                    }),
+                            )
+                        } else {
+                            None
+                        };
+
+                        match result {
+                            Some(Ok(message)) => {
+                                notifications.push(MessageCategory::Construction, message)
                             }
-                        }
-
-                        // Handle upgrade (no level limit)
-                        if keyboard.just_pressed(KeyCode::KeyU) {
-                            if selected_star.building_state == BuildingState::Ready {
-                                let upgrade_time = selected_star
-                                    .specialization
-                                    .upgrade_time(selected_star.specialization_level);
-                                selected_star.building_state = BuildingState::Upgrading {
-                                    timer: upgrade_time,
-                                    total_time: upgrade_time,
-                                };
+                            Some(Err(err)) => {
+                                notifications.push(MessageCategory::Construction, err.to_string())
                             }
+                            None => {}
                         }
                     }
                 }
@@ -2036,70 +1930,71 @@ fn update_ui(
     }
 }
 
-// System to detect and create constellations when cycles are formed
-fn detect_and_create_constellations(
+// Spawn visuals for constellations that the simulation has formed since the last frame
+fn spawn_constellation_visuals(
     stars_query: Query<(Entity, &Star, &Transform)>,
-    stars_simple: Query<(Entity, &Star)>,
-    mut constellation_tracker: ResMut<ConstellationTracker>,
+    marker_query: Query<&ConstellationMarker>,
+    constellation_tracker: Res<ConstellationTracker>,
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    // Find all cycles of 3 or more stars
-    let cycles = find_cycles_in_graph(&stars_simple, 3);
-    
-    for cycle_entities in cycles {
-        // Check if this constellation already exists
-        let is_new = !constellation_tracker.constellations.iter().any(|c| {
-            let mut sorted_cycle = cycle_entities.clone();
-            sorted_cycle.sort_by_key(|e| e.index());
-            let mut sorted_existing = c.stars.clone();
-            sorted_existing.sort_by_key(|e| e.index());
-            sorted_cycle == sorted_existing
-        });
-        
-        // Check if any star in this cycle is already part of another constellation
-        let has_existing_constellation_star = cycle_entities.iter().any(|&star_entity| {
-            constellation_tracker.constellations.iter().any(|existing_constellation| {
-                existing_constellation.stars.contains(&star_entity)
-            })
-        });
-        
-        // Only create constellation if it's new AND no stars are already in other constellations
-        if is_new && !has_existing_constellation_star {
-            // Create a new constellation with varied colors
-            let hue = (constellation_tracker.next_id as f32 * 137.5) % 360.0; // Golden angle for color distribution
-            let color = Color::hsla(
-                hue,
-                0.7,  // Good saturation
-                0.6,  // Medium lightness 
-                0.25, // Semi-transparent
+    let drawn: HashSet<u32> = marker_query.iter().map(|marker| marker.id).collect();
+
+    for constellation in &constellation_tracker.constellations {
+        if !drawn.contains(&constellation.id) {
+            create_constellation_visual(
+                constellation,
+                &stars_query,
+                &mut commands,
+                &mut meshes,
+                &mut materials,
             );
-            
-            let constellation = Constellation {
-                id: constellation_tracker.next_id,
-                stars: cycle_entities.clone(),
-                color,
-            };
-            
-            constellation_tracker.next_id += 1;
-            
-            // Create visual representation of the constellation
-            create_constellation_visual(&constellation, &stars_query, &mut commands, &mut meshes, &mut materials);
-            
-            constellation_tracker.constellations.push(constellation);
-            
-            info!("New constellation formed with {} stars!", cycle_entities.len());
-        } else if !is_new {
-            // Constellation already exists
-            debug!("Cycle detected but constellation already exists");
-        } else if has_existing_constellation_star {
-            // Can't create because stars are already in other constellations
-            info!("Cannot form new constellation: one or more stars already belong to existing constellations");
         }
     }
 }
 
+// Drop the polygon of constellations that broke up
+fn remove_dissolved_constellations(
+    mut dissolved_events: EventReader<ConstellationDissolved>,
+    marker_query: Query<(Entity, &ConstellationMarker)>,
+    mut notifications: ResMut<Notifications>,
+    mut commands: Commands,
+) {
+    for event in dissolved_events.read() {
+        for (entity, marker) in &marker_query {
+            if marker.id == event.id {
+                commands.entity(entity).despawn();
+            }
+        }
+        notifications.push(
+            MessageCategory::Constellation,
+            format!(
+                "Constellation of {} stars dissolved: {}",
+                event.stars.len(),
+                event.reason.describe()
+            ),
+        );
+    }
+}
+
+fn announce_zodiac_signs(
+    mut completed_events: EventReader<ZodiacCompleted>,
+    mut notifications: ResMut<Notifications>,
+) {
+    for event in completed_events.read() {
+        notifications.push(
+            MessageCategory::Constellation,
+            format!(
+                "{} {} completed! {}",
+                event.sign.symbol(),
+                event.sign.name(),
+                event.sign.perk_description()
+            ),
+        );
+    }
+}
+
 // Create visual polygon for constellation
 fn create_constellation_visual(
     constellation: &Constellation,
@@ -2247,15 +2142,803 @@ fn create_constellation_visual(
     }
 }
 
-// Check if a star is part of any constellation and apply bonuses
-fn check_constellation_bonuses(
-    star_entity: Entity,
-    constellation_tracker: &ConstellationTracker,
-) -> f32 {
-    for constellation in &constellation_tracker.constellations {
-        if constellation.stars.contains(&star_entity) {
-            return 2.0; // 100% bonus (2x multiplier)
+// F1-F4 pick the save slot, F5 quicksaves and F9 quickloads it
+fn save_load_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut save_slot: ResMut<SaveSlot>,
+    mut save_requests: EventWriter<SaveRequest>,
+    mut load_requests: EventWriter<LoadRequest>,
+    mut slot_text: Query<&mut Text, With<SaveSlotText>>,
+) {
+    let slot_keys = [KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4];
+    for (slot, key) in (1..=SAVE_SLOTS).zip(slot_keys) {
+        if keyboard.just_pressed(key) {
+            save_slot.0 = slot;
         }
     }
-    1.0 // No bonus
+
+    if keyboard.just_pressed(KeyCode::F5) {
+        save_requests.send(SaveRequest { slot: save_slot.0 });
+    }
+    if keyboard.just_pressed(KeyCode::F9) {
+        load_requests.send(LoadRequest { slot: save_slot.0 });
+    }
+
+    if let Ok(mut text) = slot_text.get_single_mut() {
+        text.sections[0].value = format!(
+            "Save slot {}/{} [F1-F4] | [F5] Quicksave | [F9] Quickload",
+            save_slot.0, SAVE_SLOTS
+        );
+    }
+}
+
+// Space pauses, -/= change the speed and . steps a paused simulation forward
+fn simulation_speed_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut clock: ResMut<SimulationClock>,
+    mut speed_text: Query<&mut Text, With<SimulationSpeedText>>,
+) {
+    if keyboard.just_pressed(KeyCode::Space) {
+        clock.toggle_pause();
+    }
+    if keyboard.just_pressed(KeyCode::Equal) {
+        clock.faster();
+    }
+    if keyboard.just_pressed(KeyCode::Minus) {
+        clock.slower();
+    }
+    if keyboard.just_pressed(KeyCode::Period) {
+        clock.step();
+    }
+
+    if !clock.is_changed() {
+        return;
+    }
+    if let Ok(mut text) = speed_text.get_single_mut() {
+        text.sections[0].value = if clock.paused {
+            format!(
+                "PAUSED ({}x) [Space] Resume | [.] Step {}s",
+                clock.speed(),
+                STEP_SECONDS
+            )
+        } else {
+            format!("Speed {}x [-/=] | [Space] Pause", clock.speed())
+        };
+    }
+}
+
+// Show the session summary when the game ends, Enter hides it and keeps playing
+fn game_summary_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut ended_events: EventReader<GameEnded>,
+    mut loaded_events: EventReader<GameLoaded>,
+    stats: Res<SessionStats>,
+    star_query: Query<&Star>,
+    player_resources: Res<PlayerResources>,
+    mut clock: ResMut<SimulationClock>,
+    mut panel_query: Query<(&mut Text, &mut Visibility), With<GameSummaryPanel>>,
+) {
+    let Ok((mut text, mut visibility)) = panel_query.get_single_mut() else {
+        return;
+    };
+
+    if loaded_events.read().count() > 0 {
+        *visibility = Visibility::Hidden;
+    }
+    if *visibility == Visibility::Visible && keyboard.just_pressed(KeyCode::Enter) {
+        *visibility = Visibility::Hidden;
+        clock.paused = false;
+    }
+
+    let Some(event) = ended_events.read().last() else {
+        return;
+    };
+    let (title, color) = if event.outcome.is_victory() {
+        ("VICTORY", Color::srgb(1.0, 0.85, 0.3))
+    } else {
+        ("DEFEAT", Color::srgb(1.0, 0.35, 0.35))
+    };
+    let colonized = star_query
+        .iter()
+        .filter(|star| star.is_owned_by(EmpireId::PLAYER))
+        .count();
+    let mut delivered: Vec<(ResourceType, f32)> = stats
+        .resources_delivered
+        .iter()
+        .map(|(resource_type, amount)| (*resource_type, *amount))
+        .collect();
+    delivered.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let delivered: Vec<String> = delivered
+        .iter()
+        .take(3)
+        .map(|(resource_type, amount)| format!("{} {:.0}", resource_type.name(), amount))
+        .collect();
+
+    let minutes = (stats.elapsed / 60.0) as u32;
+    let seconds = stats.elapsed as u32 % 60;
+    let mut summary = format!("=== {} ===\n{}\n\n", title, event.outcome.describe());
+    summary.push_str(&format!("Time played: {:02}:{:02}\n", minutes, seconds));
+    summary.push_str(&format!(
+        "Stars colonized: {}/{}\n",
+        colonized,
+        star_query.iter().count()
+    ));
+    summary.push_str(&format!(
+        "Connections built: {} ({} removed)\n",
+        stats.connections_built, stats.connections_removed
+    ));
+    summary.push_str(&format!(
+        "Constellations formed: {}\n",
+        stats.constellations_formed
+    ));
+    summary.push_str(&format!(
+        "Zodiac signs completed: {}/{}\n",
+        stats.zodiac_signs.len(),
+        ZodiacSign::ALL.len()
+    ));
+    summary.push_str(&format!("Upgrades completed: {}\n", stats.upgrades));
+    summary.push_str(&format!("Units produced: {}\n", stats.units_produced));
+    summary.push_str(&format!("Stars depleted: {}\n", stats.stars_depleted));
+    summary.push_str(&format!(
+        "Colonies abandoned: {}\n",
+        stats.colonies_abandoned
+    ));
+    summary.push_str(&format!(
+        "Battles fought: {} ({} stars captured, {} lost)\n",
+        stats.battles_fought, stats.stars_captured, stats.stars_lost
+    ));
+    summary.push_str(&format!(
+        "Resources delivered: {:.0}{}\n",
+        stats.total_delivered(),
+        if delivered.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", delivered.join(", "))
+        }
+    ));
+    summary.push_str(&format!(
+        "Research points left: {:.1}\n\n[Enter] Keep playing",
+        player_resources.research_points
+    ));
+
+    text.sections[0].value = summary;
+    text.sections[0].style.color = color;
+    *visibility = Visibility::Visible;
+}
+
+// Drop visuals and selections that point at the state replaced by a load
+fn rebuild_after_load(
+    mut loaded_events: EventReader<GameLoaded>,
+    marker_query: Query<Entity, With<ConstellationMarker>>,
+    star_query: Query<(Entity, &Star)>,
+    mut game_state: ResMut<GameState>,
+    mut drag_state: ResMut<DragState>,
+    mut commands: Commands,
+) {
+    if loaded_events.read().count() == 0 {
+        return;
+    }
+
+    for marker in &marker_query {
+        commands.entity(marker).despawn();
+    }
+
+    if let Some(line_entity) = drag_state.current_line.take() {
+        commands.entity(line_entity).despawn();
+    }
+    drag_state.is_dragging = false;
+    drag_state.start_star = None;
+
+    if let Some(selected) = game_state.selected_star.take() {
+        if let Some(mut entity) = commands.get_entity(selected) {
+            entity.remove::<SelectedStar>();
+        }
+    }
+    commands.remove_resource::<SelectedConnection>();
+
+    for (entity, star) in &star_query {
+        if star.is_colonized {
+            commands.entity(entity).insert(StarBorder);
+        } else {
+            commands.entity(entity).remove::<StarBorder>();
+        }
+    }
+}
+
+// Give stars spawned by the simulation (e.g. from a save) their mesh
+fn attach_star_visuals(
+    star_query: Query<(Entity, &Star, &Transform), Without<Handle<ColorMaterial>>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, star, transform) in &star_query {
+        commands.entity(entity).insert(MaterialMesh2dBundle {
+            mesh: meshes.add(Circle::new(25.0)).into(),
+            material: materials.add(ColorMaterial::from(star.base_color)),
+            transform: *transform,
+            ..default()
+        });
+    }
+}
+
+// Give connections spawned by the simulation (e.g. from a save or a rival
+// empire) a line in the color of their owner, and border their stars
+fn attach_connection_visuals(
+    connection_query: Query<(Entity, &Connection), Without<ConnectionLine>>,
+    star_query: Query<&Star>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, connection) in &connection_query {
+        let owner = star_query
+            .get(connection.from)
+            .map_or(EmpireId::PLAYER, |star| star.empire);
+        for star in [connection.from, connection.to] {
+            if let Some(mut star) = commands.get_entity(star) {
+                star.insert(StarBorder);
+            }
+        }
+        commands.entity(entity).insert((
+            MaterialMesh2dBundle {
+                mesh: meshes.add(Rectangle::new(1.0, 1.0)).into(),
+                material: materials.add(ColorMaterial::from(owner.color())),
+                transform: Transform::from_xyz(0.0, 0.0, -1.0),
+                ..default()
+            },
+            ConnectionLine,
+        ));
+    }
+}
+
+// Zoom with the mouse wheel, keeping the world point under the cursor in place
+fn camera_zoom_system(
+    mut wheel_events: EventReader<MouseWheel>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut camera_q: Query<(
+        &Camera,
+        &GlobalTransform,
+        &mut Transform,
+        &mut OrthographicProjection,
+    )>,
+    settings: Res<CameraSettings>,
+) {
+    let scroll: f32 = wheel_events
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 100.0,
+        })
+        .sum();
+    if scroll == 0.0 {
+        return;
+    }
+
+    let Ok((camera, camera_global, mut camera_transform, mut projection)) =
+        camera_q.get_single_mut()
+    else {
+        return;
+    };
+
+    let old_scale = projection.scale;
+    let new_scale = (old_scale * (1.0 - settings.zoom_step).powf(scroll))
+        .clamp(settings.min_zoom, settings.max_zoom);
+    projection.scale = new_scale;
+
+    // Without a cursor, zoom around the screen center
+    let cursor_world = windows
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_global, cursor));
+    if let Some(cursor_world) = cursor_world {
+        let camera_pos = camera_transform.translation.truncate();
+        let offset = cursor_world - camera_pos;
+        let new_pos = cursor_world - offset * (new_scale / old_scale);
+        camera_transform.translation.x = new_pos.x;
+        camera_transform.translation.y = new_pos.y;
+    }
+}
+
+// Pan with middle-drag, WASD / arrow keys, or by moving the cursor to the window edge
+fn camera_pan_system(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    mut motion_events: EventReader<MouseMotion>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut camera_q: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+    settings: Res<CameraSettings>,
+    menu_state: Res<ConfigMenuState>,
+    time: Res<Time<Real>>,
+) {
+    let Ok((mut camera_transform, projection)) = camera_q.get_single_mut() else {
+        return;
+    };
+
+    // Screen-space movement, converted to world space by the zoom level below
+    let mut screen_delta = Vec2::ZERO;
+
+    if mouse_button.pressed(MouseButton::Middle) {
+        for motion in motion_events.read() {
+            screen_delta += Vec2::new(-motion.delta.x, motion.delta.y);
+        }
+    } else {
+        motion_events.clear();
+    }
+
+    let mut direction = Vec2::ZERO;
+    // WASD adjusts bloom while the config menu is open, so only arrows pan then
+    let wasd_enabled = !menu_state.visible;
+    if keyboard.pressed(KeyCode::ArrowLeft) || (wasd_enabled && keyboard.pressed(KeyCode::KeyA)) {
+        direction.x -= 1.0;
+    }
+    if keyboard.pressed(KeyCode::ArrowRight) || (wasd_enabled && keyboard.pressed(KeyCode::KeyD)) {
+        direction.x += 1.0;
+    }
+    if keyboard.pressed(KeyCode::ArrowDown) || (wasd_enabled && keyboard.pressed(KeyCode::KeyS)) {
+        direction.y -= 1.0;
+    }
+    if keyboard.pressed(KeyCode::ArrowUp) || (wasd_enabled && keyboard.pressed(KeyCode::KeyW)) {
+        direction.y += 1.0;
+    }
+
+    if let Ok(window) = windows.get_single() {
+        if window.focused && !mouse_button.pressed(MouseButton::Middle) {
+            if let Some(cursor) = window.cursor_position() {
+                if cursor.x < settings.edge_margin {
+                    direction.x -= 1.0;
+                } else if cursor.x > window.width() - settings.edge_margin {
+                    direction.x += 1.0;
+                }
+                // Cursor coordinates grow downwards
+                if cursor.y < settings.edge_margin {
+                    direction.y += 1.0;
+                } else if cursor.y > window.height() - settings.edge_margin {
+                    direction.y -= 1.0;
+                }
+            }
+        }
+    }
+
+    screen_delta += direction.normalize_or_zero() * settings.pan_speed * time.delta_seconds();
+
+    let world_delta = screen_delta * projection.scale;
+    camera_transform.translation.x += world_delta.x;
+    camera_transform.translation.y += world_delta.y;
+}
+
+// Center the camera on the selected star with F
+fn camera_focus_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    game_state: Res<GameState>,
+    star_query: Query<&Transform, (With<Star>, Without<Camera>)>,
+    mut camera_q: Query<&mut Transform, With<Camera>>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyF) {
+        return;
+    }
+
+    let Some(selected) = game_state.selected_star else {
+        return;
+    };
+
+    if let (Ok(star_transform), Ok(mut camera_transform)) =
+        (star_query.get(selected), camera_q.get_single_mut())
+    {
+        camera_transform.translation.x = star_transform.translation.x;
+        camera_transform.translation.y = star_transform.translation.y;
+    }
+}
+
+fn format_costs(costs: &[(ResourceType, f32)]) -> String {
+    if costs.is_empty() {
+        return "free".to_string();
+    }
+    costs
+        .iter()
+        .map(|(resource_type, amount)| format!("{} {:.0}", resource_type.icon(), amount))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn log_entry_sections(entry: &LogEntry, style: &TextStyle) -> [TextSection; 2] {
+    let minutes = (entry.time / 60.0) as u32;
+    let seconds = entry.time as u32 % 60;
+    [
+        TextSection::new(
+            format!("[{:02}:{:02}] ", minutes, seconds),
+            TextStyle {
+                color: Color::srgb(0.6, 0.6, 0.6),
+                ..style.clone()
+            },
+        ),
+        TextSection::new(
+            format!("{}\n", entry.text),
+            TextStyle {
+                color: entry.category.color(),
+                ..style.clone()
+            },
+        ),
+    ]
+}
+
+// Age the toasts and show the ones still alive, newest last
+// Messages are stamped with game time, toasts fade in real time even while paused
+fn update_toasts(
+    time: Res<Time>,
+    real_time: Res<Time<Real>>,
+    mut notifications: ResMut<Notifications>,
+    mut toast_area: Query<&mut Text, With<ToastArea>>,
+) {
+    notifications.now = time.elapsed_seconds();
+    let dt = real_time.delta_seconds();
+    let had_toasts = !notifications.toasts.is_empty();
+    notifications.toasts.retain_mut(|(_, remaining)| {
+        *remaining -= dt;
+        *remaining > 0.0
+    });
+    if !had_toasts && !notifications.is_changed() {
+        return;
+    }
+
+    if let Ok(mut text) = toast_area.get_single_mut() {
+        let style = TextStyle {
+            font_size: 16.0,
+            ..default()
+        };
+        text.sections = notifications
+            .toasts
+            .iter()
+            .flat_map(|(index, _)| log_entry_sections(&notifications.log[*index], &style))
+            .collect();
+    }
+}
+
+// Turn simulation and input events into messages
+fn log_game_events(
+    mut notifications: ResMut<Notifications>,
+    star_query: Query<&Star>,
+    mut colonized: EventReader<StarColonized>,
+    mut created: EventReader<ConnectionCreated>,
+    mut removed: EventReader<ConnectionRemoved>,
+    mut refused: EventReader<ConnectionRefused>,
+    (mut depleted, mut evolved): (EventReader<StarDepleted>, EventReader<StarEvolved>),
+    mut upgraded: EventReader<UpgradeCompleted>,
+    mut formed: EventReader<ConstellationFormed>,
+    (mut produced, mut refined): (EventReader<UnitsProduced>, EventReader<GoodsRefined>),
+    mut battles: EventReader<BattleFought>,
+    mut captured: EventReader<StarCaptured>,
+    mut abandoned: EventReader<ColonyAbandoned>,
+    mut loaded: EventReader<GameLoaded>,
+    (mut ended, mut blocked): (EventReader<GameEnded>, EventReader<ZodiacVictoryBlocked>),
+) {
+    let name = |entity: Entity| {
+        star_query
+            .get(entity)
+            .map_or_else(|_| "Unknown star".to_string(), |star| star.name.clone())
+    };
+
+    for event in colonized.read() {
+        notifications.push(
+            MessageCategory::Network,
+            format!("Colonized {}", name(event.star)),
+        );
+    }
+    for event in created.read() {
+        notifications.log(
+            MessageCategory::Network,
+            format!("Connected {} to {}", name(event.from), name(event.to)),
+        );
+    }
+    for event in removed.read() {
+        notifications.push(
+            MessageCategory::Network,
+            format!(
+                "Removed connection {} to {}",
+                name(event.from),
+                name(event.to)
+            ),
+        );
+    }
+    for event in refused.read() {
+        notifications.push(MessageCategory::Network, event.reason.to_string());
+    }
+    for event in depleted.read() {
+        notifications.push(
+            MessageCategory::Economy,
+            format!("{} is depleted", name(event.star)),
+        );
+    }
+    for event in evolved.read() {
+        notifications.push(
+            MessageCategory::Economy,
+            format!("{} became a {}", name(event.star), event.evolution.name()),
+        );
+    }
+    for event in upgraded.read() {
+        notifications.push(
+            MessageCategory::Construction,
+            format!("{} reached level {}", name(event.star), event.level),
+        );
+    }
+    for event in formed.read() {
+        notifications.push(
+            MessageCategory::Constellation,
+            format!(
+                "{} constellation formed with {} stars: {}",
+                event.kind.name(),
+                event.stars.len(),
+                event.kind.bonus().describe()
+            ),
+        );
+    }
+    for event in produced.read() {
+        notifications.log(
+            MessageCategory::Economy,
+            format!(
+                "{} produced {} {}",
+                name(event.star),
+                event.count,
+                event.unit_type.name()
+            ),
+        );
+    }
+    for event in refined.read() {
+        notifications.log(
+            MessageCategory::Economy,
+            format!(
+                "{} refined {:.0} {}",
+                name(event.star),
+                event.amount,
+                event.resource_type.name()
+            ),
+        );
+    }
+    for event in battles.read() {
+        notifications.push(
+            MessageCategory::Combat,
+            format!(
+                "Battle at {}: {} lost {} warships, {} lost {}",
+                name(event.star),
+                event.attacker.name(),
+                event.attacker_losses,
+                event.defender.name(),
+                event.defender_losses
+            ),
+        );
+    }
+    for event in captured.read() {
+        let text = if event.to.is_player() && event.capital {
+            format!(
+                "Captured {}, the capital of the {}",
+                name(event.star),
+                event.from.name()
+            )
+        } else if event.to.is_player() {
+            format!(
+                "Captured {} from the {}",
+                name(event.star),
+                event.from.name()
+            )
+        } else if event.capital {
+            format!(
+                "The {} captured our home star {}",
+                event.to.name(),
+                name(event.star)
+            )
+        } else {
+            format!("The {} captured {}", event.to.name(), name(event.star))
+        };
+        notifications.push(MessageCategory::Combat, text);
+    }
+    for event in abandoned.read() {
+        notifications.push(
+            MessageCategory::Economy,
+            format!(
+                "{} was abandoned, its colonists ran out of life support",
+                name(event.star)
+            ),
+        );
+    }
+    for event in loaded.read() {
+        notifications.push(
+            MessageCategory::System,
+            format!("Loaded save slot {}", event.slot),
+        );
+    }
+    for event in ended.read() {
+        let result = if event.outcome.is_victory() {
+            "Victory"
+        } else {
+            "Defeat"
+        };
+        notifications.push(
+            MessageCategory::System,
+            format!("{}: {}", result, event.outcome.describe()),
+        );
+    }
+    for event in blocked.read() {
+        let text = match event.held_by {
+            Some((sign, empire)) => format!(
+                "The zodiac victory is out of reach while the {} hold {}",
+                empire.name(),
+                sign.name()
+            ),
+            None => "The zodiac victory can be reached again".to_string(),
+        };
+        notifications.push(MessageCategory::System, text);
+    }
+}
+
+// L toggles the message log, digits toggle its category filters while it is open
+// and Page Up/Page Down scroll through older messages
+fn message_log_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut open_panel: ResMut<OpenPanel>,
+    mut notifications: ResMut<Notifications>,
+    mut panel_query: Query<(&mut Text, &mut Visibility), With<MessageLogPanel>>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyL) {
+        *open_panel = if *open_panel == OpenPanel::MessageLog {
+            OpenPanel::None
+        } else {
+            OpenPanel::MessageLog
+        };
+    }
+
+    let Ok((mut text, mut visibility)) = panel_query.get_single_mut() else {
+        return;
+    };
+    if *open_panel != OpenPanel::MessageLog {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Visible;
+
+    let digit_keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+    ];
+    for (key, category) in digit_keys.iter().zip(MessageCategory::ALL) {
+        if keyboard.just_pressed(*key) && !notifications.hidden.remove(&category) {
+            notifications.hidden.insert(category);
+        }
+    }
+
+    let visible: Vec<&LogEntry> = notifications
+        .log
+        .iter()
+        .filter(|entry| !notifications.hidden.contains(&entry.category))
+        .collect();
+    let max_scroll = visible.len().saturating_sub(Notifications::LOG_LINES);
+    let mut scroll = notifications.scroll.min(max_scroll);
+    if keyboard.just_pressed(KeyCode::PageUp) {
+        scroll = (scroll + Notifications::LOG_LINES).min(max_scroll);
+    }
+    if keyboard.just_pressed(KeyCode::PageDown) {
+        scroll = scroll.saturating_sub(Notifications::LOG_LINES);
+    }
+
+    let style = TextStyle {
+        font_size: 14.0,
+        ..default()
+    };
+    let end = visible.len() - scroll;
+    let start = end.saturating_sub(Notifications::LOG_LINES);
+    let mut sections = vec![TextSection::new(
+        format!(
+            "=== MESSAGE LOG ({}/{}) === [L] Close [PgUp/PgDn] Scroll\n",
+            end,
+            visible.len()
+        ),
+        TextStyle {
+            color: Color::WHITE,
+            ..style.clone()
+        },
+    )];
+    for (index, category) in MessageCategory::ALL.iter().enumerate() {
+        let shown = !notifications.hidden.contains(category);
+        sections.push(TextSection::new(
+            format!(
+                "[{}] {} {}  ",
+                index + 1,
+                if shown { "☑" } else { "☐" },
+                category.name()
+            ),
+            TextStyle {
+                color: if shown {
+                    category.color()
+                } else {
+                    Color::srgb(0.4, 0.4, 0.4)
+                },
+                ..style.clone()
+            },
+        ));
+    }
+    sections.push(TextSection::new("\n", style.clone()));
+    for entry in &visible[start..end] {
+        sections.extend(log_entry_sections(entry, &style));
+    }
+    text.sections = sections;
+
+    if notifications.scroll != scroll {
+        notifications.scroll = scroll;
+    }
+}
+
+// T toggles the research panel, digits research the listed techs while it is open
+fn research_panel_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut open_panel: ResMut<OpenPanel>,
+    mut panel_query: Query<(&mut Text, &mut Visibility), With<ResearchPanel>>,
+    mut tech_tree: ResMut<TechTree>,
+    mut player_resources: ResMut<PlayerResources>,
+    mut notifications: ResMut<Notifications>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyT) {
+        *open_panel = if *open_panel == OpenPanel::Research {
+            OpenPanel::None
+        } else {
+            OpenPanel::Research
+        };
+    }
+
+    let Ok((mut text, mut visibility)) = panel_query.get_single_mut() else {
+        return;
+    };
+    if *open_panel != OpenPanel::Research {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    *visibility = Visibility::Visible;
+
+    let digit_keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+    ];
+    for (key, tech) in digit_keys.iter().zip(Tech::ALL) {
+        if keyboard.just_pressed(*key) {
+            match tech_tree.research(tech, &mut player_resources) {
+                Ok(()) => notifications.push(
+                    MessageCategory::Research,
+                    format!("Researched {}", tech.name()),
+                ),
+                Err(err) => notifications.push(MessageCategory::Research, err.to_string()),
+            }
+        }
+    }
+
+    let mut panel_text = format!(
+        "=== RESEARCH ({:.1} points) === [T] Close\n",
+        player_resources.research_points
+    );
+    for (index, tech) in Tech::ALL.iter().enumerate() {
+        let status = if tech_tree.is_unlocked(*tech) {
+            "DONE".to_string()
+        } else if let Err(err) = tech_tree.can_research(*tech) {
+            err.to_string()
+        } else {
+            format!("{:.0} pts", tech.cost())
+        };
+        panel_text.push_str(&format!(
+            "[{}] {} - {} ({})\n",
+            index + 1,
+            tech.name(),
+            tech.description(),
+            status
+        ));
+    }
+    text.sections[0].value = panel_text;
 }
@@ -0,0 +1,66 @@
+//! Launch options read from the command line and the config file
+
+use bevy::log::warn;
+use std::path::Path;
+
+// Config file read at startup for options not given on the command line
+pub const CONFIG_FILE: &str = "zodiakos.cfg";
+
+// `--key <value>` / `--key=<value>` arguments take precedence over `key = value`
+// lines in the config file
+#[derive(Debug, Clone, Default)]
+pub struct LaunchOptions {
+    args: Vec<String>,
+    config: Vec<(String, String)>,
+}
+
+impl LaunchOptions {
+    pub fn new(args: impl IntoIterator<Item = String>, config_contents: &str) -> Self {
+        let config = config_contents
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#'))
+            .filter_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                Some((key.trim().to_string(), value.trim().to_string()))
+            })
+            .collect();
+        Self {
+            args: args.into_iter().collect(),
+            config,
+        }
+    }
+
+    // Read the process arguments and the config file, which may be missing
+    pub fn load(args: impl IntoIterator<Item = String>, config: &Path) -> Self {
+        let contents = std::fs::read_to_string(config).unwrap_or_default();
+        Self::new(args, &contents)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let flag = format!("--{}", key);
+        let mut args = self.args.iter();
+        while let Some(arg) = args.next() {
+            if *arg == flag {
+                return args.next().map(String::as_str);
+            }
+            if let Some(value) = arg.strip_prefix(&flag).and_then(|v| v.strip_prefix('=')) {
+                return Some(value);
+            }
+        }
+
+        self.config
+            .iter()
+            .find(|(config_key, _)| config_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    // Invalid values are reported and treated as missing
+    pub fn parse<T: std::str::FromStr>(&self, key: &str) -> Option<T> {
+        let value = self.get(key)?;
+        let parsed = value.parse().ok();
+        if parsed.is_none() {
+            warn!("Ignoring invalid value {:?} for option {}", value, key);
+        }
+        parsed
+    }
+}
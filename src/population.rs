@@ -0,0 +1,185 @@
+//! Colonists living on colonized stars and the life support they consume
+//!
+//! Every colonist consumes LIFE_SUPPORT_PER_COLONIST of water, oxygen and food
+//! per second from its empire's stockpile. While the stockpile covers the
+//! demand, colonies grow toward the population their doctors sustain, and an
+//! empire holding RESERVE_SECONDS of every life support resource produces
+//! WELL_SUPPLIED_BONUS faster. Shortages slow production down to the share of
+//! the demand that was met and make colonies shrink. A colony that falls below
+//! ABANDON_POPULATION is abandoned and loses its connections, home stars hold
+//! on with their last colonists.
+
+use crate::connection::{sever_connections, Connection};
+use crate::constellation::ConstellationTracker;
+use crate::empire::{EmpireId, RivalEmpires};
+use crate::events::{ColonyAbandoned, ConnectionRemoved};
+use crate::goals::LIFE_SUPPORT;
+use crate::resources::PlayerResources;
+use crate::star::Star;
+use crate::units::unit_support;
+use crate::zodiac::ZodiacPerks;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+// Water, oxygen and food each colonist consumes per second
+pub const LIFE_SUPPORT_PER_COLONIST: f32 = 0.001;
+
+// Seconds of consumption an empire must keep in stock to count as well supplied
+pub const RESERVE_SECONDS: f32 = 300.0;
+
+// Production multiplier of a well supplied empire
+pub const WELL_SUPPLIED_BONUS: f32 = 1.25;
+
+// Fraction of the gap to the population capacity closed per second
+pub const POPULATION_GROWTH_RATE: f32 = 0.02;
+
+// Fraction of the colonists lost per second when none of their needs are met
+pub const POPULATION_DECLINE_RATE: f32 = 0.05;
+
+// Colonies below this population are abandoned
+pub const ABANDON_POPULATION: f32 = 5.0;
+
+// Take what `colonists` consume over `dt` seconds from `stockpile` and return the
+// share of their needs that was met, from 0 to 1
+// The production multiplier it gives is kept in `PlayerResources::life_support`
+pub fn consume_life_support(stockpile: &mut PlayerResources, colonists: f32, dt: f32) -> f32 {
+    let rate = colonists * LIFE_SUPPORT_PER_COLONIST;
+    let demand = rate * dt;
+    let mut supplied: f32 = 1.0;
+    let mut well_supplied = true;
+    for resource_type in LIFE_SUPPORT {
+        let available = stockpile.amount(resource_type);
+        let consumed = demand.min(available);
+        if consumed > 0.0 {
+            *stockpile.resources.entry(resource_type).or_insert(0.0) -= consumed;
+        }
+        if demand > 0.0 {
+            supplied = supplied.min(consumed / demand);
+        }
+        well_supplied &= available - consumed >= rate * RESERVE_SECONDS;
+    }
+
+    stockpile.life_support = if supplied >= 1.0 && well_supplied {
+        WELL_SUPPLIED_BONUS
+    } else {
+        supplied
+    };
+    supplied
+}
+
+// Feed every empire's colonists, then grow or shrink its colonies and abandon
+// the ones that emptied out
+pub fn sustain_colonies(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut star_query: Query<(Entity, &mut Star)>,
+    connection_query: Query<(Entity, &Connection)>,
+    mut player_resources: ResMut<PlayerResources>,
+    mut rivals: ResMut<RivalEmpires>,
+    constellation_tracker: Res<ConstellationTracker>,
+    mut abandoned_events: EventWriter<ColonyAbandoned>,
+    mut removed_events: EventWriter<ConnectionRemoved>,
+) {
+    let dt = time.delta_seconds();
+    // Doctors let their own star and connected stars sustain more colonists
+    let support = {
+        let stars: Vec<(Entity, &Star)> = star_query.iter().collect();
+        unit_support(&stars)
+    };
+
+    let mut colonists: HashMap<EmpireId, f32> = HashMap::new();
+    for (_, star) in &star_query {
+        if star.is_colonized {
+            *colonists.entry(star.empire).or_insert(0.0) += star.population;
+        }
+    }
+    let supplied: HashMap<EmpireId, f32> = colonists
+        .into_iter()
+        .filter_map(|(empire, colonists)| {
+            let stockpile = rivals.stockpile(&mut player_resources, empire)?;
+            Some((empire, consume_life_support(stockpile, colonists, dt)))
+        })
+        .collect();
+
+    let mut abandoned = Vec::new();
+    for (entity, mut star) in &mut star_query {
+        if !star.is_colonized {
+            continue;
+        }
+        let supplied = supplied.get(&star.empire).copied().unwrap_or(1.0);
+        if supplied >= 1.0 {
+            let perks = ZodiacPerks::new(&constellation_tracker, star.empire);
+            let capacity = support[&entity].population_capacity() * perks.population_capacity;
+            let rate = POPULATION_GROWTH_RATE * perks.population_growth;
+            let growth = (capacity - star.population) * (rate * dt).min(1.0);
+            star.population += growth;
+            continue;
+        }
+
+        let decline = (POPULATION_DECLINE_RATE * (1.0 - supplied) * dt).min(1.0);
+        star.population -= star.population * decline;
+        if star.population >= ABANDON_POPULATION {
+            continue;
+        }
+        if star.is_home_star {
+            star.population = ABANDON_POPULATION;
+        } else {
+            info!("{} was abandoned", star.name);
+            abandoned.push((entity, star.empire));
+            star.abandon();
+        }
+    }
+
+    for (entity, empire) in abandoned {
+        sever_connections(
+            entity,
+            &connection_query,
+            &mut star_query,
+            &mut commands,
+            &mut removed_events,
+        );
+        if empire.is_player() {
+            abandoned_events.send(ColonyAbandoned { star: entity });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::ResourceType;
+
+    #[test]
+    fn full_stock_feeds_colonists_with_a_bonus() {
+        let mut stockpile = PlayerResources::default();
+        for resource_type in LIFE_SUPPORT {
+            stockpile.resources.insert(resource_type, 1000.0);
+        }
+        assert_eq!(consume_life_support(&mut stockpile, 100.0, 1.0), 1.0);
+        assert_eq!(stockpile.life_support, WELL_SUPPLIED_BONUS);
+        assert!((stockpile.amount(ResourceType::Water) - 999.9).abs() < 1e-3);
+    }
+
+    #[test]
+    fn small_reserve_feeds_colonists_without_a_bonus() {
+        let mut stockpile = PlayerResources::default();
+        for resource_type in LIFE_SUPPORT {
+            stockpile.resources.insert(resource_type, 10.0);
+        }
+        assert_eq!(consume_life_support(&mut stockpile, 100.0, 1.0), 1.0);
+        assert_eq!(stockpile.life_support, 1.0);
+    }
+
+    #[test]
+    fn scarcest_resource_limits_the_supply() {
+        let mut stockpile = PlayerResources::default();
+        for resource_type in LIFE_SUPPORT {
+            stockpile.resources.insert(resource_type, 1000.0);
+        }
+        stockpile.resources.insert(ResourceType::Oxygen, 0.05);
+        let supplied = consume_life_support(&mut stockpile, 100.0, 1.0);
+        assert!((supplied - 0.5).abs() < 1e-3);
+        assert_eq!(stockpile.life_support, supplied);
+        assert_eq!(stockpile.amount(ResourceType::Oxygen), 0.0);
+    }
+}
@@ -0,0 +1,146 @@
+//! Refining raw resources into goods
+//!
+//! Industrial stars work through RECIPES: at every collection they take the
+//! inputs of one batch from their empire's stockpile and ship the refined goods
+//! to the storage hubs as cargo, higher levels refining bigger batches. Each
+//! batch goes to the good the empire holds the least of among the recipes it
+//! can afford and store. Upgrades and most units are paid in goods, so an
+//! empire needs industry to grow past its first stars.
+
+use crate::resources::{PlayerResources, ResourceType};
+use std::collections::HashMap;
+
+// Room for every good in each storage hub, on top of its own capacity
+pub const GOODS_STORAGE: f32 = 200.0;
+
+// One batch of a good and the raw resources it is refined from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Recipe {
+    pub output: ResourceType,
+    pub amount: f32,
+    pub inputs: &'static [(ResourceType, f32)],
+}
+
+pub const RECIPES: [Recipe; 3] = [
+    Recipe {
+        output: ResourceType::Alloy,
+        amount: 5.0,
+        inputs: &[(ResourceType::Iron, 6.0), (ResourceType::Copper, 3.0)],
+    },
+    Recipe {
+        output: ResourceType::Circuits,
+        amount: 3.0,
+        inputs: &[(ResourceType::Silicon, 4.0), (ResourceType::Copper, 2.0)],
+    },
+    Recipe {
+        output: ResourceType::FuelRods,
+        amount: 2.0,
+        inputs: &[(ResourceType::Uranium, 3.0)],
+    },
+];
+
+impl Recipe {
+    pub fn for_output(output: ResourceType) -> Option<&'static Recipe> {
+        RECIPES.iter().find(|recipe| recipe.output == output)
+    }
+
+    // Inputs of one batch at `level`, every level adds a batch
+    pub fn batch_inputs(&self, level: u8) -> Vec<(ResourceType, f32)> {
+        self.inputs
+            .iter()
+            .map(|&(resource_type, amount)| (resource_type, amount * level as f32))
+            .collect()
+    }
+
+    pub fn batch_output(&self, level: u8) -> f32 {
+        self.amount * level as f32
+    }
+
+    pub fn describe(&self) -> String {
+        let inputs: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|(resource_type, amount)| format!("{:.0} {}", amount, resource_type.name()))
+            .collect();
+        format!(
+            "{} -> {:.0} {}",
+            inputs.join(" + "),
+            self.amount,
+            self.output.name()
+        )
+    }
+}
+
+// Recipe an industrial star of `level` refines next, if any is affordable and
+// its good still fits in storage once the cargo `in_transit` arrives
+pub fn choose_recipe(
+    stockpile: &PlayerResources,
+    in_transit: &HashMap<ResourceType, f32>,
+    level: u8,
+) -> Option<&'static Recipe> {
+    let held = |resource_type: ResourceType| {
+        stockpile.amount(resource_type) + in_transit.get(&resource_type).copied().unwrap_or(0.0)
+    };
+    RECIPES
+        .iter()
+        .filter(|recipe| stockpile.can_afford(&recipe.batch_inputs(level)))
+        .filter(|recipe| {
+            stockpile.capacity(recipe.output) - held(recipe.output) >= recipe.batch_output(level)
+        })
+        .min_by(|a, b| held(a.output).total_cmp(&held(b.output)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stockpile(resources: &[(ResourceType, f32)]) -> PlayerResources {
+        PlayerResources {
+            resources: resources.iter().copied().collect(),
+            capacity: ResourceType::ALL.map(|r| (r, GOODS_STORAGE)).into(),
+            ..PlayerResources::default()
+        }
+    }
+
+    #[test]
+    fn every_good_has_one_recipe_from_raw_resources() {
+        for good in ResourceType::GOODS {
+            let recipe = Recipe::for_output(good).unwrap();
+            assert!(recipe
+                .inputs
+                .iter()
+                .all(|(input, _)| ResourceType::RAW.contains(input)));
+        }
+    }
+
+    #[test]
+    fn scarcest_affordable_good_is_refined_first() {
+        let mut stockpile = stockpile(&[
+            (ResourceType::Iron, 100.0),
+            (ResourceType::Copper, 100.0),
+            (ResourceType::Silicon, 100.0),
+            (ResourceType::Alloy, 10.0),
+        ]);
+        let in_transit = HashMap::new();
+        let recipe = choose_recipe(&stockpile, &in_transit, 1).unwrap();
+        assert_eq!(recipe.output, ResourceType::Circuits);
+
+        // Circuits on their way count as held
+        let in_transit = HashMap::from([(ResourceType::Circuits, 20.0)]);
+        let recipe = choose_recipe(&stockpile, &in_transit, 1).unwrap();
+        assert_eq!(recipe.output, ResourceType::Alloy);
+
+        // Without copper only fuel rods could be refined, and there is no uranium
+        stockpile.resources.insert(ResourceType::Copper, 0.0);
+        assert_eq!(choose_recipe(&stockpile, &in_transit, 1), None);
+    }
+
+    #[test]
+    fn full_storage_stops_refining() {
+        let stockpile = stockpile(&[
+            (ResourceType::Uranium, 100.0),
+            (ResourceType::FuelRods, GOODS_STORAGE - 1.0),
+        ]);
+        assert_eq!(choose_recipe(&stockpile, &HashMap::new(), 1), None);
+    }
+}
@@ -0,0 +1,196 @@
+//! Technology tree unlocked with research points from scientists
+
+use crate::economy::production_rate_modifier_from_distance;
+use crate::resources::PlayerResources;
+use crate::star::{max_connections_for_level, Specialization};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Tech {
+    SupplyDepots,         // Supply routes count one hop shorter
+    HyperspaceLanes,      // Supply routes count another hop shorter
+    ExtendedDocking,      // +1 outbound connection per star
+    SubspaceRelays,       // +2 more outbound connections per star
+    PrefabricatedModules, // Upgrades take 25% less time
+    AutomatedShipyards,   // Upgrades take another 25% less time
+    StellarCartography,   // Constellations give +0.5x production
+    ZodiacHarmonics,      // Constellations give another +1x production
+}
+
+impl Tech {
+    pub const ALL: [Tech; 8] = [
+        Tech::SupplyDepots,
+        Tech::HyperspaceLanes,
+        Tech::ExtendedDocking,
+        Tech::SubspaceRelays,
+        Tech::PrefabricatedModules,
+        Tech::AutomatedShipyards,
+        Tech::StellarCartography,
+        Tech::ZodiacHarmonics,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tech::SupplyDepots => "Supply Depots",
+            Tech::HyperspaceLanes => "Hyperspace Lanes",
+            Tech::ExtendedDocking => "Extended Docking",
+            Tech::SubspaceRelays => "Subspace Relays",
+            Tech::PrefabricatedModules => "Prefabricated Modules",
+            Tech::AutomatedShipyards => "Automated Shipyards",
+            Tech::StellarCartography => "Stellar Cartography",
+            Tech::ZodiacHarmonics => "Zodiac Harmonics",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Tech::SupplyDepots | Tech::HyperspaceLanes => "Supply routes count 1 hop shorter",
+            Tech::ExtendedDocking => "+1 outbound connection per star",
+            Tech::SubspaceRelays => "+2 outbound connections per star",
+            Tech::PrefabricatedModules | Tech::AutomatedShipyards => "Upgrades take 25% less time",
+            Tech::StellarCartography => "Production constellations +0.5x",
+            Tech::ZodiacHarmonics => "Production constellations +1x",
+        }
+    }
+
+    // Research points needed to unlock
+    pub fn cost(&self) -> f32 {
+        match self {
+            Tech::SupplyDepots => 20.0,
+            Tech::HyperspaceLanes => 60.0,
+            Tech::ExtendedDocking => 25.0,
+            Tech::SubspaceRelays => 70.0,
+            Tech::PrefabricatedModules => 30.0,
+            Tech::AutomatedShipyards => 80.0,
+            Tech::StellarCartography => 40.0,
+            Tech::ZodiacHarmonics => 100.0,
+        }
+    }
+
+    pub fn prerequisite(&self) -> Option<Tech> {
+        match self {
+            Tech::HyperspaceLanes => Some(Tech::SupplyDepots),
+            Tech::SubspaceRelays => Some(Tech::ExtendedDocking),
+            Tech::AutomatedShipyards => Some(Tech::PrefabricatedModules),
+            Tech::ZodiacHarmonics => Some(Tech::StellarCartography),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResearchError {
+    AlreadyUnlocked(Tech),
+    MissingPrerequisite(Tech),
+    NotEnoughPoints { needed: f32, available: f32 },
+}
+
+impl fmt::Display for ResearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResearchError::AlreadyUnlocked(tech) => {
+                write!(f, "{} is already researched", tech.name())
+            }
+            ResearchError::MissingPrerequisite(tech) => write!(f, "Requires {} first", tech.name()),
+            ResearchError::NotEnoughPoints { needed, available } => write!(
+                f,
+                "Not enough research points ({:.1}/{:.0})",
+                available, needed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ResearchError {}
+
+#[derive(Resource, Debug, Clone, Default)]
+pub struct TechTree {
+    pub unlocked: HashSet<Tech>,
+}
+
+impl TechTree {
+    pub fn is_unlocked(&self, tech: Tech) -> bool {
+        self.unlocked.contains(&tech)
+    }
+
+    pub fn can_research(&self, tech: Tech) -> Result<(), ResearchError> {
+        if self.is_unlocked(tech) {
+            return Err(ResearchError::AlreadyUnlocked(tech));
+        }
+        match tech.prerequisite() {
+            Some(required) if !self.is_unlocked(required) => {
+                Err(ResearchError::MissingPrerequisite(required))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Spend research points to unlock `tech`
+    pub fn research(
+        &mut self,
+        tech: Tech,
+        player_resources: &mut PlayerResources,
+    ) -> Result<(), ResearchError> {
+        self.can_research(tech)?;
+        if player_resources.research_points < tech.cost() {
+            return Err(ResearchError::NotEnoughPoints {
+                needed: tech.cost(),
+                available: player_resources.research_points,
+            });
+        }
+        player_resources.research_points -= tech.cost();
+        self.unlocked.insert(tech);
+        Ok(())
+    }
+
+    fn count(&self, techs: &[Tech]) -> u32 {
+        techs.iter().filter(|tech| self.is_unlocked(**tech)).count() as u32
+    }
+
+    // `production_rate_modifier_from_distance` with shorter routes from logistics techs
+    // A star that is not a hub itself always stays at least one hop away
+    pub fn route_efficiency(&self, route_distance: Option<u32>) -> f32 {
+        let reduction = self.count(&[Tech::SupplyDepots, Tech::HyperspaceLanes]);
+        let effective = route_distance.map(|hops| match hops {
+            0 => 0,
+            _ => hops.saturating_sub(reduction).max(1),
+        });
+        production_rate_modifier_from_distance(effective)
+    }
+
+    // `max_connections_for_level` plus connections from docking techs
+    pub fn max_connections(&self, level: u8) -> u32 {
+        let mut extra = 0;
+        if self.is_unlocked(Tech::ExtendedDocking) {
+            extra += 1;
+        }
+        if self.is_unlocked(Tech::SubspaceRelays) {
+            extra += 2;
+        }
+        max_connections_for_level(level) + extra
+    }
+
+    // `Specialization::upgrade_time` shortened by construction techs
+    pub fn upgrade_time(&self, spec: Specialization, level: u8) -> f32 {
+        let faster = self.count(&[Tech::PrefabricatedModules, Tech::AutomatedShipyards]);
+        spec.upgrade_time(level) * 0.75f32.powi(faster as i32)
+    }
+
+    // Raise the multiplier from `check_constellation_bonuses` for stars in a constellation
+    pub fn constellation_bonus(&self, base_bonus: f32) -> f32 {
+        if base_bonus <= 1.0 {
+            return base_bonus;
+        }
+        let mut bonus = base_bonus;
+        if self.is_unlocked(Tech::StellarCartography) {
+            bonus += 0.5;
+        }
+        if self.is_unlocked(Tech::ZodiacHarmonics) {
+            bonus += 1.0;
+        }
+        bonus
+    }
+}
@@ -0,0 +1,194 @@
+//! Resource types and the player's stockpile
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Resource types in the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ResourceType {
+    // Basic life resources
+    Water,  // 💧
+    Oxygen, // 🌬️
+    Food,   // 🌱
+
+    // Construction minerals
+    Iron,    // 🪨
+    Copper,  // ⚡
+    Silicon, // 💻
+
+    // Energy resources
+    Uranium,       // ☢️
+    Helium3,       // 🔋
+    EnergyCrystal, // ✨
+
+    // Goods refined from raw resources by industrial stars, see `refining`
+    Alloy,    // 🔩
+    Circuits, // 🔌
+    FuelRods, // 🧪
+}
+
+impl ResourceType {
+    pub const ALL: [ResourceType; 12] = [
+        ResourceType::Water,
+        ResourceType::Oxygen,
+        ResourceType::Food,
+        ResourceType::Iron,
+        ResourceType::Copper,
+        ResourceType::Silicon,
+        ResourceType::Uranium,
+        ResourceType::Helium3,
+        ResourceType::EnergyCrystal,
+        ResourceType::Alloy,
+        ResourceType::Circuits,
+        ResourceType::FuelRods,
+    ];
+
+    // Resources found on stars
+    pub const RAW: [ResourceType; 9] = [
+        ResourceType::Water,
+        ResourceType::Oxygen,
+        ResourceType::Food,
+        ResourceType::Iron,
+        ResourceType::Copper,
+        ResourceType::Silicon,
+        ResourceType::Uranium,
+        ResourceType::Helium3,
+        ResourceType::EnergyCrystal,
+    ];
+
+    pub const GOODS: [ResourceType; 3] = [
+        ResourceType::Alloy,
+        ResourceType::Circuits,
+        ResourceType::FuelRods,
+    ];
+
+    pub fn color(&self) -> Color {
+        match self {
+            ResourceType::Water => Color::srgb(0.0, 1.0, 1.0),
+            ResourceType::Oxygen => Color::srgb(0.7, 0.9, 1.0),
+            ResourceType::Food => Color::srgb(0.0, 1.0, 0.0),
+            ResourceType::Iron => Color::srgb(0.5, 0.5, 0.6),
+            ResourceType::Copper => Color::srgb(0.72, 0.45, 0.20),
+            ResourceType::Silicon => Color::srgb(0.8, 0.8, 0.9),
+            ResourceType::Uranium => Color::srgb(0.0, 1.0, 0.0),
+            ResourceType::Helium3 => Color::srgb(1.0, 0.8, 0.0),
+            ResourceType::EnergyCrystal => Color::srgb(1.0, 0.0, 1.0),
+            ResourceType::Alloy => Color::srgb(0.75, 0.75, 0.8),
+            ResourceType::Circuits => Color::srgb(0.2, 0.8, 0.4),
+            ResourceType::FuelRods => Color::srgb(0.6, 1.0, 0.2),
+        }
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            ResourceType::Water => "💧",
+            ResourceType::Oxygen => "🌬️",
+            ResourceType::Food => "🌱",
+            ResourceType::Iron => "🪨",
+            ResourceType::Copper => "⚡",
+            ResourceType::Silicon => "💻",
+            ResourceType::Uranium => "☢️",
+            ResourceType::Helium3 => "🔋",
+            ResourceType::EnergyCrystal => "✨",
+            ResourceType::Alloy => "🔩",
+            ResourceType::Circuits => "🔌",
+            ResourceType::FuelRods => "🧪",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResourceType::Water => "Water",
+            ResourceType::Oxygen => "Oxygen",
+            ResourceType::Food => "Food",
+            ResourceType::Iron => "Iron",
+            ResourceType::Copper => "Copper",
+            ResourceType::Silicon => "Silicon",
+            ResourceType::Uranium => "Uranium",
+            ResourceType::Helium3 => "Helium-3",
+            ResourceType::EnergyCrystal => "Energy Crystal",
+            ResourceType::Alloy => "Alloy",
+            ResourceType::Circuits => "Circuits",
+            ResourceType::FuelRods => "Fuel Rods",
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct PlayerResources {
+    pub resources: HashMap<ResourceType, f32>,
+    pub capacity: HashMap<ResourceType, f32>, // Sum of all storage hub capacities
+    pub research_points: f32,                 // Generated by scientists
+    pub life_support: f32,                    // Production multiplier, see `population`
+}
+
+impl PlayerResources {
+    pub fn amount(&self, resource_type: ResourceType) -> f32 {
+        *self.resources.get(&resource_type).unwrap_or(&0.0)
+    }
+
+    pub fn capacity(&self, resource_type: ResourceType) -> f32 {
+        *self.capacity.get(&resource_type).unwrap_or(&0.0)
+    }
+
+    // Room left before the storage hubs are full
+    pub fn free_space(&self, resource_type: ResourceType) -> f32 {
+        (self.capacity(resource_type) - self.amount(resource_type)).max(0.0)
+    }
+
+    // Resources still missing to pay `costs`, empty when affordable
+    pub fn missing(&self, costs: &[(ResourceType, f32)]) -> Vec<(ResourceType, f32)> {
+        costs
+            .iter()
+            .filter(|(resource_type, cost)| self.amount(*resource_type) < *cost)
+            .map(|(resource_type, cost)| (*resource_type, cost - self.amount(*resource_type)))
+            .collect()
+    }
+
+    pub fn can_afford(&self, costs: &[(ResourceType, f32)]) -> bool {
+        self.missing(costs).is_empty()
+    }
+
+    // Pay `costs` in full, or nothing if they are not affordable
+    pub fn pay(&mut self, costs: &[(ResourceType, f32)]) -> bool {
+        if !self.can_afford(costs) {
+            return false;
+        }
+        for (resource_type, cost) in costs {
+            *self.resources.entry(*resource_type).or_insert(0.0) -= cost;
+        }
+        true
+    }
+
+    // Store as much as fits and return the amount actually stored
+    pub fn store(&mut self, resource_type: ResourceType, amount: f32) -> f32 {
+        let stored = amount.min(self.free_space(resource_type));
+        if stored > 0.0 {
+            *self.resources.entry(resource_type).or_insert(0.0) += stored;
+        }
+        stored
+    }
+}
+
+impl Default for PlayerResources {
+    fn default() -> Self {
+        let mut resources = HashMap::new();
+        // Start with a small amount of each resource
+        resources.insert(ResourceType::Water, 50.0);
+        resources.insert(ResourceType::Oxygen, 30.0);
+        resources.insert(ResourceType::Food, 40.0);
+        resources.insert(ResourceType::Iron, 20.0);
+        resources.insert(ResourceType::Copper, 15.0);
+        resources.insert(ResourceType::Silicon, 10.0);
+        resources.insert(ResourceType::Uranium, 5.0);
+        resources.insert(ResourceType::Helium3, 2.0);
+        resources.insert(ResourceType::EnergyCrystal, 1.0);
+        Self {
+            resources,
+            capacity: HashMap::new(),
+            research_points: 0.0,
+            life_support: 1.0,
+        }
+    }
+}
@@ -0,0 +1,620 @@
+//! Saving and loading the full game state to disk
+//!
+//! Entities are not stable between runs, so every `Entity` reference (star
+//! connections, connection endpoints, constellation members) is written as the
+//! stable `Star::id` and remapped to the live entities on load. Rival empires
+//! keep the strategies they were given, only their stockpiles are saved.
+
+use crate::cargo::{CargoLeg, CargoPacket};
+use crate::combat::{Fleet, Mission};
+use crate::connection::Connection;
+use crate::constellation::{Constellation, ConstellationKind, ConstellationTracker};
+use crate::empire::{EmpireId, ExpansionStrategy, RivalEmpire, RivalEmpires};
+use crate::galaxy::GalaxySeed;
+use crate::goals::SessionStats;
+use crate::lifecycle::StellarAge;
+use crate::research::{Tech, TechTree};
+use crate::resources::{PlayerResources, ResourceType};
+use crate::star::{BuildingState, Specialization, Star, UnitType};
+use crate::zodiac::ZodiacSign;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Bump whenever the save layout changes so old files are refused instead of misread
+pub const SAVE_VERSION: u32 = 13;
+
+pub const SAVE_SLOTS: u8 = 4;
+
+// Where save files are written
+#[derive(Resource, Debug, Clone)]
+pub struct SaveSettings {
+    pub directory: PathBuf,
+}
+
+impl Default for SaveSettings {
+    fn default() -> Self {
+        Self {
+            directory: PathBuf::from("saves"),
+        }
+    }
+}
+
+impl SaveSettings {
+    pub fn slot_path(&self, slot: u8) -> PathBuf {
+        self.directory.join(format!("slot_{}.ron", slot))
+    }
+}
+
+// Ask the simulation to write the current state to a slot
+#[derive(Event, Debug, Clone, Copy)]
+pub struct SaveRequest {
+    pub slot: u8,
+}
+
+// Ask the simulation to replace the current state with a slot
+#[derive(Event, Debug, Clone, Copy)]
+pub struct LoadRequest {
+    pub slot: u8,
+}
+
+// Sent once a save has been applied, so presentation code can rebuild its visuals
+#[derive(Event, Debug, Clone, Copy)]
+pub struct GameLoaded {
+    pub slot: u8,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Format(String),
+    UnsupportedVersion(u32),
+    UnknownStar(usize),
+    DuplicateStar(usize),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "I/O error: {}", err),
+            SaveError::Format(err) => write!(f, "invalid save file: {}", err),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "save version {} is not supported (expected {})",
+                version, SAVE_VERSION
+            ),
+            SaveError::UnknownStar(id) => write!(f, "save references unknown star {}", id),
+            SaveError::DuplicateStar(id) => write!(f, "save has more than one star {}", id),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(err: std::io::Error) -> Self {
+        SaveError::Io(err)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    pub player_resources: HashMap<ResourceType, f32>,
+    pub research_points: f32,
+    pub unlocked_techs: Vec<Tech>,
+    pub stars: Vec<SavedStar>,
+    pub connections: Vec<SavedConnection>,
+    pub next_constellation_id: u32,
+    pub constellations: Vec<SavedConstellation>,
+    pub cargo: Vec<SavedCargo>,
+    pub fleets: Vec<SavedFleet>,
+    pub stats: SessionStats,
+    pub seed: u64, // Galaxy seed, stellar evolution keeps rolling from it
+    pub stellar_age: f32,
+    pub rivals: Vec<SavedRival>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedStar {
+    pub id: usize,
+    pub position: [f32; 3],
+    pub name: String,
+    pub resources: HashMap<ResourceType, f32>,
+    pub max_resources: HashMap<ResourceType, f32>,
+    pub production_rate: f32,
+    pub is_colonized: bool,
+    pub is_home_star: bool,
+    pub empire: EmpireId,
+    pub specialization: Specialization,
+    pub specialization_level: u8,
+    pub units: HashMap<UnitType, u32>,
+    pub building_state: BuildingState,
+    pub connections_from: Vec<usize>,
+    pub connections_to: Vec<usize>,
+    pub base_color: [f32; 4],
+    pub storage_capacity: HashMap<ResourceType, f32>,
+    pub is_storage_hub: bool,
+    pub population: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedConnection {
+    pub from: usize,
+    pub to: usize,
+    pub timer_elapsed: f32,
+    pub is_collecting: bool,
+    pub creation_time: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedConstellation {
+    pub id: u32,
+    pub stars: Vec<usize>,
+    pub color: [f32; 4],
+    pub kind: ConstellationKind,
+    pub zodiac: Option<ZodiacSign>,
+    pub empire: EmpireId,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedCargo {
+    pub resource_type: ResourceType,
+    pub amount: f32,
+    pub at: usize,
+    pub leg: Option<SavedCargoLeg>,
+    pub empire: EmpireId,
+}
+
+// Connections are rebuilt on load, so a leg is stored by its destination star
+// and matched to the connection between `at` and `to`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedCargoLeg {
+    pub to: usize,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+// Like cargo legs, a fleet is matched to the connection between its two stars on load
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedFleet {
+    pub empire: EmpireId,
+    pub ships: u32,
+    pub mission: Mission,
+    pub from: usize,
+    pub target: usize,
+    pub elapsed: f32,
+    pub duration: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedRival {
+    pub id: EmpireId,
+    pub resources: HashMap<ResourceType, f32>,
+    pub research_points: f32,
+}
+
+fn star_ids(world: &mut World) -> HashMap<Entity, usize> {
+    world
+        .query::<(Entity, &Star)>()
+        .iter(world)
+        .map(|(entity, star)| (entity, star.id))
+        .collect()
+}
+
+fn map_ids(entities: &[Entity], ids: &HashMap<Entity, usize>) -> Vec<usize> {
+    entities
+        .iter()
+        .filter_map(|entity| ids.get(entity).copied())
+        .collect()
+}
+
+impl SaveGame {
+    // Snapshot everything the simulation owns
+    pub fn capture(world: &mut World) -> Self {
+        let ids = star_ids(world);
+
+        let mut stars: Vec<SavedStar> = world
+            .query::<(&Star, Option<&Transform>)>()
+            .iter(world)
+            .map(|(star, transform)| SavedStar {
+                id: star.id,
+                position: transform.map_or([0.0; 3], |t| t.translation.to_array()),
+                name: star.name.clone(),
+                resources: star.resources.clone(),
+                max_resources: star.max_resources.clone(),
+                production_rate: star.production_rate,
+                is_colonized: star.is_colonized,
+                is_home_star: star.is_home_star,
+                empire: star.empire,
+                specialization: star.specialization,
+                specialization_level: star.specialization_level,
+                units: star.units.clone(),
+                building_state: star.building_state.clone(),
+                connections_from: map_ids(&star.connections_from, &ids),
+                connections_to: map_ids(&star.connections_to, &ids),
+                base_color: star.base_color.to_srgba().to_f32_array(),
+                storage_capacity: star.storage_capacity.clone(),
+                is_storage_hub: star.is_storage_hub,
+                population: star.population,
+            })
+            .collect();
+        stars.sort_by_key(|star| star.id);
+
+        let connections = world
+            .query::<&Connection>()
+            .iter(world)
+            .filter_map(|connection| {
+                Some(SavedConnection {
+                    from: *ids.get(&connection.from)?,
+                    to: *ids.get(&connection.to)?,
+                    timer_elapsed: connection.collection_timer.elapsed_secs(),
+                    is_collecting: connection.is_collecting,
+                    creation_time: connection.creation_time,
+                })
+            })
+            .collect();
+
+        let cargo = world
+            .query::<&CargoPacket>()
+            .iter(world)
+            .filter_map(|packet| {
+                let leg = match packet.leg {
+                    Some(leg) => Some(SavedCargoLeg {
+                        to: *ids.get(&leg.to)?,
+                        elapsed: leg.elapsed,
+                        duration: leg.duration,
+                    }),
+                    None => None,
+                };
+                Some(SavedCargo {
+                    resource_type: packet.resource_type,
+                    amount: packet.amount,
+                    at: *ids.get(&packet.at)?,
+                    leg,
+                    empire: packet.empire,
+                })
+            })
+            .collect();
+
+        let fleets = world
+            .query::<&Fleet>()
+            .iter(world)
+            .filter_map(|fleet| {
+                Some(SavedFleet {
+                    empire: fleet.empire,
+                    ships: fleet.ships,
+                    mission: fleet.mission,
+                    from: *ids.get(&fleet.from)?,
+                    target: *ids.get(&fleet.target)?,
+                    elapsed: fleet.elapsed,
+                    duration: fleet.duration,
+                })
+            })
+            .collect();
+
+        let tracker = world.resource::<ConstellationTracker>();
+        let constellations = tracker
+            .constellations
+            .iter()
+            .map(|constellation| SavedConstellation {
+                id: constellation.id,
+                stars: map_ids(&constellation.stars, &ids),
+                color: constellation.color.to_srgba().to_f32_array(),
+                kind: constellation.kind,
+                zodiac: constellation.zodiac,
+                empire: constellation.empire,
+            })
+            .collect();
+
+        let mut unlocked_techs: Vec<Tech> = world
+            .resource::<TechTree>()
+            .unlocked
+            .iter()
+            .copied()
+            .collect();
+        unlocked_techs.sort_by_key(|tech| Tech::ALL.iter().position(|t| t == tech));
+
+        let rivals = world
+            .resource::<RivalEmpires>()
+            .rivals
+            .iter()
+            .map(|rival| SavedRival {
+                id: rival.id,
+                resources: rival.resources.resources.clone(),
+                research_points: rival.resources.research_points,
+            })
+            .collect();
+
+        Self {
+            version: SAVE_VERSION,
+            player_resources: world.resource::<PlayerResources>().resources.clone(),
+            research_points: world.resource::<PlayerResources>().research_points,
+            unlocked_techs,
+            stars,
+            connections,
+            next_constellation_id: tracker.next_id,
+            constellations,
+            cargo,
+            fleets,
+            stats: world.resource::<SessionStats>().clone(),
+            seed: world.get_resource::<GalaxySeed>().map_or(0, |seed| seed.0),
+            stellar_age: world.resource::<StellarAge>().0,
+            rivals,
+        }
+    }
+
+    // Replace the simulation state with this save
+    // Stars are matched by id: existing entities are reused, missing ones spawned
+    // and stars that are not in the save are despawned. All connections are rebuilt.
+    pub fn apply(&self, world: &mut World) -> Result<(), SaveError> {
+        if self.version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(self.version));
+        }
+        self.validate()?;
+
+        let existing: HashMap<usize, Entity> = star_ids(world)
+            .into_iter()
+            .map(|(entity, id)| (id, entity))
+            .collect();
+
+        let mut entities = HashMap::new();
+        for saved in &self.stars {
+            let entity = match existing.get(&saved.id) {
+                Some(&entity) => entity,
+                None => world.spawn(Transform::default()).id(),
+            };
+            entities.insert(saved.id, entity);
+        }
+        for (id, entity) in &existing {
+            if !entities.contains_key(id) {
+                world.entity_mut(*entity).despawn_recursive();
+            }
+        }
+
+        let lookup = |ids: &[usize]| -> Vec<Entity> { ids.iter().map(|id| entities[id]).collect() };
+
+        for saved in &self.stars {
+            let star = Star {
+                id: saved.id,
+                name: saved.name.clone(),
+                resources: saved.resources.clone(),
+                max_resources: saved.max_resources.clone(),
+                production_rate: saved.production_rate,
+                is_colonized: saved.is_colonized,
+                is_home_star: saved.is_home_star,
+                empire: saved.empire,
+                specialization: saved.specialization,
+                specialization_level: saved.specialization_level,
+                units: saved.units.clone(),
+                building_state: saved.building_state.clone(),
+                connections_from: lookup(&saved.connections_from),
+                connections_to: lookup(&saved.connections_to),
+                base_color: Color::srgba(
+                    saved.base_color[0],
+                    saved.base_color[1],
+                    saved.base_color[2],
+                    saved.base_color[3],
+                ),
+                storage_capacity: saved.storage_capacity.clone(),
+                is_storage_hub: saved.is_storage_hub,
+                population: saved.population,
+            };
+            let mut entity = world.entity_mut(entities[&saved.id]);
+            let translation = Vec3::from_array(saved.position);
+            match entity.get_mut::<Transform>() {
+                Some(mut transform) => transform.translation = translation,
+                None => {
+                    entity.insert(Transform::from_translation(translation));
+                }
+            }
+            entity.insert(star);
+        }
+
+        let old_connections: Vec<Entity> = world
+            .query_filtered::<Entity, With<Connection>>()
+            .iter(world)
+            .collect();
+        for entity in old_connections {
+            world.entity_mut(entity).despawn_recursive();
+        }
+
+        let mut connection_entities = HashMap::new();
+        for saved in &self.connections {
+            let mut connection = Connection::new(entities[&saved.from], entities[&saved.to]);
+            connection
+                .collection_timer
+                .set_elapsed(Duration::from_secs_f32(saved.timer_elapsed));
+            connection.is_collecting = saved.is_collecting;
+            connection.creation_time = saved.creation_time;
+            let entity = world.spawn(connection).id();
+            connection_entities.insert((saved.from, saved.to), entity);
+            connection_entities.insert((saved.to, saved.from), entity);
+        }
+
+        let old_cargo: Vec<Entity> = world
+            .query_filtered::<Entity, With<CargoPacket>>()
+            .iter(world)
+            .collect();
+        for entity in old_cargo {
+            world.entity_mut(entity).despawn_recursive();
+        }
+        for saved in &self.cargo {
+            let mut packet = CargoPacket::new(
+                saved.resource_type,
+                saved.amount,
+                entities[&saved.at],
+                saved.empire,
+            );
+            // A leg over a connection that is not in the save starts again from `at`
+            packet.leg = saved.leg.as_ref().and_then(|leg| {
+                Some(CargoLeg {
+                    connection: *connection_entities.get(&(saved.at, leg.to))?,
+                    to: entities[&leg.to],
+                    elapsed: leg.elapsed,
+                    duration: leg.duration,
+                })
+            });
+            world.spawn(packet);
+        }
+
+        let old_fleets: Vec<Entity> = world
+            .query_filtered::<Entity, With<Fleet>>()
+            .iter(world)
+            .collect();
+        for entity in old_fleets {
+            world.entity_mut(entity).despawn_recursive();
+        }
+        for saved in &self.fleets {
+            // A fleet whose connection is not in the save goes back to its origin
+            let connection = connection_entities
+                .get(&(saved.from, saved.target))
+                .copied()
+                .unwrap_or(Entity::PLACEHOLDER);
+            world.spawn(Fleet {
+                empire: saved.empire,
+                ships: saved.ships,
+                mission: saved.mission,
+                from: entities[&saved.from],
+                target: entities[&saved.target],
+                connection,
+                elapsed: saved.elapsed,
+                duration: saved.duration,
+            });
+        }
+
+        let constellations = self
+            .constellations
+            .iter()
+            .map(|saved| Constellation {
+                id: saved.id,
+                stars: lookup(&saved.stars),
+                color: Color::srgba(
+                    saved.color[0],
+                    saved.color[1],
+                    saved.color[2],
+                    saved.color[3],
+                ),
+                kind: saved.kind,
+                zodiac: saved.zodiac,
+                empire: saved.empire,
+            })
+            .collect();
+        let mut tracker = world.resource_mut::<ConstellationTracker>();
+        tracker.next_id = self.next_constellation_id;
+        tracker.constellations = constellations;
+
+        let mut player_resources = world.resource_mut::<PlayerResources>();
+        player_resources.resources = self.player_resources.clone();
+        player_resources.research_points = self.research_points;
+        world.resource_mut::<TechTree>().unlocked = self.unlocked_techs.iter().copied().collect();
+        *world.resource_mut::<SessionStats>() = self.stats.clone();
+        world.insert_resource(GalaxySeed(self.seed));
+        world.resource_mut::<StellarAge>().0 = self.stellar_age;
+
+        // Rivals missing from the current game get the default strategy
+        let mut rivals = world.resource_mut::<RivalEmpires>();
+        let mut current = std::mem::take(&mut rivals.rivals);
+        for saved in &self.rivals {
+            let mut rival = match current.iter().position(|rival| rival.id == saved.id) {
+                Some(index) => current.swap_remove(index),
+                None => RivalEmpire::new(saved.id, Box::new(ExpansionStrategy)),
+            };
+            rival.resources.resources = saved.resources.clone();
+            rival.resources.research_points = saved.research_points;
+            rivals.rivals.push(rival);
+        }
+        Ok(())
+    }
+
+    // Make sure star ids are unique and every star reference points at a saved
+    // star before touching the world
+    fn validate(&self) -> Result<(), SaveError> {
+        let mut known = HashSet::new();
+        for star in &self.stars {
+            if !known.insert(star.id) {
+                return Err(SaveError::DuplicateStar(star.id));
+            }
+        }
+        let references = self
+            .stars
+            .iter()
+            .flat_map(|star| star.connections_from.iter().chain(&star.connections_to))
+            .chain(
+                self.connections
+                    .iter()
+                    .flat_map(|connection| [&connection.from, &connection.to]),
+            )
+            .chain(self.constellations.iter().flat_map(|c| &c.stars))
+            .chain(self.cargo.iter().flat_map(|cargo| {
+                std::iter::once(&cargo.at).chain(cargo.leg.as_ref().map(|leg| &leg.to))
+            }))
+            .chain(
+                self.fleets
+                    .iter()
+                    .flat_map(|fleet| [&fleet.from, &fleet.target]),
+            );
+        for id in references {
+            if !known.contains(id) {
+                return Err(SaveError::UnknownStar(*id));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_ron(&self) -> Result<String, SaveError> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| SaveError::Format(err.to_string()))
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, SaveError> {
+        ron::from_str(text).map_err(|err| SaveError::Format(err.to_string()))
+    }
+}
+
+pub fn save_to_slot(world: &mut World, slot: u8) -> Result<PathBuf, SaveError> {
+    let path = world.resource::<SaveSettings>().slot_path(slot);
+    let text = SaveGame::capture(world).to_ron()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, text)?;
+    Ok(path)
+}
+
+pub fn load_from_slot(world: &mut World, slot: u8) -> Result<(), SaveError> {
+    let path = world.resource::<SaveSettings>().slot_path(slot);
+    let text = std::fs::read_to_string(path)?;
+    SaveGame::from_ron(&text)?.apply(world)
+}
+
+// Handle queued save and load requests with full world access
+pub fn handle_save_requests(world: &mut World) {
+    let saves: Vec<SaveRequest> = world
+        .resource_mut::<Events<SaveRequest>>()
+        .drain()
+        .collect();
+    for request in saves {
+        match save_to_slot(world, request.slot) {
+            Ok(path) => info!("Game saved to slot {} ({})", request.slot, path.display()),
+            Err(err) => warn!("Could not save slot {}: {}", request.slot, err),
+        }
+    }
+
+    let loads: Vec<LoadRequest> = world
+        .resource_mut::<Events<LoadRequest>>()
+        .drain()
+        .collect();
+    for request in loads {
+        match load_from_slot(world, request.slot) {
+            Ok(()) => {
+                info!("Game loaded from slot {}", request.slot);
+                world.send_event(GameLoaded { slot: request.slot });
+            }
+            Err(err) => warn!("Could not load slot {}: {}", request.slot, err),
+        }
+    }
+}
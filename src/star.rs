@@ -0,0 +1,441 @@
+//! Stars, their specializations and construction state
+
+use crate::empire::EmpireId;
+use crate::resources::ResourceType;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Specialization types for stars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Specialization {
+    None,        // Default - collects resources
+    Storage,     // Storage hub - increased resource capacity
+    Military,    // Produces warships
+    Mining,      // Produces mining ships (increases resource collection)
+    Agriculture, // Produces food and biological resources
+    Research,    // Produces scientists and technology
+    Medical,     // Produces medical units and health resources
+    Industrial,  // Refines goods and produces builders
+}
+
+impl Specialization {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Specialization::None => "Resource Extraction",
+            Specialization::Storage => "Storage Hub",
+            Specialization::Military => "Military Base",
+            Specialization::Mining => "Mining Station",
+            Specialization::Agriculture => "Agricultural Colony",
+            Specialization::Research => "Research Center",
+            Specialization::Medical => "Medical Facility",
+            Specialization::Industrial => "Industrial Complex",
+        }
+    }
+
+    pub fn build_time(&self) -> f32 {
+        match self {
+            Specialization::None => 5.0,         // 5 seconds to start extraction
+            Specialization::Storage => 10.0,     // 10 seconds
+            Specialization::Military => 20.0,    // 20 seconds
+            Specialization::Mining => 15.0,      // 15 seconds
+            Specialization::Agriculture => 12.0, // 12 seconds
+            Specialization::Research => 25.0,    // 25 seconds
+            Specialization::Medical => 15.0,     // 15 seconds
+            Specialization::Industrial => 18.0,  // 18 seconds
+        }
+    }
+
+    pub fn upgrade_time(&self, level: u8) -> f32 {
+        let base_time = self.build_time();
+        base_time * (level as f32 * 1.5) // Higher levels take longer
+    }
+
+    pub fn icon(&self) -> &'static str {
+        match self {
+            Specialization::None => "⛏️",
+            Specialization::Storage => "📦",
+            Specialization::Military => "🚀",
+            Specialization::Mining => "⚒️",
+            Specialization::Agriculture => "🌾",
+            Specialization::Research => "🔬",
+            Specialization::Medical => "🏥",
+            Specialization::Industrial => "🏭",
+        }
+    }
+
+    // Resources paid up front to switch a star to this specialization
+    // Paid in raw resources, so an empire can always build its first industry
+    pub fn construction_cost(&self) -> Vec<(ResourceType, f32)> {
+        match self {
+            Specialization::None => vec![], // Going back to extraction is free
+            Specialization::Storage => {
+                vec![(ResourceType::Iron, 40.0), (ResourceType::Silicon, 20.0)]
+            }
+            Specialization::Military => vec![
+                (ResourceType::Iron, 60.0),
+                (ResourceType::Copper, 30.0),
+                (ResourceType::Uranium, 10.0),
+            ],
+            Specialization::Mining => {
+                vec![(ResourceType::Iron, 50.0), (ResourceType::Copper, 25.0)]
+            }
+            Specialization::Agriculture => vec![
+                (ResourceType::Water, 40.0),
+                (ResourceType::Food, 20.0),
+                (ResourceType::Iron, 15.0),
+            ],
+            Specialization::Research => vec![
+                (ResourceType::Silicon, 40.0),
+                (ResourceType::Copper, 20.0),
+                (ResourceType::EnergyCrystal, 3.0),
+            ],
+            Specialization::Medical => vec![
+                (ResourceType::Water, 30.0),
+                (ResourceType::Oxygen, 30.0),
+                (ResourceType::Silicon, 15.0),
+            ],
+            Specialization::Industrial => vec![
+                (ResourceType::Iron, 70.0),
+                (ResourceType::Copper, 40.0),
+                (ResourceType::Silicon, 20.0),
+            ],
+        }
+    }
+
+    // Resources paid up front to upgrade to `target_level`, mostly refined goods
+    pub fn upgrade_cost(&self, target_level: u8) -> Vec<(ResourceType, f32)> {
+        let base = match self {
+            Specialization::None => vec![(ResourceType::Alloy, 10.0), (ResourceType::Copper, 10.0)],
+            Specialization::Storage => {
+                vec![(ResourceType::Alloy, 20.0), (ResourceType::Silicon, 10.0)]
+            }
+            Specialization::Military => {
+                vec![(ResourceType::Alloy, 30.0), (ResourceType::FuelRods, 10.0)]
+            }
+            Specialization::Mining => {
+                vec![(ResourceType::Alloy, 25.0), (ResourceType::Copper, 10.0)]
+            }
+            Specialization::Agriculture => {
+                vec![(ResourceType::Alloy, 10.0), (ResourceType::Water, 20.0)]
+            }
+            Specialization::Research => vec![
+                (ResourceType::Circuits, 20.0),
+                (ResourceType::EnergyCrystal, 2.0),
+            ],
+            Specialization::Medical => {
+                vec![(ResourceType::Circuits, 10.0), (ResourceType::Water, 20.0)]
+            }
+            Specialization::Industrial => {
+                vec![(ResourceType::Alloy, 30.0), (ResourceType::Circuits, 10.0)]
+            }
+        };
+        let multiplier = 0.5 * (target_level as f32).powf(1.5); // Higher levels cost more
+        base.into_iter()
+            .map(|(resource_type, cost)| (resource_type, cost * multiplier))
+            .collect()
+    }
+
+    // Resources paid for every batch of units, ships and machines are built from goods
+    pub fn production_cost(&self, level: u8) -> Vec<(ResourceType, f32)> {
+        let multiplier = 1.0 / (1.0 + (level - 1) as f32 * 0.2); // Higher levels are more efficient
+        match self {
+            Specialization::None => vec![],
+            Specialization::Storage => vec![
+                (ResourceType::Alloy, 4.0 * multiplier),
+                (ResourceType::Silicon, 5.0 * multiplier),
+            ],
+            Specialization::Military => vec![
+                (ResourceType::Alloy, 8.0 * multiplier),
+                (ResourceType::FuelRods, 4.0 * multiplier),
+                (ResourceType::Circuits, 3.0 * multiplier),
+            ],
+            Specialization::Mining => vec![
+                (ResourceType::Alloy, 6.0 * multiplier),
+                (ResourceType::Copper, 5.0 * multiplier),
+            ],
+            Specialization::Agriculture => vec![
+                (ResourceType::Water, 20.0 * multiplier),
+                (ResourceType::Food, 10.0 * multiplier),
+            ],
+            Specialization::Research => vec![
+                (ResourceType::Circuits, 8.0 * multiplier),
+                (ResourceType::EnergyCrystal, 2.0 * multiplier),
+            ],
+            Specialization::Medical => vec![
+                (ResourceType::Oxygen, 15.0 * multiplier),
+                (ResourceType::Water, 10.0 * multiplier),
+            ],
+            Specialization::Industrial => vec![(ResourceType::Alloy, 5.0 * multiplier)],
+        }
+    }
+}
+
+// Unit types produced by specialized stars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum UnitType {
+    Warship,
+    MiningShip,    // Raises extraction on its star and connected stars
+    Farmer,        // Grows food
+    Scientist,     // Generates research points
+    Doctor,        // Raises the population its star and connected stars sustain
+    Builder,       // Speeds up construction on its star and connected stars
+    StorageModule, // Adds capacity to its storage hub
+}
+
+impl UnitType {
+    pub const ALL: [UnitType; 7] = [
+        UnitType::Warship,
+        UnitType::MiningShip,
+        UnitType::Farmer,
+        UnitType::Scientist,
+        UnitType::Doctor,
+        UnitType::Builder,
+        UnitType::StorageModule,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnitType::Warship => "Warship",
+            UnitType::MiningShip => "Mining Ship",
+            UnitType::Farmer => "Farmer",
+            UnitType::Scientist => "Scientist",
+            UnitType::Doctor => "Doctor",
+            UnitType::Builder => "Builder",
+            UnitType::StorageModule => "Storage Module",
+        }
+    }
+}
+
+// Building state for stars
+// `paid` is what the construction cost, part of it comes back if it is cancelled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BuildingState {
+    Ready,
+    Building {
+        timer: f32,
+        total_time: f32,
+        paid: Vec<(ResourceType, f32)>,
+    },
+    Upgrading {
+        timer: f32,
+        total_time: f32,
+        paid: Vec<(ResourceType, f32)>,
+    },
+}
+
+// Calculate Fibonacci number for connection limit
+// fibonacci(0) = fibonacci(1) = 1, then 2, 3, 5, 8... Levels start at 1, so
+// the 1 is only used once and every level adds connections
+pub fn fibonacci(n: u8) -> u32 {
+    if n <= 1 {
+        return 1;
+    }
+
+    let mut a = 1u32;
+    let mut b = 2u32;
+
+    for _ in 2..n {
+        let temp = a + b;
+        a = b;
+        b = temp;
+    }
+
+    b
+}
+
+// Get maximum connections based on star level
+pub fn max_connections_for_level(level: u8) -> u32 {
+    fibonacci(level)
+}
+
+// Components
+#[derive(Component, Debug, PartialEq)]
+pub struct Star {
+    pub id: usize,
+    pub name: String,
+    pub resources: HashMap<ResourceType, f32>,
+    pub max_resources: HashMap<ResourceType, f32>,
+    pub production_rate: f32, // Resources per second
+    pub is_colonized: bool,
+    pub is_home_star: bool,
+    pub empire: EmpireId,               // Owner, only meaningful once colonized
+    pub specialization: Specialization, // None = extraction; other = specialization (stops extraction)
+    pub specialization_level: u8,       // Level (no limit, follows Fibonacci for connections)
+    pub units: HashMap<UnitType, u32>,  // Units produced if specialized, per type
+    pub building_state: BuildingState,  // Current construction/upgrade state
+    pub connections_from: Vec<Entity>,  // List of stars connected TO this star
+    pub connections_to: Vec<Entity>,    // List of stars this star connects TO
+    pub base_color: Color,              // Base color based on resources
+    pub storage_capacity: HashMap<ResourceType, f32>, // Storage capacity if it's a storage hub
+    pub is_storage_hub: bool,           // Whether this star is a storage hub
+    pub population: f32,                // Colonists living here, 0 until colonized
+}
+
+impl Star {
+    // A player colony holding `resources`, filled up to their maximum, with
+    // everything else at its starting value
+    pub fn new_colony(resources: &[(ResourceType, f32)]) -> Self {
+        let resources: HashMap<ResourceType, f32> = resources.iter().copied().collect();
+        Self {
+            id: 0,
+            name: "Colony".to_string(),
+            max_resources: resources.clone(),
+            resources,
+            production_rate: 1.0,
+            is_colonized: true,
+            is_home_star: false,
+            empire: EmpireId::PLAYER,
+            specialization: Specialization::None,
+            specialization_level: 1,
+            units: HashMap::new(),
+            building_state: BuildingState::Ready,
+            connections_from: vec![],
+            connections_to: vec![],
+            base_color: Color::WHITE,
+            storage_capacity: HashMap::new(),
+            is_storage_hub: false,
+            population: crate::units::BASE_POPULATION,
+        }
+    }
+
+    pub fn unit_count(&self, unit_type: UnitType) -> u32 {
+        *self.units.get(&unit_type).unwrap_or(&0)
+    }
+
+    pub fn add_units(&mut self, unit_type: UnitType, count: u32) {
+        *self.units.entry(unit_type).or_insert(0) += count;
+    }
+
+    pub fn is_owned_by(&self, empire: EmpireId) -> bool {
+        self.is_colonized && self.empire == empire
+    }
+
+    // Claim the star for the player and settle its first colonists
+    // Returns false if it was already colonized
+    pub fn colonize(&mut self) -> bool {
+        self.colonize_by(EmpireId::PLAYER)
+    }
+
+    pub fn colonize_by(&mut self, empire: EmpireId) -> bool {
+        if self.is_colonized {
+            return false;
+        }
+        self.is_colonized = true;
+        self.empire = empire;
+        self.population = crate::units::BASE_POPULATION;
+        true
+    }
+
+    // Give the star up: its colonists, units, buildings and connections are gone
+    pub fn abandon(&mut self) {
+        self.is_colonized = false;
+        self.population = 0.0;
+        self.units.clear();
+        self.specialization = Specialization::None;
+        self.specialization_level = 1;
+        self.building_state = BuildingState::Ready;
+        self.is_storage_hub = false;
+        self.storage_capacity.clear();
+        self.connections_from.clear();
+        self.connections_to.clear();
+    }
+
+    // Switch to a new specialization and start building it
+    // Returns false if the star already has that specialization
+    pub fn set_specialization(
+        &mut self,
+        spec: Specialization,
+        paid: Vec<(ResourceType, f32)>,
+    ) -> bool {
+        if self.specialization == spec {
+            return false;
+        }
+
+        self.specialization = spec;
+        self.specialization_level = 1; // Reset level when changing
+        self.units.clear();
+
+        // If becoming a storage hub, set up storage capacity
+        if spec == Specialization::Storage {
+            self.is_storage_hub = true;
+            let max_resources_copy = self.max_resources.clone();
+            for (resource_type, max_value) in &max_resources_copy {
+                let capacity = max_value * 10.0; // Storage hub has 10x capacity
+                self.storage_capacity.insert(*resource_type, capacity);
+            }
+        } else {
+            self.is_storage_hub = false;
+            self.storage_capacity.clear();
+        }
+
+        let build_time = spec.build_time();
+        self.building_state = BuildingState::Building {
+            timer: build_time,
+            total_time: build_time,
+            paid,
+        };
+        true
+    }
+
+    // Start upgrading to the next level (no level limit)
+    // Returns false if the star is busy building or upgrading
+    // `upgrade_time` is usually `Specialization::upgrade_time`, shortened by research
+    pub fn start_upgrade(&mut self, upgrade_time: f32, paid: Vec<(ResourceType, f32)>) -> bool {
+        if self.building_state != BuildingState::Ready {
+            return false;
+        }
+
+        self.building_state = BuildingState::Upgrading {
+            timer: upgrade_time,
+            total_time: upgrade_time,
+            paid,
+        };
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fibonacci_starts_with_two_ones() {
+        let values: Vec<u32> = (0..=8).map(fibonacci).collect();
+        assert_eq!(values, vec![1, 1, 2, 3, 5, 8, 13, 21, 34]);
+    }
+
+    #[test]
+    fn every_level_allows_more_connections() {
+        for level in 1..20 {
+            assert!(max_connections_for_level(level + 1) > max_connections_for_level(level));
+        }
+        assert_eq!(max_connections_for_level(1), 1);
+    }
+
+    #[test]
+    fn upgrade_cost_grows_with_level() {
+        for spec in [
+            Specialization::None,
+            Specialization::Storage,
+            Specialization::Military,
+        ] {
+            let total = |level| spec.upgrade_cost(level).iter().map(|(_, c)| c).sum::<f32>();
+            assert!(total(3) > total(2));
+        }
+    }
+
+    #[test]
+    fn construction_is_raw_but_upgrades_need_goods() {
+        let needs_goods = |costs: Vec<(ResourceType, f32)>| {
+            costs
+                .iter()
+                .any(|(resource_type, _)| ResourceType::GOODS.contains(resource_type))
+        };
+        assert!(!needs_goods(Specialization::Industrial.construction_cost()));
+        for spec in [Specialization::None, Specialization::Industrial] {
+            assert!(needs_goods(spec.upgrade_cost(2)));
+        }
+        assert!(needs_goods(Specialization::Military.production_cost(1)));
+    }
+}
@@ -0,0 +1,130 @@
+//! Gameplay effects of the units produced by specialized stars
+//!
+//! Support units (mining ships, builders, doctors) help their own star and every
+//! star directly connected to it. Scientists, farmers and storage modules work
+//! at the star that produced them.
+
+use crate::constellation::ConstellationTracker;
+use crate::empire::{EmpireId, RivalEmpires};
+use crate::resources::{PlayerResources, ResourceType};
+use crate::star::{Star, UnitType};
+use crate::zodiac::ZodiacPerks;
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+// Extraction bonus per mining ship in range, capped at MAX_MINING_MULTIPLIER
+pub const MINING_BONUS_PER_SHIP: f32 = 0.05;
+pub const MAX_MINING_MULTIPLIER: f32 = 2.0;
+
+// Construction speed bonus per builder in range, capped at MAX_BUILD_SPEED
+pub const BUILD_SPEED_PER_BUILDER: f32 = 0.1;
+pub const MAX_BUILD_SPEED: f32 = 3.0;
+
+// Extra capacity per resource type for every storage module at a hub
+pub const STORAGE_PER_MODULE: f32 = 50.0;
+
+// Research points per scientist per second
+pub const RESEARCH_PER_SCIENTIST: f32 = 0.2;
+
+// Food per farmer per second
+pub const FOOD_PER_FARMER: f32 = 0.1;
+
+// Population a colony sustains on its own, and extra capacity per doctor in range
+pub const BASE_POPULATION: f32 = 100.0;
+pub const POPULATION_PER_DOCTOR: f32 = 10.0;
+pub const MAX_POPULATION_FACTOR: f32 = 2.0;
+
+// Support units within one connection of a star
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnitSupport {
+    pub mining_ships: u32,
+    pub builders: u32,
+    pub doctors: u32,
+}
+
+impl UnitSupport {
+    pub fn mining_multiplier(&self) -> f32 {
+        (1.0 + self.mining_ships as f32 * MINING_BONUS_PER_SHIP).min(MAX_MINING_MULTIPLIER)
+    }
+
+    pub fn build_speed(&self) -> f32 {
+        (1.0 + self.builders as f32 * BUILD_SPEED_PER_BUILDER).min(MAX_BUILD_SPEED)
+    }
+
+    pub fn population_capacity(&self) -> f32 {
+        BASE_POPULATION + self.doctors as f32 * POPULATION_PER_DOCTOR
+    }
+}
+
+// Sum the support units of every star and its direct neighbours of the same owner
+pub fn unit_support(stars: &[(Entity, &Star)]) -> HashMap<Entity, UnitSupport> {
+    let own: HashMap<Entity, (EmpireId, UnitSupport)> = stars
+        .iter()
+        .map(|&(entity, star)| {
+            (
+                entity,
+                (
+                    star.empire,
+                    UnitSupport {
+                        mining_ships: star.unit_count(UnitType::MiningShip),
+                        builders: star.unit_count(UnitType::Builder),
+                        doctors: star.unit_count(UnitType::Doctor),
+                    },
+                ),
+            )
+        })
+        .collect();
+
+    stars
+        .iter()
+        .map(|&(entity, star)| {
+            let mut support = own[&entity].1;
+            for neighbour in star.connections_from.iter().chain(&star.connections_to) {
+                if let Some((_, other)) = own
+                    .get(neighbour)
+                    .filter(|(owner, _)| *owner == star.empire)
+                {
+                    support.mining_ships += other.mining_ships;
+                    support.builders += other.builders;
+                    support.doctors += other.doctors;
+                }
+            }
+            (entity, support)
+        })
+        .collect()
+}
+
+// Production multiplier from a colony's population relative to what it sustains on its own
+pub fn population_factor(population: f32) -> f32 {
+    (population / BASE_POPULATION).clamp(0.0, MAX_POPULATION_FACTOR)
+}
+
+// Scientists research and farmers grow food, doctors are left to `population`
+pub fn apply_unit_effects(
+    time: Res<Time>,
+    star_query: Query<&Star>,
+    mut player_resources: ResMut<PlayerResources>,
+    mut rivals: ResMut<RivalEmpires>,
+    constellation_tracker: Res<ConstellationTracker>,
+) {
+    let dt = time.delta_seconds();
+
+    for star in &star_query {
+        let perks = ZodiacPerks::new(&constellation_tracker, star.empire);
+        // Units work for the empire that owns their star
+        let Some(stockpile) = rivals.stockpile(&mut player_resources, star.empire) else {
+            continue;
+        };
+        let scientists = star.unit_count(UnitType::Scientist);
+        stockpile.research_points +=
+            scientists as f32 * RESEARCH_PER_SCIENTIST * perks.research * dt;
+
+        let farmers = star.unit_count(UnitType::Farmer);
+        if farmers > 0 {
+            stockpile.store(
+                ResourceType::Food,
+                farmers as f32 * FOOD_PER_FARMER * perks.food * dt,
+            );
+        }
+    }
+}
@@ -0,0 +1,491 @@
+//! The twelve zodiac signs, recognised from the shape of constellations
+//!
+//! Every sign is a closed outline of stars taken from its real asterism, and no
+//! sign is a plain triangle, square or regular polygon, so ordinary
+//! constellations do not complete signs by chance. A constellation completes a
+//! sign when its stars, in cycle order, trace the same outline up to rotation,
+//! scale and a little positional noise. Each sign can only be completed once,
+//! by any empire, and grants its perk to the constellation's owner for as long
+//! as the constellation stands.
+
+use crate::constellation::ConstellationTracker;
+use crate::empire::EmpireId;
+use crate::star::Star;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+// Largest RMS distance between a normalized constellation and a sign outline
+// that still counts as a match, in units of the outline's radius
+pub const MATCH_TOLERANCE: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ZodiacSign {
+    Aries,
+    Taurus,
+    Gemini,
+    Cancer,
+    Leo,
+    Virgo,
+    Libra,
+    Scorpio,
+    Sagittarius,
+    Capricorn,
+    Aquarius,
+    Pisces,
+}
+
+impl ZodiacSign {
+    pub const ALL: [ZodiacSign; 12] = [
+        ZodiacSign::Aries,
+        ZodiacSign::Taurus,
+        ZodiacSign::Gemini,
+        ZodiacSign::Cancer,
+        ZodiacSign::Leo,
+        ZodiacSign::Virgo,
+        ZodiacSign::Libra,
+        ZodiacSign::Scorpio,
+        ZodiacSign::Sagittarius,
+        ZodiacSign::Capricorn,
+        ZodiacSign::Aquarius,
+        ZodiacSign::Pisces,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ZodiacSign::Aries => "Aries",
+            ZodiacSign::Taurus => "Taurus",
+            ZodiacSign::Gemini => "Gemini",
+            ZodiacSign::Cancer => "Cancer",
+            ZodiacSign::Leo => "Leo",
+            ZodiacSign::Virgo => "Virgo",
+            ZodiacSign::Libra => "Libra",
+            ZodiacSign::Scorpio => "Scorpio",
+            ZodiacSign::Sagittarius => "Sagittarius",
+            ZodiacSign::Capricorn => "Capricorn",
+            ZodiacSign::Aquarius => "Aquarius",
+            ZodiacSign::Pisces => "Pisces",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ZodiacSign::Aries => "♈",
+            ZodiacSign::Taurus => "♉",
+            ZodiacSign::Gemini => "♊",
+            ZodiacSign::Cancer => "♋",
+            ZodiacSign::Leo => "♌",
+            ZodiacSign::Virgo => "♍",
+            ZodiacSign::Libra => "♎",
+            ZodiacSign::Scorpio => "♏",
+            ZodiacSign::Sagittarius => "♐",
+            ZodiacSign::Capricorn => "♑",
+            ZodiacSign::Aquarius => "♒",
+            ZodiacSign::Pisces => "♓",
+        }
+    }
+
+    pub fn perk_description(&self) -> &'static str {
+        match self {
+            ZodiacSign::Aries => "Construction 25% faster everywhere",
+            ZodiacSign::Taurus => "Resource extraction +25%",
+            ZodiacSign::Gemini => "+1 outbound connection per star",
+            ZodiacSign::Cancer => "Colonies sustain 20% more population",
+            ZodiacSign::Leo => "Production constellations +0.5x",
+            ZodiacSign::Virgo => "Farmers grow 50% more food",
+            ZodiacSign::Libra => "+150 storage for every resource",
+            ZodiacSign::Scorpio => "Isolated stars produce at 30% instead of 10%",
+            ZodiacSign::Sagittarius => "Supply routes count 1 hop shorter",
+            ZodiacSign::Capricorn => "Specialized stars produce 1 extra unit per cycle",
+            ZodiacSign::Aquarius => "Scientists research 50% faster",
+            ZodiacSign::Pisces => "Populations grow twice as fast",
+        }
+    }
+
+    // Outline of the sign, one point per star in cycle order, traced around the
+    // brightest stars of its constellation as seen from Earth
+    pub fn pattern(&self) -> &'static [(f32, f32)] {
+        match self {
+            // Hamal, Sheratan, Mesarthim and 41 Arietis
+            ZodiacSign::Aries => &[(0.0, 0.0), (-0.1, 0.6), (-1.2, 1.6), (-5.0, 3.1)],
+            // Aldebaran, the Hyades and the tips of the horns
+            ZodiacSign::Taurus => &[
+                (0.0, 0.0),
+                (1.3, -0.3),
+                (0.6, 0.9),
+                (-4.1, 4.2),
+                (-5.0, 1.6),
+            ],
+            // Castor and Pollux down to the feet of the twins
+            ZodiacSign::Gemini => &[
+                (0.0, 0.0),
+                (3.7, -2.1),
+                (5.0, 0.2),
+                (3.2, 1.2),
+                (-1.3, 3.8),
+                (-2.2, 2.3),
+            ],
+            // Acubens, the Aselli, Iota and Tarf
+            ZodiacSign::Cancer => &[(0.0, 0.0), (3.0, -0.8), (1.1, 2.8), (0.8, 5.0), (1.0, 1.9)],
+            // Regulus, the Sickle and the hindquarters up to Denebola
+            ZodiacSign::Leo => &[
+                (0.0, 0.0),
+                (1.5, 0.2),
+                (4.3, -0.5),
+                (3.8, 1.0),
+                (5.0, 2.1),
+                (4.0, 1.6),
+                (1.5, 1.1),
+            ],
+            // Spica, Porrima, Vindemiatrix and the head
+            ZodiacSign::Virgo => &[
+                (0.0, 0.0),
+                (0.5, -2.0),
+                (2.5, -0.2),
+                (3.6, 0.0),
+                (5.0, 0.5),
+                (1.6, 2.2),
+            ],
+            // Both claws, Gamma, Sigma and Upsilon
+            ZodiacSign::Libra => &[(0.0, 0.0), (2.1, 0.8), (2.9, 3.2), (1.3, 5.0), (0.1, 3.6)],
+            // Acrab and Antares down to the sting
+            ZodiacSign::Scorpio => &[
+                (0.0, 0.0),
+                (-0.2, -1.5),
+                (2.3, 0.7),
+                (5.0, 3.6),
+                (3.4, 2.7),
+                (4.7, 4.3),
+            ],
+            // The Teapot
+            ZodiacSign::Sagittarius => &[
+                (0.0, 0.0),
+                (3.4, -1.8),
+                (3.7, 0.0),
+                (5.0, -0.2),
+                (3.0, 1.8),
+                (0.6, 1.4),
+                (-0.4, 0.9),
+            ],
+            // Algedi, Dabih and the tail
+            ZodiacSign::Capricorn => &[
+                (0.0, 0.0),
+                (2.5, -1.4),
+                (4.8, 2.3),
+                (5.0, 3.0),
+                (1.5, 1.6),
+                (-1.5, 1.9),
+            ],
+            // Albali and Sadalsuud to the water jar and Skat
+            ZodiacSign::Aquarius => &[
+                (0.0, 0.0),
+                (-0.6, -0.9),
+                (5.0, 1.0),
+                (3.3, 1.6),
+                (1.9, 2.5),
+                (1.0, 2.5),
+                (0.1, 1.3),
+            ],
+            // The Circlet and the cord to Alrescha
+            ZodiacSign::Pisces => &[
+                (0.0, 0.0),
+                (5.0, 0.1),
+                (4.3, 0.3),
+                (3.7, 0.5),
+                (0.9, 1.5),
+                (2.2, 0.6),
+            ],
+        }
+    }
+
+    // Sign traced by `points` (star positions in cycle order), if any
+    pub fn matching(points: &[Vec2]) -> Option<ZodiacSign> {
+        ZodiacSign::ALL
+            .into_iter()
+            .filter(|sign| sign.pattern().len() == points.len())
+            .map(|sign| {
+                let pattern: Vec<Vec2> = sign
+                    .pattern()
+                    .iter()
+                    .map(|&(x, y)| Vec2::new(x, y))
+                    .collect();
+                (sign, shape_distance(points, &pattern))
+            })
+            .filter(|(_, distance)| *distance <= MATCH_TOLERANCE)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(sign, _)| sign)
+    }
+}
+
+// Center on the centroid and scale to unit RMS radius
+fn normalize(points: &[Vec2]) -> Vec<Vec2> {
+    let centroid = points.iter().copied().sum::<Vec2>() / points.len() as f32;
+    let centered: Vec<Vec2> = points.iter().map(|&p| p - centroid).collect();
+    let radius =
+        (centered.iter().map(|p| p.length_squared()).sum::<f32>() / points.len() as f32).sqrt();
+    if radius <= f32::EPSILON {
+        return centered;
+    }
+    centered.into_iter().map(|p| p / radius).collect()
+}
+
+// RMS distance between two outlines after the best rotation, starting star and
+// direction, once both are normalized
+fn shape_distance(a: &[Vec2], b: &[Vec2]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return f32::INFINITY;
+    }
+    let a = normalize(a);
+    let b = normalize(b);
+    let n = a.len();
+    let reversed: Vec<Vec2> = b.iter().rev().copied().collect();
+
+    let mut best = f32::INFINITY;
+    for candidate in [&b, &reversed] {
+        for shift in 0..n {
+            // For unit-radius shapes the best rotation leaves a mean squared
+            // error of 2 - 2 * |sum of a_i * conj(b_i)| / n
+            let (mut dot, mut cross) = (0.0, 0.0);
+            for i in 0..n {
+                let (p, q) = (a[i], candidate[(i + shift) % n]);
+                dot += p.dot(q);
+                cross += p.perp_dot(q);
+            }
+            let mean_squared = 2.0 - 2.0 * (dot * dot + cross * cross).sqrt() / n as f32;
+            best = best.min(mean_squared.max(0.0).sqrt());
+        }
+    }
+    best
+}
+
+// Sent when a constellation completes a zodiac sign
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ZodiacCompleted {
+    pub sign: ZodiacSign,
+    pub constellation_id: u32,
+}
+
+// Perks of every sign an empire currently holds, neutral when none are
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ZodiacPerks {
+    pub build_speed: f32,
+    pub production: f32,
+    pub extra_connections: u32,
+    pub population_capacity: f32,
+    pub constellation_production: f32,
+    pub food: f32,
+    pub storage: f32,
+    pub isolated_production: f32,
+    pub route_hops_saved: u32,
+    pub extra_units: u32,
+    pub research: f32,
+    pub population_growth: f32,
+}
+
+impl Default for ZodiacPerks {
+    fn default() -> Self {
+        Self {
+            build_speed: 1.0,
+            production: 1.0,
+            extra_connections: 0,
+            population_capacity: 1.0,
+            constellation_production: 0.0,
+            food: 1.0,
+            storage: 0.0,
+            isolated_production: 0.1,
+            route_hops_saved: 0,
+            extra_units: 0,
+            research: 1.0,
+            population_growth: 1.0,
+        }
+    }
+}
+
+impl ZodiacPerks {
+    pub fn new(constellation_tracker: &ConstellationTracker, empire: EmpireId) -> Self {
+        let mut perks = Self::default();
+        for sign in constellation_tracker.zodiac_signs_of(empire) {
+            match sign {
+                ZodiacSign::Aries => perks.build_speed = 1.25,
+                ZodiacSign::Taurus => perks.production = 1.25,
+                ZodiacSign::Gemini => perks.extra_connections = 1,
+                ZodiacSign::Cancer => perks.population_capacity = 1.2,
+                ZodiacSign::Leo => perks.constellation_production = 0.5,
+                ZodiacSign::Virgo => perks.food = 1.5,
+                ZodiacSign::Libra => perks.storage = 150.0,
+                ZodiacSign::Scorpio => perks.isolated_production = 0.3,
+                ZodiacSign::Sagittarius => perks.route_hops_saved = 1,
+                ZodiacSign::Capricorn => perks.extra_units = 1,
+                ZodiacSign::Aquarius => perks.research = 1.5,
+                ZodiacSign::Pisces => perks.population_growth = 2.0,
+            }
+        }
+        perks
+    }
+
+    // Leo raises the multiplier of stars in production constellations
+    pub fn constellation_bonus(&self, base_bonus: f32) -> f32 {
+        if base_bonus <= 1.0 {
+            return base_bonus;
+        }
+        base_bonus + self.constellation_production
+    }
+
+    // Route distance after Sagittarius, a star that is not a hub stays at least one hop away
+    pub fn route_distance(&self, route_distance: Option<u32>) -> Option<u32> {
+        route_distance.map(|hops| match hops {
+            0 => 0,
+            _ => hops.saturating_sub(self.route_hops_saved).max(1),
+        })
+    }
+}
+
+// Match constellations that have no sign yet against the zodiac outlines
+// Only runs when the constellations changed
+pub fn detect_zodiac_signs(
+    mut constellation_tracker: ResMut<ConstellationTracker>,
+    star_query: Query<&Transform, With<Star>>,
+    mut completed_events: EventWriter<ZodiacCompleted>,
+) {
+    if !constellation_tracker.is_changed() {
+        return;
+    }
+
+    let mut claimed: HashSet<ZodiacSign> = constellation_tracker.zodiac_signs().collect();
+    let mut matches = Vec::new();
+    for (index, constellation) in constellation_tracker.constellations.iter().enumerate() {
+        if constellation.zodiac.is_some() {
+            continue;
+        }
+        let points: Option<Vec<Vec2>> = constellation
+            .stars
+            .iter()
+            .map(|entity| {
+                star_query
+                    .get(*entity)
+                    .ok()
+                    .map(|transform| transform.translation.truncate())
+            })
+            .collect();
+        let Some(sign) = points.and_then(|points| ZodiacSign::matching(&points)) else {
+            continue;
+        };
+        if claimed.insert(sign) {
+            matches.push((index, sign));
+        }
+    }
+
+    // Only borrow mutably on a match, so an unchanged tracker stops the next run early
+    for (index, sign) in matches {
+        let constellation = &mut constellation_tracker.constellations[index];
+        constellation.zodiac = Some(sign);
+        info!(
+            "Constellation {} completed {}!",
+            constellation.id,
+            sign.name()
+        );
+        if constellation.empire.is_player() {
+            completed_events.send(ZodiacCompleted {
+                sign,
+                constellation_id: constellation.id,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(sign: ZodiacSign) -> Vec<Vec2> {
+        sign.pattern()
+            .iter()
+            .map(|&(x, y)| Vec2::new(x, y))
+            .collect()
+    }
+
+    #[test]
+    fn every_sign_matches_its_own_outline() {
+        for sign in ZodiacSign::ALL {
+            assert_eq!(ZodiacSign::matching(&outline(sign)), Some(sign));
+        }
+    }
+
+    #[test]
+    fn matching_ignores_rotation_scale_and_start() {
+        for sign in ZodiacSign::ALL {
+            let mut points: Vec<Vec2> = outline(sign)
+                .into_iter()
+                .map(|p| Vec2::from_angle(1.1).rotate(p) * 80.0 + Vec2::new(-200.0, 45.0))
+                .collect();
+            let shift = 2 % points.len();
+            points.rotate_left(shift);
+            points.reverse();
+            assert_eq!(ZodiacSign::matching(&points), Some(sign), "{}", sign.name());
+        }
+    }
+
+    #[test]
+    fn matching_tolerates_small_noise() {
+        let noise = [0.03, -0.02, 0.025, -0.03, 0.01, 0.02, -0.015];
+        for sign in ZodiacSign::ALL {
+            let points: Vec<Vec2> = outline(sign)
+                .into_iter()
+                .enumerate()
+                .map(|(i, p)| p + Vec2::new(noise[i], noise[(i + 3) % noise.len()]))
+                .collect();
+            assert_eq!(ZodiacSign::matching(&points), Some(sign), "{}", sign.name());
+        }
+    }
+
+    #[test]
+    fn signs_with_the_same_star_count_do_not_match_each_other() {
+        for a in ZodiacSign::ALL {
+            for b in ZodiacSign::ALL {
+                if a != b && a.pattern().len() == b.pattern().len() {
+                    assert!(shape_distance(&outline(a), &outline(b)) > 2.0 * MATCH_TOLERANCE);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn regular_polygons_and_rectangles_complete_no_sign() {
+        for n in 3..=8 {
+            let polygon: Vec<Vec2> = (0..n)
+                .map(|i| Vec2::from_angle(std::f32::consts::TAU * i as f32 / n as f32))
+                .collect();
+            assert_eq!(ZodiacSign::matching(&polygon), None, "{} sides", n);
+        }
+        for width in [1.0, 1.5, 2.0, 3.0] {
+            let rectangle = [
+                Vec2::ZERO,
+                Vec2::new(width, 0.0),
+                Vec2::new(width, 1.0),
+                Vec2::new(0.0, 1.0),
+            ];
+            assert_eq!(ZodiacSign::matching(&rectangle), None, "{} wide", width);
+        }
+    }
+
+    #[test]
+    fn mirrored_outline_is_not_a_match() {
+        let mirrored: Vec<Vec2> = outline(ZodiacSign::Scorpio)
+            .into_iter()
+            .map(|p| Vec2::new(-p.x, p.y))
+            .collect();
+        assert_eq!(ZodiacSign::matching(&mirrored), None);
+    }
+
+    #[test]
+    fn sagittarius_keeps_routes_at_least_one_hop() {
+        let perks = ZodiacPerks {
+            route_hops_saved: 1,
+            ..default()
+        };
+        assert_eq!(perks.route_distance(Some(0)), Some(0));
+        assert_eq!(perks.route_distance(Some(1)), Some(1));
+        assert_eq!(perks.route_distance(Some(3)), Some(2));
+        assert_eq!(perks.route_distance(None), None);
+    }
+}
@@ -0,0 +1,1375 @@
+//! Headless simulation runs: stars and connections spawned in a `World`, then
+//! driven through `SimulationPlugin` with a fixed time step
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use std::collections::HashSet;
+use std::time::Duration;
+use zodiakos::cargo::{CargoPacket, CARGO_SPEED, CONNECTION_CAPACITY, MIN_LEG_TIME};
+use zodiakos::combat::{connection_between, launch_fleet};
+use zodiakos::constellation::{ConstellationKind, DissolveReason};
+use zodiakos::construction;
+use zodiakos::empire::{ExpansionStrategy, MAX_RIVALS, THINK_INTERVAL};
+use zodiakos::events::{
+    BattleFought, ColonyAbandoned, ConnectionRemoved, ConstellationFormed, GoodsRefined,
+    StarCaptured, StarColonized, StarDepleted, StarEvolved, UnitsProduced, UpgradeCompleted,
+};
+use zodiakos::galaxy::generate_galaxy;
+use zodiakos::goals::{Defeat, GameEnded, GameOutcome, Victory, ZodiacVictoryBlocked};
+use zodiakos::lifecycle::{
+    evolution_rng, roll_evolution, Evolution, EVOLUTION_INTERVAL, REVIVAL_SHARE,
+};
+use zodiakos::options::LaunchOptions;
+use zodiakos::population::{ABANDON_POPULATION, WELL_SUPPLIED_BONUS};
+use zodiakos::prelude::*;
+use zodiakos::refining::Recipe;
+use zodiakos::save::{SaveError, SaveGame, SAVE_VERSION};
+
+const TICK: Duration = Duration::from_millis(250);
+
+fn app() -> App {
+    let mut app = App::new();
+    // Test galaxies are tiny and would be won or lost right away
+    app.add_plugins((MinimalPlugins, SimulationPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(TICK))
+        .insert_resource(GameGoals::sandbox());
+    // The first update only starts the clock
+    app.update();
+    app
+}
+
+// Keeps every `E` sent during the test, since events only live for two updates
+#[derive(Resource)]
+struct Recorded<E: Event>(Vec<E>);
+
+fn record<E: Event + Clone>(app: &mut App) {
+    app.insert_resource(Recorded::<E>(Vec::new())).add_systems(
+        Update,
+        (|mut reader: EventReader<E>, mut recorded: ResMut<Recorded<E>>| {
+            recorded.0.extend(reader.read().cloned());
+        })
+        .after(SimulationSet),
+    );
+}
+
+fn recorded<E: Event>(app: &App) -> &[E] {
+    &app.world().resource::<Recorded<E>>().0
+}
+
+fn run_for(app: &mut App, seconds: f32) {
+    let ticks = (seconds / TICK.as_secs_f32()).ceil() as u32;
+    for _ in 0..ticks {
+        app.update();
+    }
+}
+
+fn hub(capacity: f32) -> Star {
+    let mut hub = Star::new_colony(&[]);
+    hub.is_home_star = true;
+    hub.specialization = Specialization::Storage;
+    hub.is_storage_hub = true;
+    hub.storage_capacity = ResourceType::ALL.iter().map(|&r| (r, capacity)).collect();
+    hub
+}
+
+fn connect(app: &mut App, from: Entity, to: Entity) {
+    let world = app.world_mut();
+    world.get_mut::<Star>(from).unwrap().connections_to.push(to);
+    world
+        .get_mut::<Star>(to)
+        .unwrap()
+        .connections_from
+        .push(from);
+    world.spawn(Connection::new(from, to));
+}
+
+fn player_amount(app: &App, resource_type: ResourceType) -> f32 {
+    app.world()
+        .resource::<PlayerResources>()
+        .amount(resource_type)
+}
+
+fn star_amount(app: &App, entity: Entity, resource_type: ResourceType) -> f32 {
+    app.world().get::<Star>(entity).unwrap().resources[&resource_type]
+}
+
+#[test]
+fn connected_star_delivers_resources_to_the_hub() {
+    let mut app = app();
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let mine = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+    connect(&mut app, home, mine);
+
+    let before = player_amount(&app, ResourceType::Iron);
+    run_for(&mut app, 4.0 + MIN_LEG_TIME);
+
+    // Two collections of 5 * rate * 90% (one hop from the hub)
+    let collected = player_amount(&app, ResourceType::Iron) - before;
+    assert!((collected - 9.0).abs() < 0.01, "collected {}", collected);
+    assert!((star_amount(&app, mine, ResourceType::Iron) - 91.0).abs() < 0.01);
+}
+
+#[test]
+fn unconnected_star_produces_nothing() {
+    let mut app = app();
+    app.world_mut().spawn(hub(1000.0));
+    let mine = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+
+    let before = player_amount(&app, ResourceType::Iron);
+    run_for(&mut app, 6.0);
+
+    assert_eq!(player_amount(&app, ResourceType::Iron), before);
+    assert_eq!(star_amount(&app, mine, ResourceType::Iron), 100.0);
+}
+
+#[test]
+fn depleted_star_stops_collecting() {
+    let mut app = app();
+    record::<StarDepleted>(&mut app);
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let mine = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 6.0)]))
+        .id();
+    connect(&mut app, home, mine);
+
+    run_for(&mut app, 10.0);
+
+    assert!(star_amount(&app, mine, ResourceType::Iron) < 0.1);
+    let mut connections = app.world_mut().query::<&Connection>();
+    let connection = connections.single(app.world());
+    assert!(!connection.is_collecting);
+    let depleted = recorded::<StarDepleted>(&app);
+    assert_eq!(depleted.len(), 1);
+    assert_eq!(depleted[0].star, mine);
+}
+
+#[test]
+fn full_storage_leaves_resources_at_the_star() {
+    let mut app = app();
+    let home = app.world_mut().spawn(hub(25.0)).id();
+    let mine = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+    connect(&mut app, home, mine);
+
+    run_for(&mut app, 20.0);
+
+    assert!((player_amount(&app, ResourceType::Iron) - 25.0).abs() < 0.01);
+    // Starting iron was 20, so only 5 could be taken from the star
+    assert!((star_amount(&app, mine, ResourceType::Iron) - 95.0).abs() < 0.01);
+}
+
+#[test]
+fn upgrade_finishes_after_its_build_time() {
+    let mut app = app();
+    record::<UpgradeCompleted>(&mut app);
+    app.world_mut().spawn(hub(1000.0));
+    let colony = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+
+    let upgrade_time = Specialization::None.upgrade_time(1);
+    {
+        let world = app.world_mut();
+        world.resource_mut::<PlayerResources>().capacity =
+            ResourceType::ALL.iter().map(|&r| (r, 1000.0)).collect();
+        world
+            .resource_mut::<PlayerResources>()
+            .resources
+            .insert(ResourceType::Alloy, 100.0);
+        world
+            .resource_mut::<PlayerResources>()
+            .resources
+            .insert(ResourceType::Copper, 100.0);
+        world.resource_scope(|world, mut player_resources: Mut<PlayerResources>| {
+            let tech_tree = world.resource::<TechTree>().clone();
+            let mut star = world.get_mut::<Star>(colony).unwrap();
+            construction::upgrade(&mut star, &mut player_resources, &tech_tree).unwrap();
+        });
+    }
+
+    run_for(&mut app, upgrade_time - 1.0);
+    let star = app.world().get::<Star>(colony).unwrap();
+    assert_eq!(star.specialization_level, 1);
+    assert!(matches!(
+        star.building_state,
+        BuildingState::Upgrading { .. }
+    ));
+
+    run_for(&mut app, 1.5);
+    let star = app.world().get::<Star>(colony).unwrap();
+    assert_eq!(star.specialization_level, 2);
+    assert_eq!(star.building_state, BuildingState::Ready);
+    let upgraded = recorded::<UpgradeCompleted>(&app);
+    assert_eq!(upgraded.len(), 1);
+    assert_eq!((upgraded[0].star, upgraded[0].level), (colony, 2));
+}
+
+// A colony upgrading to level 2, and the player's stockpile that paid for it
+fn upgrading_colony() -> (Star, PlayerResources) {
+    let mut player_resources = PlayerResources {
+        capacity: ResourceType::ALL.iter().map(|&r| (r, 1000.0)).collect(),
+        ..PlayerResources::default()
+    };
+    player_resources
+        .resources
+        .extend([(ResourceType::Alloy, 100.0), (ResourceType::Copper, 100.0)]);
+    let mut star = Star::new_colony(&[(ResourceType::Iron, 100.0)]);
+    construction::upgrade(&mut star, &mut player_resources, &TechTree::default()).unwrap();
+    (star, player_resources)
+}
+
+#[test]
+fn cancel_refunds_half_of_what_was_paid() {
+    let (mut star, mut player_resources) = upgrading_colony();
+    let paid = Specialization::None.upgrade_cost(2);
+    // The cost of the next level no longer matters once paid
+    star.specialization_level = 5;
+
+    let refund = construction::cancel(&mut star, &mut player_resources).unwrap();
+    let expected: Vec<(ResourceType, f32)> = paid
+        .iter()
+        .map(|&(resource_type, cost)| (resource_type, cost * construction::CANCEL_REFUND))
+        .collect();
+    assert_eq!(refund, expected);
+    assert_eq!(star.building_state, BuildingState::Ready);
+}
+
+#[test]
+fn cancel_waits_for_room_to_store_the_refund() {
+    let (mut star, mut player_resources) = upgrading_colony();
+    player_resources
+        .resources
+        .insert(ResourceType::Alloy, 1000.0);
+
+    let result = construction::cancel(&mut star, &mut player_resources);
+    assert!(matches!(
+        result,
+        Err(construction::ConstructionError::NoRoomForRefund(_))
+    ));
+    assert!(matches!(
+        star.building_state,
+        BuildingState::Upgrading { .. }
+    ));
+    assert_eq!(player_resources.amount(ResourceType::Alloy), 1000.0);
+
+    player_resources.resources.insert(ResourceType::Alloy, 0.0);
+    assert!(construction::cancel(&mut star, &mut player_resources).is_ok());
+    assert!(player_resources.amount(ResourceType::Alloy) > 0.0);
+}
+
+#[test]
+fn closing_a_triangle_forms_a_constellation() {
+    let mut app = app();
+    record::<ConstellationFormed>(&mut app);
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let a = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 50.0)]))
+        .id();
+    let b = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 50.0)]))
+        .id();
+    connect(&mut app, home, a);
+    connect(&mut app, a, b);
+    app.update();
+    assert!(app
+        .world()
+        .resource::<ConstellationTracker>()
+        .constellations
+        .is_empty());
+
+    connect(&mut app, b, home);
+    app.update();
+
+    let tracker = app.world().resource::<ConstellationTracker>();
+    assert_eq!(tracker.constellations.len(), 1);
+    let mut members = tracker.constellations[0].stars.clone();
+    members.sort();
+    assert_eq!(members, vec![home, a, b]);
+    assert_eq!(recorded::<ConstellationFormed>(&app).len(), 1);
+}
+
+#[test]
+fn constellation_doubles_collection() {
+    let mut app = app();
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let a = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+    let b = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Copper, 100.0)]))
+        .id();
+    connect(&mut app, home, a);
+    connect(&mut app, a, b);
+    connect(&mut app, b, home);
+    app.update();
+
+    let before = player_amount(&app, ResourceType::Iron);
+    run_for(&mut app, 2.0 + MIN_LEG_TIME);
+
+    // 5 * rate * 90% route efficiency * 2x constellation bonus
+    let collected = player_amount(&app, ResourceType::Iron) - before;
+    assert!((collected - 9.0).abs() < 0.01, "collected {}", collected);
+}
+
+#[test]
+fn route_distance_follows_graph_changes() {
+    let mut app = app();
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let a = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 50.0)]))
+        .id();
+    let b = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 50.0)]))
+        .id();
+    connect(&mut app, home, a);
+    connect(&mut app, a, b);
+    app.update();
+
+    let route = |app: &App, entity| app.world().get::<RouteDistance>(entity).copied();
+    assert_eq!(route(&app, home), Some(RouteDistance(Some(0))));
+    assert_eq!(route(&app, b), Some(RouteDistance(Some(2))));
+
+    // Turning `a` into a hub brings `b` one hop closer
+    app.world_mut().get_mut::<Star>(a).unwrap().is_storage_hub = true;
+    app.update();
+    assert_eq!(route(&app, a), Some(RouteDistance(Some(0))));
+    assert_eq!(route(&app, b), Some(RouteDistance(Some(1))));
+}
+
+#[test]
+fn stars_join_only_one_constellation() {
+    let mut app = app();
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let stars: Vec<Entity> = (0..4)
+        .map(|_| {
+            app.world_mut()
+                .spawn(Star::new_colony(&[(ResourceType::Iron, 50.0)]))
+                .id()
+        })
+        .collect();
+    // Two triangles sharing `home`, both closed in the same frame
+    connect(&mut app, home, stars[0]);
+    connect(&mut app, stars[0], stars[1]);
+    connect(&mut app, stars[1], home);
+    connect(&mut app, home, stars[2]);
+    connect(&mut app, stars[2], stars[3]);
+    connect(&mut app, stars[3], home);
+    app.update();
+    app.update();
+
+    let tracker = app.world().resource::<ConstellationTracker>();
+    assert_eq!(tracker.constellations.len(), 1);
+    assert!(tracker.contains_star(home));
+}
+
+fn disconnect(app: &mut App, from: Entity, to: Entity) {
+    let world = app.world_mut();
+    world
+        .get_mut::<Star>(from)
+        .unwrap()
+        .connections_to
+        .retain(|&e| e != to);
+    world
+        .get_mut::<Star>(to)
+        .unwrap()
+        .connections_from
+        .retain(|&e| e != from);
+    let mut connections = world.query::<(Entity, &Connection)>();
+    let line = connections
+        .iter(world)
+        .find(|(_, c)| c.from == from && c.to == to)
+        .map(|(entity, _)| entity)
+        .unwrap();
+    world.despawn(line);
+}
+
+#[test]
+fn deleting_a_connection_dissolves_its_constellation() {
+    let mut app = app();
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let a = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 50.0)]))
+        .id();
+    let b = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 50.0)]))
+        .id();
+    connect(&mut app, home, a);
+    connect(&mut app, a, b);
+    connect(&mut app, b, home);
+    app.update();
+    assert_eq!(
+        app.world()
+            .resource::<ConstellationTracker>()
+            .constellations
+            .len(),
+        1
+    );
+
+    disconnect(&mut app, a, b);
+    app.update();
+
+    let tracker = app.world().resource::<ConstellationTracker>();
+    assert!(tracker.constellations.is_empty());
+    assert_eq!(
+        zodiakos::constellation::check_constellation_bonuses(a, tracker),
+        1.0
+    );
+    let events = app
+        .world()
+        .resource::<Events<zodiakos::constellation::ConstellationDissolved>>();
+    assert_eq!(events.len(), 1);
+    let mut reader = events.get_reader();
+    let dissolved = reader.read(events).next().unwrap();
+    assert_eq!(dissolved.reason, DissolveReason::ConnectionRemoved);
+}
+
+#[test]
+fn freed_stars_can_form_a_new_constellation() {
+    let mut app = app();
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let stars: Vec<Entity> = (0..3)
+        .map(|_| {
+            app.world_mut()
+                .spawn(Star::new_colony(&[(ResourceType::Iron, 50.0)]))
+                .id()
+        })
+        .collect();
+    // Triangle home-0-1 first, then home-1-2 which shares two of its stars
+    connect(&mut app, home, stars[0]);
+    connect(&mut app, stars[0], stars[1]);
+    connect(&mut app, stars[1], home);
+    app.update();
+    connect(&mut app, stars[1], stars[2]);
+    connect(&mut app, stars[2], home);
+    app.update();
+    assert_eq!(
+        app.world()
+            .resource::<ConstellationTracker>()
+            .constellations
+            .len(),
+        1
+    );
+
+    disconnect(&mut app, home, stars[0]);
+    app.update();
+
+    let tracker = app.world().resource::<ConstellationTracker>();
+    assert_eq!(tracker.constellations.len(), 1);
+    let mut members = tracker.constellations[0].stars.clone();
+    members.sort();
+    assert_eq!(members, vec![home, stars[1], stars[2]]);
+}
+
+// Stars at `corners`, connected in a cycle in that order
+fn ring(app: &mut App, corners: &[Vec3]) {
+    let stars: Vec<Entity> = corners
+        .iter()
+        .enumerate()
+        .map(|(i, &corner)| {
+            let resource = ResourceType::ALL[i];
+            app.world_mut()
+                .spawn((
+                    Star::new_colony(&[(resource, 50.0)]),
+                    Transform::from_translation(corner),
+                ))
+                .id()
+        })
+        .collect();
+    for (&from, &to) in stars.iter().zip(stars.iter().cycle().skip(1)) {
+        connect(app, from, to);
+    }
+    app.update();
+}
+
+#[test]
+fn square_of_stars_completes_no_sign() {
+    let mut app = app();
+    ring(
+        &mut app,
+        &[
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(100.0, 2.0, 0.0),
+            Vec3::new(98.0, 100.0, 0.0),
+            Vec3::new(0.0, 101.0, 0.0),
+        ],
+    );
+
+    let tracker = app.world().resource::<ConstellationTracker>();
+    assert_eq!(tracker.constellations.len(), 1);
+    assert_eq!(tracker.constellations[0].zodiac, None);
+}
+
+#[test]
+fn outline_of_the_crab_completes_cancer() {
+    let mut app = app();
+    let corners: Vec<Vec3> = ZodiacSign::Cancer
+        .pattern()
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, y))| Vec3::new(x * 40.0 + i as f32, y * 40.0, 0.0))
+        .collect();
+    ring(&mut app, &corners);
+
+    let tracker = app.world().resource::<ConstellationTracker>();
+    assert_eq!(tracker.constellations.len(), 1);
+    assert_eq!(tracker.constellations[0].zodiac, Some(ZodiacSign::Cancer));
+    let events = app
+        .world()
+        .resource::<Events<zodiakos::zodiac::ZodiacCompleted>>();
+    assert_eq!(events.len(), 1);
+}
+
+#[test]
+fn specialized_star_reports_produced_units() {
+    let mut app = app();
+    record::<UnitsProduced>(&mut app);
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let mut research = Star::new_colony(&[(ResourceType::Iron, 100.0)]);
+    research.specialization = Specialization::Research;
+    let research = app.world_mut().spawn(research).id();
+    connect(&mut app, home, research);
+    app.world_mut()
+        .resource_mut::<PlayerResources>()
+        .resources
+        .extend(ResourceType::ALL.map(|r| (r, 500.0)));
+
+    run_for(&mut app, 2.0);
+
+    let produced = recorded::<UnitsProduced>(&app);
+    assert_eq!(produced.len(), 1);
+    assert_eq!(produced[0].star, research);
+    assert_eq!(produced[0].unit_type, UnitType::Scientist);
+    let star = app.world().get::<Star>(research).unwrap();
+    assert_eq!(star.unit_count(UnitType::Scientist), produced[0].count);
+}
+
+fn cargo(app: &mut App) -> Vec<CargoPacket> {
+    let world = app.world_mut();
+    world.query::<&CargoPacket>().iter(world).cloned().collect()
+}
+
+#[test]
+fn cargo_takes_longer_over_longer_connections() {
+    let mut app = app();
+    let home = app
+        .world_mut()
+        .spawn((hub(1000.0), Transform::default()))
+        .id();
+    let mine = app
+        .world_mut()
+        .spawn((
+            Star::new_colony(&[(ResourceType::Iron, 100.0)]),
+            Transform::from_xyz(CARGO_SPEED * 3.0, 0.0, 0.0),
+        ))
+        .id();
+    connect(&mut app, home, mine);
+
+    let before = player_amount(&app, ResourceType::Iron);
+    run_for(&mut app, 4.0);
+
+    // The first collection left the star but is still 1 second away
+    assert_eq!(player_amount(&app, ResourceType::Iron), before);
+    assert!((star_amount(&app, mine, ResourceType::Iron) - 91.0).abs() < 0.01);
+    let packets = cargo(&mut app);
+    assert_eq!(packets.len(), 2);
+    assert!(packets.iter().all(|packet| packet.leg.unwrap().to == home));
+
+    run_for(&mut app, 1.5);
+    let collected = player_amount(&app, ResourceType::Iron) - before;
+    assert!((collected - 4.5).abs() < 0.01, "collected {}", collected);
+}
+
+#[test]
+fn cargo_hops_toward_the_nearest_hub() {
+    let mut app = app();
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let relay = app.world_mut().spawn(Star::new_colony(&[])).id();
+    let mine = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+    connect(&mut app, home, relay);
+    connect(&mut app, relay, mine);
+
+    run_for(&mut app, 2.0 + MIN_LEG_TIME);
+    let packets = cargo(&mut app);
+    assert_eq!(packets.len(), 1);
+    assert_eq!(packets[0].at, relay);
+    assert_eq!(packets[0].leg.unwrap().to, home);
+}
+
+#[test]
+fn busy_connection_holds_cargo_back() {
+    let mut app = app();
+    let home = app
+        .world_mut()
+        .spawn((hub(1000.0), Transform::default()))
+        .id();
+    // Five resource types of 4.5 per collection do not fit on one connection
+    let resources: Vec<(ResourceType, f32)> =
+        ResourceType::ALL[..5].iter().map(|&r| (r, 100.0)).collect();
+    let mine = app
+        .world_mut()
+        .spawn((
+            Star::new_colony(&resources),
+            Transform::from_xyz(600.0, 0.0, 0.0),
+        ))
+        .id();
+    connect(&mut app, home, mine);
+
+    run_for(&mut app, 2.0);
+
+    let packets = cargo(&mut app);
+    let travelling: f32 = packets
+        .iter()
+        .filter(|packet| packet.leg.is_some())
+        .map(|packet| packet.amount)
+        .sum();
+    let waiting: Vec<&CargoPacket> = packets.iter().filter(|p| p.leg.is_none()).collect();
+    assert_eq!(packets.len(), 5);
+    assert!(travelling <= CONNECTION_CAPACITY);
+    assert_eq!(waiting.len(), 1);
+    assert_eq!(waiting[0].at, mine);
+}
+
+// Hub with one iron star next to it, ready to collect
+fn mining_app(tick: Duration) -> (App, Entity) {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, SimulationPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(tick))
+        .insert_resource(GameGoals::sandbox());
+    app.update();
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let mine = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+    connect(&mut app, home, mine);
+    (app, mine)
+}
+
+#[test]
+fn results_do_not_depend_on_the_frame_rate() {
+    let (mut slow, slow_mine) = mining_app(Duration::from_millis(200));
+    let (mut fast, fast_mine) = mining_app(Duration::from_millis(10));
+    for _ in 0..50 {
+        slow.update();
+    }
+    for _ in 0..1000 {
+        fast.update();
+    }
+
+    assert_eq!(
+        player_amount(&slow, ResourceType::Iron),
+        player_amount(&fast, ResourceType::Iron)
+    );
+    assert_eq!(
+        star_amount(&slow, slow_mine, ResourceType::Iron),
+        star_amount(&fast, fast_mine, ResourceType::Iron)
+    );
+}
+
+#[test]
+fn paused_simulation_only_advances_by_single_steps() {
+    let (mut app, mine) = mining_app(TICK);
+    app.world_mut()
+        .resource_mut::<SimulationClock>()
+        .toggle_pause();
+    app.update();
+
+    run_for(&mut app, 10.0);
+    assert_eq!(star_amount(&app, mine, ResourceType::Iron), 100.0);
+
+    // The first collection is due after 2 seconds of simulated time
+    for _ in 0..8 {
+        app.world_mut().resource_mut::<SimulationClock>().step();
+        app.update();
+    }
+    assert!((star_amount(&app, mine, ResourceType::Iron) - 95.5).abs() < 0.01);
+}
+
+#[test]
+fn faster_clock_collects_sooner() {
+    let (mut app, mine) = mining_app(TICK);
+    {
+        let mut clock = app.world_mut().resource_mut::<SimulationClock>();
+        clock.faster();
+        clock.faster();
+    }
+    app.update();
+
+    // 4x speed reaches the 2 second collection in half a second
+    run_for(&mut app, 0.5);
+    assert!((star_amount(&app, mine, ResourceType::Iron) - 95.5).abs() < 0.01);
+}
+
+#[test]
+fn stockpile_target_wins_and_pauses_the_game() {
+    let mut app = app();
+    record::<GameEnded>(&mut app);
+    app.insert_resource(GameGoals {
+        victories: vec![Victory::Stockpile(vec![(ResourceType::Iron, 29.0)])],
+        ..GameGoals::sandbox()
+    });
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let mine = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+    connect(&mut app, home, mine);
+
+    // Starting iron is 20, the second delivery of 4.5 reaches the target
+    run_for(&mut app, 2.0 + MIN_LEG_TIME);
+    assert!(recorded::<GameEnded>(&app).is_empty());
+    run_for(&mut app, 2.0);
+
+    let ended = recorded::<GameEnded>(&app);
+    assert_eq!(ended.len(), 1);
+    assert!(ended[0].outcome.is_victory());
+    let stats = app.world().resource::<SessionStats>();
+    assert_eq!(stats.outcome, Some(ended[0].outcome.clone()));
+    assert!((stats.total_delivered() - 9.0).abs() < 0.01);
+    assert!(app.world().resource::<SimulationClock>().paused);
+
+    // Nothing moves once the game is over
+    let iron = star_amount(&app, mine, ResourceType::Iron);
+    run_for(&mut app, 4.0);
+    assert_eq!(star_amount(&app, mine, ResourceType::Iron), iron);
+}
+
+#[test]
+fn cutting_off_the_home_star_loses_the_game() {
+    let mut app = app();
+    record::<GameEnded>(&mut app);
+    app.insert_resource(GameGoals {
+        home_isolation: true,
+        ..GameGoals::sandbox()
+    });
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let colony = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+    connect(&mut app, home, colony);
+    run_for(&mut app, 1.0);
+    assert!(recorded::<GameEnded>(&app).is_empty());
+
+    disconnect(&mut app, home, colony);
+    app.update();
+
+    let ended = recorded::<GameEnded>(&app);
+    assert_eq!(ended.len(), 1);
+    assert_eq!(ended[0].outcome, GameOutcome::Defeat(Defeat::HomeIsolated));
+}
+
+#[test]
+fn running_out_of_food_loses_the_game() {
+    let mut app = app();
+    app.insert_resource(GameGoals {
+        life_support: true,
+        ..GameGoals::sandbox()
+    });
+    app.world_mut().spawn(hub(1000.0));
+    app.update();
+    assert_eq!(app.world().resource::<SessionStats>().outcome, None);
+
+    app.world_mut()
+        .resource_mut::<PlayerResources>()
+        .resources
+        .insert(ResourceType::Food, 0.0);
+    app.update();
+
+    assert_eq!(
+        app.world().resource::<SessionStats>().outcome,
+        Some(GameOutcome::Defeat(Defeat::LifeSupport(ResourceType::Food)))
+    );
+}
+
+#[test]
+fn rival_empire_colonizes_and_stocks_its_own_hub() {
+    let mut app = app();
+    let rival = app
+        .world_mut()
+        .resource_mut::<RivalEmpires>()
+        .add(Box::new(ExpansionStrategy));
+    record::<StarColonized>(&mut app);
+    let player_home = app
+        .world_mut()
+        .spawn((hub(1000.0), Transform::from_xyz(0.0, 0.0, 0.0)))
+        .id();
+    let mut rival_home = hub(1000.0);
+    rival_home.empire = rival;
+    let rival_home = app
+        .world_mut()
+        .spawn((rival_home, Transform::from_xyz(600.0, 0.0, 0.0)))
+        .id();
+    let mut free = Star::new_colony(&[(ResourceType::Iron, 100.0)]);
+    free.is_colonized = false;
+    let near_rival = app
+        .world_mut()
+        .spawn((free, Transform::from_xyz(550.0, 0.0, 0.0)))
+        .id();
+
+    let player_iron = player_amount(&app, ResourceType::Iron);
+    let rival_iron = |app: &App| {
+        app.world()
+            .resource::<RivalEmpires>()
+            .get(rival)
+            .unwrap()
+            .resources
+            .amount(ResourceType::Iron)
+    };
+    let before = rival_iron(&app);
+    run_for(&mut app, THINK_INTERVAL + 4.0 + MIN_LEG_TIME);
+
+    let claimed = app.world().get::<Star>(near_rival).unwrap();
+    assert!(claimed.is_owned_by(rival));
+    assert_eq!(claimed.connections_from, vec![rival_home]);
+    assert!(app
+        .world()
+        .get::<Star>(player_home)
+        .unwrap()
+        .connections_to
+        .is_empty());
+    // Rivals play silently and fill their own stockpile
+    assert!(recorded::<StarColonized>(&app).is_empty());
+    assert!(rival_iron(&app) > before);
+    assert_eq!(player_amount(&app, ResourceType::Iron), player_iron);
+}
+
+#[test]
+fn generated_galaxy_gives_every_rival_a_home() {
+    let config = GalaxyConfig {
+        rivals: 2,
+        ..GalaxyConfig::default()
+    };
+    let stars = generate_galaxy(GalaxySeed(7), &config);
+    let homes: Vec<&(Vec2, Star)> = stars.iter().filter(|(_, star)| star.is_home_star).collect();
+
+    let mut empires: Vec<EmpireId> = homes.iter().map(|(_, star)| star.empire).collect();
+    empires.sort_by_key(|empire| empire.0);
+    assert_eq!(empires, vec![EmpireId(0), EmpireId(1), EmpireId(2)]);
+    for (position, star) in &homes {
+        assert!(star.is_colonized && star.is_storage_hub);
+        if !star.empire.is_player() {
+            assert!(position.length() > config.min_distance);
+        }
+    }
+    assert_eq!(
+        stars.iter().filter(|(_, star)| star.is_colonized).count(),
+        3
+    );
+}
+
+// Every star of a generated galaxy with its position
+fn galaxy_map(seed: u64) -> Vec<(Vec2, Star)> {
+    let config = GalaxyConfig {
+        rivals: 2,
+        ..GalaxyConfig::default()
+    };
+    generate_galaxy(GalaxySeed(seed), &config)
+}
+
+#[test]
+fn too_many_rivals_are_capped_to_distinct_empires() {
+    let options = LaunchOptions::new(["--rivals=5".to_string()], "");
+    let config = GalaxyConfig::from_options(&options);
+    assert_eq!(config.rivals, MAX_RIVALS);
+
+    let mut empires: Vec<EmpireId> = generate_galaxy(GalaxySeed(3), &config)
+        .into_iter()
+        .filter(|(_, star)| star.is_home_star)
+        .map(|(_, star)| star.empire)
+        .collect();
+    empires.sort_by_key(|empire| empire.0);
+    assert_eq!(empires.len(), MAX_RIVALS + 1);
+    let names: HashSet<&str> = empires.iter().map(|empire| empire.name()).collect();
+    assert_eq!(names.len(), empires.len());
+    for rival in &empires[1..] {
+        assert_ne!(rival.name(), EmpireId::PLAYER.name());
+        assert_ne!(rival.color(), EmpireId::PLAYER.color());
+    }
+}
+
+#[test]
+fn same_seed_generates_the_same_galaxy() {
+    let map = galaxy_map(11);
+    assert!(!map.is_empty());
+    assert_eq!(map, galaxy_map(11));
+}
+
+#[test]
+fn different_seeds_generate_different_galaxies() {
+    let map = galaxy_map(11);
+    for seed in [12, 13, 1000] {
+        assert_ne!(map, galaxy_map(seed), "seed {}", seed);
+    }
+}
+
+// Send every warship at `from` to `to` the way the player's orders do
+fn send_fleet(app: &mut App, from: Entity, to: Entity, mission: Mission) {
+    let world = app.world_mut();
+    let connection =
+        connection_between(world.query::<(Entity, &Connection)>().iter(world), from, to);
+    let [mut origin, target] = world.get_many_entities_mut([from, to]).unwrap();
+    let target_star = target.get::<Star>().unwrap();
+    let fleet = launch_fleet(
+        (from, &mut origin.get_mut::<Star>().unwrap(), None),
+        (to, target_star, None),
+        connection,
+        mission,
+    )
+    .unwrap();
+    world.spawn(fleet);
+}
+
+fn warships(app: &App, entity: Entity) -> u32 {
+    app.world()
+        .get::<Star>(entity)
+        .unwrap()
+        .unit_count(UnitType::Warship)
+}
+
+fn front_line(app: &mut App, garrison: u32, defenders: u32) -> (Entity, Entity) {
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    app.world_mut()
+        .get_mut::<Star>(home)
+        .unwrap()
+        .add_units(UnitType::Warship, garrison);
+    let mut enemy = Star::new_colony(&[(ResourceType::Iron, 100.0)]);
+    enemy.empire = EmpireId(1);
+    enemy.add_units(UnitType::Warship, defenders);
+    let enemy = app.world_mut().spawn(enemy).id();
+    connect(app, home, enemy);
+    (home, enemy)
+}
+
+#[test]
+fn attack_captures_a_weaker_star() {
+    let mut app = app();
+    record::<BattleFought>(&mut app);
+    record::<StarCaptured>(&mut app);
+    let (home, enemy) = front_line(&mut app, 10, 2);
+
+    send_fleet(&mut app, home, enemy, Mission::Attack);
+    assert_eq!(warships(&app, home), 0);
+    run_for(&mut app, MIN_LEG_TIME + 0.5);
+
+    // 10 against 2 fighting as 3: sqrt(100 - 9) = 9.5 survivors
+    assert!(app
+        .world()
+        .get::<Star>(enemy)
+        .unwrap()
+        .is_owned_by(EmpireId::PLAYER));
+    assert_eq!(warships(&app, enemy), 9);
+    let battles = recorded::<BattleFought>(&app);
+    assert_eq!(battles.len(), 1);
+    assert_eq!(
+        (battles[0].attacker_losses, battles[0].defender_losses),
+        (1, 2)
+    );
+    let captured = recorded::<StarCaptured>(&app);
+    assert_eq!(captured.len(), 1);
+    assert_eq!(
+        (captured[0].from, captured[0].to),
+        (EmpireId(1), EmpireId::PLAYER)
+    );
+    assert_eq!(
+        app.world_mut().query::<&Fleet>().iter(app.world()).count(),
+        0
+    );
+}
+
+#[test]
+fn captured_capital_becomes_an_ordinary_colony() {
+    let mut app = app();
+    record::<StarCaptured>(&mut app);
+    let (home, enemy) = front_line(&mut app, 10, 2);
+    app.world_mut().get_mut::<Star>(enemy).unwrap().is_home_star = true;
+
+    send_fleet(&mut app, home, enemy, Mission::Attack);
+    run_for(&mut app, MIN_LEG_TIME + 0.5);
+
+    assert!(!app.world().get::<Star>(enemy).unwrap().is_home_star);
+    let captured = recorded::<StarCaptured>(&app);
+    assert_eq!(captured.len(), 1);
+    assert!(captured[0].capital);
+    // Only the player's own capital counts as lost
+    assert!(!app.world().resource::<SessionStats>().home_lost);
+}
+
+#[test]
+fn strong_garrison_holds_its_star() {
+    let mut app = app();
+    let (home, enemy) = front_line(&mut app, 3, 4);
+
+    send_fleet(&mut app, home, enemy, Mission::Attack);
+    run_for(&mut app, MIN_LEG_TIME + 0.5);
+
+    let enemy_star = app.world().get::<Star>(enemy).unwrap();
+    assert!(enemy_star.is_owned_by(EmpireId(1)));
+    assert_eq!(enemy_star.unit_count(UnitType::Warship), 3);
+    assert_eq!(warships(&app, home), 0);
+    assert_eq!(
+        app.world_mut().query::<&Fleet>().iter(app.world()).count(),
+        0
+    );
+}
+
+#[test]
+fn blockade_stops_collection_at_the_star() {
+    let mut app = app();
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let mine = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+    connect(&mut app, home, mine);
+    let mut enemy = Star::new_colony(&[]);
+    enemy.empire = EmpireId(1);
+    enemy.add_units(UnitType::Warship, 2);
+    let enemy = app.world_mut().spawn(enemy).id();
+    connect(&mut app, enemy, mine);
+
+    send_fleet(&mut app, enemy, mine, Mission::Blockade);
+    let before = player_amount(&app, ResourceType::Iron);
+    run_for(&mut app, 4.0 + MIN_LEG_TIME);
+
+    assert_eq!(player_amount(&app, ResourceType::Iron), before);
+    assert_eq!(star_amount(&app, mine, ResourceType::Iron), 100.0);
+    // The blockade holds the star but does not take it
+    assert!(app
+        .world()
+        .get::<Star>(mine)
+        .unwrap()
+        .is_owned_by(EmpireId::PLAYER));
+    assert_eq!(
+        app.world_mut().query::<&Fleet>().iter(app.world()).count(),
+        1
+    );
+}
+
+fn supply_life_support(app: &mut App, amount: f32) {
+    for resource_type in [
+        ResourceType::Water,
+        ResourceType::Oxygen,
+        ResourceType::Food,
+    ] {
+        app.world_mut()
+            .resource_mut::<PlayerResources>()
+            .resources
+            .insert(resource_type, amount);
+    }
+}
+
+#[test]
+fn well_supplied_colonies_produce_faster() {
+    let mut app = app();
+    supply_life_support(&mut app, 500.0);
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let mine = app
+        .world_mut()
+        .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+        .id();
+    connect(&mut app, home, mine);
+
+    let before = player_amount(&app, ResourceType::Iron);
+    run_for(&mut app, 4.0 + MIN_LEG_TIME);
+
+    // Two collections of 5 * rate * 90%, with the well supplied bonus
+    let collected = player_amount(&app, ResourceType::Iron) - before;
+    let expected = 9.0 * WELL_SUPPLIED_BONUS;
+    assert!(
+        (collected - expected).abs() < 0.01,
+        "collected {}",
+        collected
+    );
+}
+
+#[test]
+fn starving_colony_is_abandoned() {
+    let mut app = app();
+    record::<ColonyAbandoned>(&mut app);
+    record::<ConnectionRemoved>(&mut app);
+    app.world_mut()
+        .resource_mut::<PlayerResources>()
+        .resources
+        .insert(ResourceType::Water, 0.0);
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let colony = app.world_mut().spawn(Star::new_colony(&[])).id();
+    connect(&mut app, home, colony);
+
+    run_for(&mut app, 10.0);
+    let population = app.world().get::<Star>(colony).unwrap().population;
+    assert!(population < 100.0 && population > ABANDON_POPULATION);
+
+    run_for(&mut app, 60.0);
+    let colony_star = app.world().get::<Star>(colony).unwrap();
+    assert!(!colony_star.is_colonized);
+    assert_eq!(recorded::<ColonyAbandoned>(&app).len(), 1);
+    assert_eq!(recorded::<ConnectionRemoved>(&app).len(), 1);
+    assert_eq!(
+        app.world_mut()
+            .query::<&Connection>()
+            .iter(app.world())
+            .count(),
+        0
+    );
+    // The home star holds on with its last colonists
+    let home_star = app.world().get::<Star>(home).unwrap();
+    assert!(home_star.is_colonized && home_star.connections_to.is_empty());
+    assert_eq!(home_star.population, ABANDON_POPULATION);
+}
+
+#[test]
+fn depleted_star_regrows_and_collects_again() {
+    let mut app = app();
+    supply_life_support(&mut app, 500.0);
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let mut depleted = Star::new_colony(&[(ResourceType::Iron, 100.0)]);
+    depleted.resources.insert(ResourceType::Iron, 6.0);
+    let mine = app.world_mut().spawn(depleted).id();
+    connect(&mut app, home, mine);
+
+    run_for(&mut app, 10.0);
+    let mut connections = app.world_mut().query::<&Connection>();
+    assert!(!connections.single(app.world()).is_collecting);
+
+    // Iron regrows 0.1% of its maximum per second
+    run_for(&mut app, 250.0);
+    assert!(connections.single(app.world()).is_collecting);
+    assert!(star_amount(&app, mine, ResourceType::Iron) < 100.0 * REVIVAL_SHARE);
+}
+
+#[test]
+fn nova_severs_the_connections_of_its_star() {
+    let mut app = app();
+    record::<StarEvolved>(&mut app);
+    record::<ConnectionRemoved>(&mut app);
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let mut doomed = Star::new_colony(&[(ResourceType::Iron, 100.0)]);
+    doomed.id = 1;
+    let doomed = app.world_mut().spawn(doomed).id();
+    connect(&mut app, home, doomed);
+
+    // A seed whose first evolution roll turns star 1 into a nova
+    let seed = (0..)
+        .find(|&seed| roll_evolution(&mut evolution_rng(seed, 1, 1)) == Some(Evolution::Nova))
+        .unwrap();
+    app.insert_resource(GalaxySeed(seed));
+    run_for(&mut app, EVOLUTION_INTERVAL + 1.0);
+
+    let evolved = recorded::<StarEvolved>(&app);
+    assert_eq!(evolved.len(), 1);
+    assert_eq!(evolved[0].star, doomed);
+    assert_eq!(evolved[0].evolution, Evolution::Nova);
+    let mut connections = app.world_mut().query::<&Connection>();
+    assert_eq!(connections.iter(app.world()).count(), 0);
+    let removed = recorded::<ConnectionRemoved>(&app);
+    assert_eq!(removed.len(), 1);
+    assert_eq!((removed[0].from, removed[0].to), (home, doomed));
+    assert!(app
+        .world()
+        .get::<Star>(home)
+        .unwrap()
+        .connections_to
+        .is_empty());
+    let star = app.world().get::<Star>(doomed).unwrap();
+    assert!(star.resources.contains_key(&ResourceType::EnergyCrystal));
+    assert!(!star.resources.contains_key(&ResourceType::Iron));
+}
+
+#[test]
+fn industrial_star_refines_goods_for_the_hub() {
+    let mut app = app();
+    record::<GoodsRefined>(&mut app);
+    let home = app.world_mut().spawn(hub(1000.0)).id();
+    let mut industry = Star::new_colony(&[(ResourceType::Iron, 100.0)]);
+    industry.specialization = Specialization::Industrial;
+    let industry = app.world_mut().spawn(industry).id();
+    connect(&mut app, home, industry);
+    {
+        let mut player_resources = app.world_mut().resource_mut::<PlayerResources>();
+        player_resources.resources.insert(ResourceType::Iron, 100.0);
+        player_resources
+            .resources
+            .insert(ResourceType::Copper, 100.0);
+        player_resources
+            .resources
+            .insert(ResourceType::Silicon, 0.0);
+        player_resources
+            .resources
+            .insert(ResourceType::Uranium, 0.0);
+    }
+
+    run_for(&mut app, 4.0 + MIN_LEG_TIME);
+
+    // Two batches of alloy, there is no silicon or uranium for the other goods
+    let alloy = Recipe::for_output(ResourceType::Alloy).unwrap();
+    let refined = recorded::<GoodsRefined>(&app);
+    assert_eq!(refined.len(), 2);
+    assert_eq!(refined[0].star, industry);
+    assert_eq!(refined[1].resource_type, ResourceType::Alloy);
+    assert_eq!(
+        player_amount(&app, ResourceType::Iron),
+        100.0 - 2.0 * alloy.inputs[0].1
+    );
+    // The first batch came in as cargo and paid for the builders of the second cycle
+    let builders = Specialization::Industrial.production_cost(1)[0].1;
+    assert_eq!(
+        player_amount(&app, ResourceType::Alloy),
+        2.0 * alloy.amount - builders
+    );
+    let star = app.world().get::<Star>(industry).unwrap();
+    assert_eq!(star.unit_count(UnitType::Builder), 2);
+}
+
+// A small game with a constellation, cargo on its way, a fleet in flight and a rival
+fn saved_game() -> SaveGame {
+    let mut app = app();
+    app.insert_resource(GalaxySeed(42));
+    let rival = app
+        .world_mut()
+        .resource_mut::<RivalEmpires>()
+        .add(Box::new(ExpansionStrategy));
+    let (home, enemy) = front_line(&mut app, 10, 2);
+    app.world_mut().get_mut::<Star>(enemy).unwrap().empire = rival;
+    let mut stars = vec![home, enemy];
+    for _ in 0..2 {
+        stars.push(
+            app.world_mut()
+                .spawn(Star::new_colony(&[(ResourceType::Iron, 100.0)]))
+                .id(),
+        );
+    }
+    for (id, &entity) in stars.iter().enumerate() {
+        app.world_mut().get_mut::<Star>(entity).unwrap().id = id;
+    }
+    connect(&mut app, home, stars[2]);
+    connect(&mut app, stars[2], stars[3]);
+    connect(&mut app, stars[3], home);
+    run_for(&mut app, 2.0);
+    send_fleet(&mut app, home, enemy, Mission::Attack);
+
+    let saved = SaveGame::capture(app.world_mut());
+    assert_eq!(saved.stars.len(), 4);
+    assert_eq!(saved.constellations.len(), 1);
+    assert!(!saved.cargo.is_empty());
+    assert_eq!(saved.fleets.len(), 1);
+    assert_eq!(saved.rivals.len(), 1);
+    saved
+}
+
+#[test]
+fn saved_game_loads_into_a_fresh_world() {
+    let saved = saved_game();
+    let text = saved.to_ron().unwrap();
+
+    let mut app = app();
+    SaveGame::from_ron(&text)
+        .unwrap()
+        .apply(app.world_mut())
+        .unwrap();
+
+    assert_eq!(SaveGame::capture(app.world_mut()), saved);
+    assert_eq!(app.world().resource::<GalaxySeed>().0, 42);
+}
+
+#[test]
+fn save_of_another_version_is_refused() {
+    let mut saved = saved_game();
+    saved.version = SAVE_VERSION + 1;
+
+    let mut app = app();
+    let result = SaveGame::from_ron(&saved.to_ron().unwrap())
+        .unwrap()
+        .apply(app.world_mut());
+
+    assert!(matches!(result, Err(SaveError::UnsupportedVersion(v)) if v == SAVE_VERSION + 1));
+    // Nothing of the refused save reaches the world
+    assert_eq!(
+        app.world_mut().query::<&Star>().iter(app.world()).count(),
+        0
+    );
+}
+
+#[test]
+fn save_referencing_an_unknown_star_is_refused() {
+    let mut saved = saved_game();
+    saved.connections[0].to = 99;
+
+    let mut app = app();
+    let result = saved.apply(app.world_mut());
+    assert!(matches!(result, Err(SaveError::UnknownStar(99))));
+}
+
+#[test]
+fn save_with_two_stars_of_the_same_id_is_refused() {
+    let mut saved = saved_game();
+    saved.stars[1].id = saved.stars[0].id;
+
+    let mut app = app();
+    let result = saved.apply(app.world_mut());
+    assert!(matches!(result, Err(SaveError::DuplicateStar(0))));
+    assert_eq!(
+        app.world_mut().query::<&Star>().iter(app.world()).count(),
+        0
+    );
+}
+
+#[test]
+fn rival_sign_puts_the_zodiac_victory_out_of_reach() {
+    let mut app = app();
+    app.insert_resource(GameGoals {
+        victories: vec![Victory::Zodiac],
+        ..GameGoals::sandbox()
+    });
+    record::<ZodiacVictoryBlocked>(&mut app);
+    let rival = EmpireId(1);
+    app.world_mut()
+        .resource_mut::<ConstellationTracker>()
+        .constellations
+        .push(Constellation {
+            id: 0,
+            stars: vec![],
+            color: Color::WHITE,
+            kind: ConstellationKind::Triangle,
+            zodiac: Some(ZodiacSign::Aries),
+            empire: rival,
+        });
+    app.update();
+    assert_eq!(
+        recorded::<ZodiacVictoryBlocked>(&app),
+        [ZodiacVictoryBlocked {
+            held_by: Some((ZodiacSign::Aries, rival))
+        }]
+    );
+
+    app.world_mut()
+        .resource_mut::<ConstellationTracker>()
+        .constellations
+        .clear();
+    app.update();
+    assert_eq!(
+        recorded::<ZodiacVictoryBlocked>(&app)[1],
+        ZodiacVictoryBlocked { held_by: None }
+    );
+}